@@ -0,0 +1,87 @@
+use std::fmt::{self, Write};
+
+use crate::formatter::Formatter;
+
+use crate::r#type::Type;
+
+/// Defines a function argument.
+#[derive(Debug, Clone)]
+pub struct Arg {
+    /// Argument name, or a pattern such as `(x, y)`
+    name: String,
+
+    /// Argument type
+    ty: Type,
+
+    /// Whether the argument binding is `mut`
+    mutable: bool,
+
+    /// Argument attributes, e.g., `#[allow(unused)]`.
+    attributes: Vec<String>,
+}
+
+impl Arg {
+    /// Return a new argument with the provided name and type.
+    pub fn new<T>(name: impl ToString, ty: T) -> Self
+    where
+        T: Into<Type>,
+    {
+        Arg {
+            name: name.to_string(),
+            ty: ty.into(),
+            mutable: false,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the argument, or its pattern for arguments
+    /// destructured in the signature.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns the type of the argument.
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    /// Set whether the argument is bound as `mut` or not.
+    pub fn set_mut(&mut self, mutable: bool) -> &mut Self {
+        self.mutable = mutable;
+        self
+    }
+
+    /// Add an attribute to the argument.
+    ///
+    /// ```
+    /// use codegen::Arg;
+    ///
+    /// let mut arg = Arg::new("ctx", "Context");
+    ///
+    /// // add a `#[allow(unused)]` attribute
+    /// arg.attr("allow(unused)");
+    /// ```
+    pub fn attr(&mut self, attribute: impl ToString) -> &mut Self {
+        self.attributes.push(attribute.to_string());
+        self
+    }
+
+    /// Call `f` on every type used by the argument, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.ty.visit_mut(f);
+    }
+
+    /// Formats the argument using the given formatter.
+    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        for attr in &self.attributes {
+            write!(fmt, "#[{}] ", attr)?;
+        }
+
+        if self.mutable {
+            write!(fmt, "mut ")?;
+        }
+
+        write!(fmt, "{}: ", self.name)?;
+        self.ty.fmt(fmt)
+    }
+}
@@ -1,5 +1,9 @@
+use std::fmt::{self, Write};
+
 use crate::bound::Bound;
-use crate::r#type::Type;
+use crate::formatter::{fmt_bound_rhs, fmt_generics, Formatter};
+
+use crate::r#type::{param_order, Type};
 
 /// Defines an associated type.
 #[derive(Debug, Clone)]
@@ -15,3 +19,93 @@ impl AssociatedType {
         self
     }
 }
+
+/// Defines the value of an associated type in an impl block, e.g.
+/// `type Iter<'a> = std::slice::Iter<'a, T> where Self: 'a;`.
+#[derive(Debug, Clone)]
+pub struct ImplAssociatedType {
+    name: String,
+    generics: Vec<String>,
+    ty: Type,
+    bounds: Vec<Bound>,
+}
+
+impl ImplAssociatedType {
+    /// Return a new associated type called `name`, set to `ty`.
+    pub fn new<T>(name: impl ToString, ty: T) -> Self
+    where
+        T: Into<Type>,
+    {
+        ImplAssociatedType {
+            name: name.to_string(),
+            generics: Vec::new(),
+            ty: ty.into(),
+            bounds: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the associated type.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Add a generic to the associated type, e.g. `'a` for a generic
+    /// associated type such as `type Iter<'a>`.
+    pub fn generic(&mut self, name: impl ToString) -> &mut Self {
+        self.generics.push(name.to_string());
+        self
+    }
+
+    /// Add a `where` bound to the associated type.
+    pub fn bound<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.bounds.push(Bound {
+            name: name.to_string(),
+            bound: vec![ty.into()],
+        });
+        self
+    }
+
+    /// Add a `where` predicate to the associated type, e.g. `Self: 'a`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the predicate doesn't contain a `:`.
+    pub fn where_bound(&mut self, predicate: &str) -> &mut Self {
+        self.bounds.push(Bound::parse(predicate));
+        self
+    }
+
+    /// Call `f` on every type used by the associated type, including nested
+    /// ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.ty.visit_mut(f);
+        for bound in &mut self.bounds {
+            bound.visit_types_mut(f);
+        }
+    }
+
+    /// Formats the associated type using the given formatter.
+    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        write!(fmt, "type {}", self.name)?;
+        let mut generics = self.generics.clone();
+        generics.sort_by_key(|generic| param_order(generic));
+        fmt_generics(&generics, fmt)?;
+        write!(fmt, " = ")?;
+        self.ty.fmt(fmt)?;
+
+        // the `where` clause of generic associated types goes after the type
+        for (i, bound) in self.bounds.iter().enumerate() {
+            match i {
+                0 => write!(fmt, " where ")?,
+                _ => write!(fmt, ", ")?,
+            }
+            write!(fmt, "{}: ", bound.name)?;
+            fmt_bound_rhs(&bound.bound, fmt)?;
+        }
+
+        writeln!(fmt, ";")
+    }
+}
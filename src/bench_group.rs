@@ -0,0 +1,88 @@
+use std::fmt::{self, Write};
+
+use crate::formatter::Formatter;
+use crate::function::Function;
+
+use crate::r#type::Type;
+
+/// Defines a group of criterion benchmarks, formatted as the benchmark
+/// functions followed by `criterion_group!`.
+///
+/// The scope containing the group formats `criterion_main!` for all of its
+/// groups after the other items.
+#[derive(Debug, Clone)]
+pub struct BenchGroup {
+    /// Name of the group, which is the name of the generated function
+    name: String,
+
+    /// Benchmark functions
+    fns: Vec<Function>,
+}
+
+impl BenchGroup {
+    /// Return a new benchmark group with the given name, e.g. `"benches"`.
+    pub fn new(name: impl ToString) -> Self {
+        BenchGroup {
+            name: name.to_string(),
+            fns: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the group.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Push a new benchmark, returning a mutable reference to its function.
+    ///
+    /// This generates `fn bench_{name}(c: &mut Criterion)` benchmarking the
+    /// `routine` expression.
+    ///
+    /// ```
+    /// use codegen::Scope;
+    ///
+    /// let mut scope = Scope::new();
+    /// scope
+    ///     .new_bench_group("benches")
+    ///     .new_bench("serialize", "black_box(&value).serialize()")
+    ///     .doc("Benchmarks serializing a value");
+    /// ```
+    pub fn new_bench(&mut self, name: &str, routine: impl ToString) -> &mut Function {
+        let mut func = Function::new(format!("bench_{}", name));
+        func.arg("c", "&mut Criterion").line(format!(
+            "c.bench_function({:?}, |b| b.iter(|| {}));",
+            name,
+            routine.to_string()
+        ));
+
+        self.push_fn(func);
+        self.fns.last_mut().unwrap()
+    }
+
+    /// Push a benchmark function taking a `&mut Criterion`.
+    pub fn push_fn(&mut self, item: Function) -> &mut Self {
+        self.fns.push(item);
+        self
+    }
+
+    /// Call `f` on every type used by the group, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        for func in &mut self.fns {
+            func.visit_types_mut(f);
+        }
+    }
+
+    /// Formats the benchmark group using the given formatter.
+    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        for func in &self.fns {
+            func.fmt(false, fmt)?;
+            writeln!(fmt)?;
+        }
+
+        write!(fmt, "criterion_group!({}", self.name)?;
+        for func in &self.fns {
+            write!(fmt, ", {}", func.name())?;
+        }
+        writeln!(fmt, ");")
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::{self, Write};
 
-use crate::body::Body;
+use crate::body::{self, Body};
 use crate::formatter::Formatter;
 
 /// Defines a code block. This is used to define a function body.
@@ -30,6 +30,22 @@ impl Block {
         self
     }
 
+    /// Push a line to the code block, ignoring its indentation.
+    pub fn push_line(&mut self, line: &str) -> &mut Self {
+        self.push_lines(line)
+    }
+
+    /// Push lines to the code block, re-indenting them relative to the
+    /// block.
+    ///
+    /// See [`Function::push_lines`] for details.
+    ///
+    /// [`Function::push_lines`]: struct.Function.html#method.push_lines
+    pub fn push_lines(&mut self, lines: &str) -> &mut Self {
+        self.body.extend(body::dedent(lines));
+        self
+    }
+
     /// Push a nested block to this block.
     pub fn push_block(&mut self, block: Block) -> &mut Self {
         self.body.push(Body::Block(block));
@@ -54,7 +70,7 @@ impl Block {
             write!(fmt, " ")?;
         }
 
-        write!(fmt, "{{\n")?;
+        writeln!(fmt, "{{")?;
 
         fmt.indent(|fmt| {
             for b in &self.body {
@@ -70,7 +86,7 @@ impl Block {
             write!(fmt, "{}", after)?;
         }
 
-        write!(fmt, "\n")?;
+        writeln!(fmt)?;
         Ok(())
     }
 }
@@ -2,18 +2,52 @@ use std::fmt::{self, Write};
 
 use crate::block::Block;
 use crate::formatter::Formatter;
+use crate::tokens;
 
 #[derive(Debug, Clone)]
 pub enum Body {
     String(String),
     Block(Block),
+    Tokens(proc_macro2::TokenStream),
 }
 
 impl Body {
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         match &self {
-            Body::String(s) => write!(fmt, "{}\n", s),
+            Body::String(s) => writeln!(fmt, "{}", s),
             Body::Block(b) => b.fmt(fmt),
+            Body::Tokens(ts) => tokens::fmt_lines(ts.clone(), fmt),
         }
     }
 }
+
+/// Split text into body lines, see `dedent_lines`.
+pub(crate) fn dedent(text: &str) -> Vec<Body> {
+    dedent_lines(text).into_iter().map(Body::String).collect()
+}
+
+/// Split text into lines, removing the leading blank lines, the trailing
+/// blank lines, and the indentation common to all of the lines.
+pub(crate) fn dedent_lines(text: &str) -> Vec<String> {
+    let lines = text
+        .lines()
+        .skip_while(|line| line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let len = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let lines = &lines[..len];
+
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end().to_string())
+        .collect()
+}
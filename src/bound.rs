@@ -5,3 +5,81 @@ pub struct Bound {
     pub name: String,
     pub bound: Vec<Type>,
 }
+
+impl Bound {
+    /// Call `f` on every type used by the bound, including nested ones.
+    pub(crate) fn visit_types_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut Type) + ?Sized,
+    {
+        for ty in &mut self.bound {
+            ty.visit_mut(f);
+        }
+    }
+
+    /// Returns whether the bound is `Self: Sized`.
+    pub(crate) fn is_self_sized(&self) -> bool {
+        self.name == "Self" && self.bound.iter().any(|ty| ty.name() == "Sized")
+    }
+
+    /// Parse a `where` predicate such as `T: Serialize + 'static`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the predicate doesn't contain a `:`.
+    pub(crate) fn parse(predicate: &str) -> Self {
+        let (name, bounds) = split_top_level(predicate, ':')
+            .split_first()
+            .map(|(name, rest)| (*name, rest.join(":")))
+            .filter(|(_, bounds)| !bounds.is_empty())
+            .expect("where bound must be of the form `T: Bound`");
+
+        Bound {
+            name: name.trim().to_string(),
+            bound: split_top_level(&bounds, '+')
+                .into_iter()
+                .map(|ty| Type::new(ty.trim()))
+                .collect(),
+        }
+    }
+}
+
+/// Splits `s` at every `sep` that is not nested in brackets, ignoring `::`
+/// when splitting at `:`.
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let bytes = s.as_bytes();
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            '>' if i > 0 && bytes[i - 1] == b'-' => {}
+            '>' | ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ':' if sep == ':'
+                && (bytes.get(i + 1) == Some(&b':') || (i > 0 && bytes[i - 1] == b':')) => {}
+            _ if c == sep && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&s[start..]);
+    parts
+}
+
+#[test]
+fn parse_bound() {
+    let bound = Bound::parse("T: Serialize + 'static");
+    assert_eq!(bound.name, "T");
+    assert_eq!(bound.bound.len(), 2);
+    assert_eq!(bound.bound[1].name(), "'static");
+
+    let bound = Bound::parse("<I as IntoIterator>::Item: Fn(u8) -> Option<u8> + Send");
+    assert_eq!(bound.name, "<I as IntoIterator>::Item");
+    assert_eq!(bound.bound[0], Type::new("Fn(u8) -> Option<u8>"));
+    assert_eq!(bound.bound[1].name(), "Send");
+}
@@ -0,0 +1,177 @@
+use crate::block::Block;
+use crate::field::Field;
+use crate::r#enum::Enum;
+use crate::r#struct::Struct;
+use crate::receiver::Receiver;
+use crate::scope::Scope;
+
+use crate::r#type::Type;
+
+/// Generates a builder for a struct with named fields.
+///
+/// The builder is a companion `FooBuilder` struct holding an `Option` of each
+/// field, with a setter per field and a `build()` method returning
+/// `Result<Foo, BuildError>`. Required fields that weren't set are reported
+/// as `BuildError::MissingField("name")`, and fields with a
+/// [`default_value`] fall back to it.
+///
+/// ```
+/// use codegen::{BuilderGen, Scope, Struct};
+///
+/// let mut user = Struct::new("User");
+/// user.vis("pub").field("name", "String");
+/// user.new_field("admin", "bool").default_value("false");
+///
+/// let mut scope = Scope::new();
+/// scope.push_enum(BuilderGen::error_enum("BuildError"));
+/// BuilderGen::new(&user)
+///     .validate("validate_user")
+///     .generate(&mut scope);
+/// scope.push_struct(user);
+/// ```
+///
+/// [`default_value`]: struct.Field.html#method.default_value
+#[derive(Debug, Clone)]
+pub struct BuilderGen {
+    /// The struct being built
+    target: Struct,
+
+    /// Name of the builder struct
+    name: String,
+
+    /// Error type returned by `build()`
+    error: Type,
+
+    /// Paths of the validation functions called on the built value
+    validations: Vec<String>,
+}
+
+impl BuilderGen {
+    /// Return a new builder generator for `target`, which generates a
+    /// `{Target}Builder` struct returning a `BuildError`.
+    pub fn new(target: &Struct) -> Self {
+        BuilderGen {
+            target: target.clone(),
+            name: format!("{}Builder", target.ty().name()),
+            error: Type::new("BuildError"),
+            validations: Vec::new(),
+        }
+    }
+
+    /// Return an error enum called `name` to use with the generated builders.
+    ///
+    /// The enum has a single `MissingField(&'static str)` variant. Custom error
+    /// types must have the same variant, or an associated function with the
+    /// same signature.
+    pub fn error_enum(name: impl ToString) -> Enum {
+        let mut error = Enum::new(name);
+        error.vis("pub").derive("Debug");
+        error.new_variant("MissingField").tuple("&'static str");
+        error
+    }
+
+    /// Set the name of the builder struct.
+    pub fn name(&mut self, name: impl ToString) -> &mut Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set the error type returned by `build()`.
+    pub fn error<T>(&mut self, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.error = ty.into();
+        self
+    }
+
+    /// Add a validation hook, called as `hook(&value)?` on the built value
+    /// before it is returned.
+    ///
+    /// The hook must return a `Result<(), E>` where the error converts into
+    /// the error type of the builder.
+    pub fn validate(&mut self, hook: impl ToString) -> &mut Self {
+        self.validations.push(hook.to_string());
+        self
+    }
+
+    /// Push the builder struct and its impl blocks to `scope`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the target struct has tuple fields.
+    pub fn generate(&self, scope: &mut Scope) {
+        let fields: Vec<&Field> = self.target.fields().collect();
+        assert!(
+            fields.iter().all(|field| !field.name.is_empty()),
+            "builders can only be generated for structs with named fields"
+        );
+
+        let vis = self.target.visibility();
+
+        let mut builder = self.target.companion(&self.name);
+        builder.doc(format!("Builder for [`{}`].", self.target.ty().name()));
+        for field in &fields {
+            let mut ty = Type::new("Option");
+            ty.generic(&field.ty);
+            builder.new_field(&field.name, ty).default_value("None");
+        }
+
+        let mut imp = builder.new_impl();
+        for field in &fields {
+            let setter = imp.new_fn(&field.name);
+            if let Some(vis) = vis {
+                setter.vis(vis);
+            }
+            if !field.documentation.is_empty() {
+                setter.doc(&field.documentation);
+            }
+            setter
+                .receiver(Receiver::MutValue)
+                .arg(&field.name, &field.ty)
+                .ret("Self")
+                .line(format!("self.{0} = Some({0});", field.name))
+                .line("self");
+        }
+
+        let build = imp.new_fn("build");
+        if let Some(vis) = vis {
+            build.vis(vis);
+        }
+        let mut ret = Type::new("Result");
+        ret.generic(self.target.ty()).generic(&self.error);
+        build
+            .doc(format!(
+                "Builds the [`{}`], failing if a required field wasn't set.",
+                self.target.ty().name()
+            ))
+            .arg_self()
+            .ret(ret);
+
+        let error = self.error.name();
+        let mut value = Block::new(format!("let value = {}", self.target.ty().name()));
+        for field in &fields {
+            if field.value.is_empty() {
+                value.line(format!(
+                    "{0}: self.{0}.ok_or({1}::MissingField({0:?}))?,",
+                    field.name, error
+                ));
+            } else {
+                value.line(format!(
+                    "{}: self.{}.unwrap_or_else(|| {}),",
+                    field.name, field.name, field.value
+                ));
+            }
+        }
+        value.after(";");
+        build.push_block(value);
+        for hook in &self.validations {
+            build.line(format!("{}(&value)?;", hook));
+        }
+        build.line("Ok(value)");
+
+        scope.push_impl(builder.impl_default());
+        scope.push_impl(imp);
+        scope.push_struct(builder);
+    }
+}
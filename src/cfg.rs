@@ -0,0 +1,101 @@
+use std::fmt;
+
+/// Defines a configuration predicate, as used by `#[cfg(...)]`.
+///
+/// ```
+/// use codegen::Cfg;
+///
+/// let cfg = Cfg::all([Cfg::feature("blocking"), Cfg::not(Cfg::new("target_arch = \"wasm32\""))]);
+/// assert_eq!(cfg.to_string(), "all(feature = \"blocking\", not(target_arch = \"wasm32\"))");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cfg {
+    /// A predicate written as is, e.g. `unix` or `target_os = "linux"`
+    Raw(String),
+
+    /// `feature = "name"`
+    Feature(String),
+
+    /// `all(...)`
+    All(Vec<Cfg>),
+
+    /// `any(...)`
+    Any(Vec<Cfg>),
+
+    /// `not(...)`
+    Not(Box<Cfg>),
+}
+
+impl Cfg {
+    /// Return a predicate that is written as is.
+    pub fn new(predicate: impl ToString) -> Self {
+        Cfg::Raw(predicate.to_string())
+    }
+
+    /// Return a predicate that is true when the feature is enabled.
+    pub fn feature(name: impl ToString) -> Self {
+        Cfg::Feature(name.to_string())
+    }
+
+    /// Return a predicate that is true when all of the predicates are.
+    pub fn all<I>(predicates: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Cfg>,
+    {
+        Cfg::All(predicates.into_iter().map(Into::into).collect())
+    }
+
+    /// Return a predicate that is true when any of the predicates is.
+    pub fn any<I>(predicates: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Cfg>,
+    {
+        Cfg::Any(predicates.into_iter().map(Into::into).collect())
+    }
+
+    /// Return a predicate that is true when the given one is not.
+    pub fn not(predicate: impl Into<Cfg>) -> Self {
+        Cfg::Not(Box::new(predicate.into()))
+    }
+}
+
+impl fmt::Display for Cfg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, predicates) = match *self {
+            Cfg::Raw(ref predicate) => return write!(f, "{}", predicate),
+            Cfg::Feature(ref name) => return write!(f, "feature = {:?}", name),
+            Cfg::All(ref predicates) => ("all", predicates.as_slice()),
+            Cfg::Any(ref predicates) => ("any", predicates.as_slice()),
+            Cfg::Not(ref predicate) => ("not", std::slice::from_ref(&**predicate)),
+        };
+
+        write!(f, "{}(", name)?;
+        for (i, predicate) in predicates.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", predicate)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<'a> From<&'a str> for Cfg {
+    fn from(src: &'a str) -> Self {
+        Cfg::new(src)
+    }
+}
+
+impl From<String> for Cfg {
+    fn from(src: String) -> Self {
+        Cfg::Raw(src)
+    }
+}
+
+impl<'a> From<&'a Cfg> for Cfg {
+    fn from(src: &'a Cfg) -> Self {
+        src.clone()
+    }
+}
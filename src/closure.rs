@@ -0,0 +1,180 @@
+use std::fmt::{self, Write};
+
+use crate::block::Block;
+use crate::body::{self, Body};
+use crate::formatter::Formatter;
+
+use crate::r#type::Type;
+
+/// Defines a closure, e.g. `move |x: u32| -> u64 { ... }`.
+///
+/// Closures are expressions, so they are usually formatted with `to_string`
+/// and embedded in a function body or a field value:
+///
+/// ```
+/// use codegen::{Closure, Function};
+///
+/// let mut closure = Closure::new();
+/// closure.set_move(true).arg("x", "u32").line("x * 2");
+///
+/// let mut func = Function::new("spawn_doubler");
+/// func.line(format!("spawn({});", closure));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Closure {
+    /// Parameters, with an optional type
+    args: Vec<(String, Option<Type>)>,
+
+    /// Return type
+    ret: Option<Type>,
+
+    /// Body contents
+    body: Vec<Body>,
+
+    /// Whether or not the closure is `move`
+    r#move: bool,
+
+    /// Whether or not the closure is `async`
+    r#async: bool,
+}
+
+impl Closure {
+    /// Return a new closure without parameters and with an empty body.
+    pub fn new() -> Self {
+        Closure::default()
+    }
+
+    /// Add a typed parameter to the closure.
+    pub fn arg<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.args.push((name.to_string(), Some(ty.into())));
+        self
+    }
+
+    /// Add a parameter with an inferred type to the closure.
+    pub fn arg_inferred(&mut self, name: impl ToString) -> &mut Self {
+        self.args.push((name.to_string(), None));
+        self
+    }
+
+    /// Set the closure return type.
+    ///
+    /// The body is always formatted as a block when the return type is set.
+    pub fn ret<T>(&mut self, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.ret = Some(ty.into());
+        self
+    }
+
+    /// Set whether the closure is `move` or not.
+    pub fn set_move(&mut self, r#move: bool) -> &mut Self {
+        self.r#move = r#move;
+        self
+    }
+
+    /// Returns whether the closure is `move` or not
+    pub fn is_move(&self) -> bool {
+        self.r#move
+    }
+
+    /// Set whether the closure is `async` or not.
+    pub fn set_async(&mut self, r#async: bool) -> &mut Self {
+        self.r#async = r#async;
+        self
+    }
+
+    /// Returns whether the closure is `async` or not
+    pub fn is_async(&self) -> bool {
+        self.r#async
+    }
+
+    /// Push a line to the closure body.
+    pub fn line<T>(&mut self, line: T) -> &mut Self
+    where
+        T: ToString,
+    {
+        self.body.push(Body::String(line.to_string()));
+        self
+    }
+
+    /// Push lines to the closure body, re-indenting them relative to the
+    /// closure.
+    pub fn push_lines(&mut self, lines: &str) -> &mut Self {
+        self.body.extend(body::dedent(lines));
+        self
+    }
+
+    /// Push a block to the closure body.
+    pub fn push_block(&mut self, block: Block) -> &mut Self {
+        self.body.push(Body::Block(block));
+        self
+    }
+
+    /// Formats the closure using the given formatter.
+    ///
+    /// A body made of a single expression is formatted without braces, e.g.
+    /// `|x| x + 1`.
+    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        if self.r#async {
+            write!(fmt, "async ")?;
+        }
+
+        if self.r#move {
+            write!(fmt, "move ")?;
+        }
+
+        write!(fmt, "|")?;
+        for (i, (name, ty)) in self.args.iter().enumerate() {
+            if i != 0 {
+                write!(fmt, ", ")?;
+            }
+
+            write!(fmt, "{}", name)?;
+            if let Some(ty) = ty {
+                write!(fmt, ": ")?;
+                ty.fmt(fmt)?;
+            }
+        }
+        write!(fmt, "|")?;
+
+        if let Some(ref ret) = self.ret {
+            write!(fmt, " -> ")?;
+            ret.fmt(fmt)?;
+        }
+
+        match self.body.as_slice() {
+            [Body::String(expr)] if self.ret.is_none() && is_expr(expr) => {
+                write!(fmt, " {}", expr)
+            }
+            [] => write!(fmt, " {{}}"),
+            body => {
+                writeln!(fmt, " {{")?;
+                fmt.indent(|fmt| {
+                    for b in body {
+                        b.fmt(fmt)?;
+                    }
+
+                    Ok(())
+                })?;
+                write!(fmt, "}}")
+            }
+        }
+    }
+}
+
+/// Whether the line can be used as the body of a closure without braces.
+fn is_expr(line: &str) -> bool {
+    !line.contains('\n') && !line.trim_end().ends_with(';')
+}
+
+impl fmt::Display for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ret = String::new();
+        Closure::fmt(self, &mut Formatter::new(&mut ret))?;
+        f.write_str(&ret)
+    }
+}
@@ -35,3 +35,159 @@ impl Docs {
         &self.docs
     }
 }
+
+/// Column at which wrapped doc comments end.
+const WRAP_COLUMN: usize = 80;
+
+/// Formats `docs` as `///` comments, wrapping the lines that don't fit
+/// before `WRAP_COLUMN`.
+///
+/// Lines are never joined, so blank lines and lists are kept as they are.
+/// The continuation lines of list items are aligned with the text of the
+/// item, and code blocks are not wrapped.
+pub(crate) fn fmt_wrapped(docs: &str, fmt: &mut Formatter<'_>) -> fmt::Result {
+    let width = WRAP_COLUMN
+        .saturating_sub(fmt.spaces() + "/// ".len())
+        .max(40);
+    let mut in_code = false;
+
+    for line in docs.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+
+        if line.trim().is_empty() {
+            writeln!(fmt, "///")?;
+            continue;
+        }
+
+        if in_code || line.len() <= width || line.trim_start().starts_with("```") {
+            writeln!(fmt, "/// {}", line)?;
+            continue;
+        }
+
+        let text = line.trim_start();
+        let marker = list_marker(text);
+        let indent = line.len() - text.len();
+        let hanging = " ".repeat(indent + marker.len());
+
+        let mut current = format!("{}{}", &line[..indent], marker);
+        let mut empty = true;
+        for word in text[marker.len()..].split_whitespace() {
+            if !empty && current.len() + 1 + word.len() > width {
+                writeln!(fmt, "/// {}", current)?;
+                current = hanging.clone();
+                empty = true;
+            }
+
+            if !empty {
+                current.push(' ');
+            }
+            current.push_str(word);
+            empty = false;
+        }
+        writeln!(fmt, "/// {}", current)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the list marker the line starts with, e.g. `"- "` or `"1. "`.
+fn list_marker(line: &str) -> &str {
+    if line.starts_with("- ") || line.starts_with("* ") {
+        return &line[..2];
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && line[digits..].starts_with(". ") {
+        return &line[..digits + 2];
+    }
+
+    ""
+}
+
+/// The conventional sections of function documentation, e.g. `# Errors`.
+#[derive(Debug, Clone, Default)]
+pub struct DocSections {
+    args: Vec<(String, String)>,
+    returns: Option<String>,
+    errors: Option<String>,
+    panics: Option<String>,
+    safety: Option<String>,
+    examples: Vec<(String, String)>,
+}
+
+impl DocSections {
+    pub fn arg(&mut self, name: impl ToString, description: impl ToString) {
+        self.args.push((name.to_string(), description.to_string()));
+    }
+
+    pub fn returns(&mut self, description: impl ToString) {
+        self.returns = Some(description.to_string());
+    }
+
+    pub fn errors(&mut self, description: impl ToString) {
+        self.errors = Some(description.to_string());
+    }
+
+    pub fn panics(&mut self, description: impl ToString) {
+        self.panics = Some(description.to_string());
+    }
+
+    pub fn safety(&mut self, description: impl ToString) {
+        self.safety = Some(description.to_string());
+    }
+
+    pub fn example(&mut self, info: impl ToString, code: impl ToString) {
+        self.examples.push((info.to_string(), code.to_string()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+            && self.returns.is_none()
+            && self.errors.is_none()
+            && self.panics.is_none()
+            && self.safety.is_none()
+            && self.examples.is_empty()
+    }
+
+    /// Formats the summary followed by the sections, in the order used by
+    /// the standard library.
+    pub fn fmt(&self, summary: Option<&Docs>, fmt: &mut Formatter<'_>) -> fmt::Result {
+        let mut sections = Vec::new();
+
+        if let Some(summary) = summary {
+            sections.push(summary.to_str().trim_end().to_string());
+        }
+
+        if !self.args.is_empty() {
+            let mut section = "# Arguments\n".to_string();
+            for (name, description) in &self.args {
+                write!(section, "\n* `{}` - {}", name, description)?;
+            }
+            sections.push(section);
+        }
+
+        let texts = [
+            ("Returns", &self.returns),
+            ("Errors", &self.errors),
+            ("Panics", &self.panics),
+            ("Safety", &self.safety),
+        ];
+        for (heading, text) in texts.iter() {
+            if let Some(text) = text {
+                sections.push(format!("# {}\n\n{}", heading, text.trim_end()));
+            }
+        }
+
+        if !self.examples.is_empty() {
+            let mut section = "# Examples".to_string();
+            for (info, code) in &self.examples {
+                write!(section, "\n\n```{}\n{}\n```", info, code.trim_end())?;
+            }
+            sections.push(section);
+        }
+
+        Docs::new(sections.join("\n\n")).fmt(fmt)
+    }
+}
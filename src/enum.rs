@@ -1,16 +1,99 @@
 use std::fmt;
 
+use crate::block::Block;
+use crate::cfg::Cfg;
+use crate::fields::Fields;
 use crate::formatter::Formatter;
+use crate::r#impl::Impl;
+use crate::r#struct::Struct;
 use crate::type_def::TypeDef;
 use crate::variant::Variant;
 
 use crate::r#type::Type;
 
+/// Defines the case conversion of a `#[serde(rename_all = "...")]` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameRule {
+    /// `lowercase`
+    Lower,
+
+    /// `UPPERCASE`
+    Upper,
+
+    /// `PascalCase`
+    Pascal,
+
+    /// `camelCase`
+    Camel,
+
+    /// `snake_case`
+    Snake,
+
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+
+    /// `kebab-case`
+    Kebab,
+
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    /// Returns the name of the rule in serde attributes, e.g. `camelCase`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RenameRule::Lower => "lowercase",
+            RenameRule::Upper => "UPPERCASE",
+            RenameRule::Pascal => "PascalCase",
+            RenameRule::Camel => "camelCase",
+            RenameRule::Snake => "snake_case",
+            RenameRule::ScreamingSnake => "SCREAMING_SNAKE_CASE",
+            RenameRule::Kebab => "kebab-case",
+            RenameRule::ScreamingKebab => "SCREAMING-KEBAB-CASE",
+        }
+    }
+
+    /// Returns the name serde serializes the variant `name` as with this
+    /// rule, e.g. `httpError` for `HttpError` with [`RenameRule::Camel`].
+    pub fn apply_to_variant(&self, name: &str) -> String {
+        // like serde, every uppercase letter starts a word
+        let snake = || {
+            let mut snake = String::new();
+            for (i, c) in name.char_indices() {
+                if c.is_uppercase() && i != 0 {
+                    snake.push('_');
+                }
+                snake.extend(c.to_lowercase());
+            }
+            snake
+        };
+
+        match self {
+            RenameRule::Lower => name.to_ascii_lowercase(),
+            RenameRule::Upper => name.to_ascii_uppercase(),
+            RenameRule::Pascal => name.to_string(),
+            RenameRule::Camel => {
+                let mut chars = name.chars();
+                match chars.next() {
+                    Some(first) => first.to_lowercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::Snake => snake(),
+            RenameRule::ScreamingSnake => snake().to_ascii_uppercase(),
+            RenameRule::Kebab => snake().replace('_', "-"),
+            RenameRule::ScreamingKebab => snake().replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+}
+
 /// Defines an enumeration.
 #[derive(Debug, Clone)]
 pub struct Enum {
     type_def: TypeDef,
     variants: Vec<Variant>,
+    rename_all: Option<RenameRule>,
 }
 
 impl Enum {
@@ -19,6 +102,60 @@ impl Enum {
         Enum {
             type_def: TypeDef::new(name),
             variants: vec![],
+            rename_all: None,
+        }
+    }
+
+    /// Return an enum with a fieldless variant for each of `variants`.
+    ///
+    /// Variants are given either as a name, or as a `(name, docs)` pair.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let color = Enum::from_names("Color", ["Red", "Green", "Blue"]);
+    /// assert_eq!(color.len(), 3);
+    ///
+    /// let level = Enum::from_names("Level", [("Low", "Low priority"), ("High", "High priority")]);
+    /// assert_eq!(level.len(), 2);
+    /// ```
+    pub fn from_names<I>(name: impl ToString, variants: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Variant>,
+    {
+        Enum {
+            type_def: TypeDef::new(name),
+            variants: variants.into_iter().map(Into::into).collect(),
+            rename_all: None,
+        }
+    }
+
+    /// Return an enum definition from an enum parsed by `syn`.
+    ///
+    /// The visibility, generics, `where` clause, docs, derives, `repr` hints
+    /// and variants are kept, so new variants can be pushed before the enum
+    /// is formatted again. Other attributes are kept verbatim.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let item: syn::ItemEnum = syn::parse_str("
+    ///     #[derive(Debug)]
+    ///     pub enum Status {
+    ///         Active,
+    ///         Suspended { reason: String },
+    ///     }
+    /// ").unwrap();
+    ///
+    /// let mut status = Enum::from_syn(&item);
+    /// status.new_variant("Deleted");
+    /// ```
+    pub fn from_syn(item: &syn::ItemEnum) -> Self {
+        Enum {
+            type_def: TypeDef::from_syn(&item.ident, &item.vis, &item.attrs, &item.generics),
+            variants: item.variants.iter().map(Variant::from_syn).collect(),
+            rename_all: None,
         }
     }
 
@@ -35,7 +172,16 @@ impl Enum {
 
     /// Add a generic to the enum.
     pub fn generic(&mut self, name: impl ToString) -> &mut Self {
-        self.type_def.ty.generic(name);
+        self.type_def.ty.generic(Type::new(name));
+        self
+    }
+
+    /// Add a generic with a default type to the enum, e.g. `T = String`.
+    pub fn generic_with_default<T>(&mut self, name: impl ToString, default: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.type_def.generic_with_default(name, default);
         self
     }
 
@@ -54,24 +200,99 @@ impl Enum {
         self
     }
 
+    /// Mark the enum as `#[deprecated]`, with an optional version and note.
+    pub fn deprecated(&mut self, since: Option<&str>, note: Option<&str>) -> &mut Self {
+        self.type_def.deprecated(since, note);
+        self
+    }
+
+    /// Add a `#[must_use]` attribute to the enum, with an optional message.
+    ///
+    /// The attribute is formatted after the derives, `repr` and
+    /// `#[deprecated]`, and before the other attributes.
+    pub fn must_use(&mut self, message: Option<&str>) -> &mut Self {
+        self.type_def.must_use(message);
+        self
+    }
+
     /// Add a new type that the struct should derive.
+    ///
+    /// Derives are formatted sorted by name in a single `#[derive(...)]`,
+    /// and can be path-qualified, e.g. `serde::Serialize`. Derives are
+    /// deduplicated by their last path segment, so deriving `Serialize` and
+    /// `serde::Serialize` only formats `Serialize`.
     pub fn derive(&mut self, name: impl ToString) -> &mut Self {
         self.type_def.derive(name);
         self
     }
 
+    /// Derive the types only when the configuration predicate holds, using
+    /// `#[cfg_attr(...)]`.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let mut model = Enum::new("Model");
+    ///
+    /// // add `#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]`
+    /// model.derive_when("feature = \"serde\"", ["Serialize", "Deserialize"]);
+    /// ```
+    pub fn derive_when<I>(&mut self, predicate: impl Into<Cfg>, derives: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+    {
+        self.type_def.derive_when(predicate, derives);
+        self
+    }
+
     /// Specify lint attribute to supress a warning or error.
     pub fn allow(&mut self, allow: impl ToString) -> &mut Self {
         self.type_def.allow(allow);
         self
     }
 
-    /// Specify representation.
+    /// Set whether the enum is `#[non_exhaustive]` or not, so it can get
+    /// new variants without breaking downstream crates.
+    pub fn set_non_exhaustive(&mut self, non_exhaustive: bool) -> &mut Self {
+        self.type_def.set_non_exhaustive(non_exhaustive);
+        self
+    }
+
+    /// Returns whether the enum is `#[non_exhaustive]` or not
+    pub fn is_non_exhaustive(&self) -> bool {
+        self.type_def.is_non_exhaustive()
+    }
+
+    /// Add a representation hint, e.g. `"C"`.
+    ///
+    /// Several hints are combined in a single `#[repr(...)]`.
+    ///
+    /// # Panics
+    ///
+    /// Formatting panics if the hints can't be combined, e.g. `transparent`
+    /// with any other hint.
     pub fn repr(&mut self, repr: impl ToString) -> &mut Self {
         self.type_def.repr(repr);
         self
     }
 
+    /// Add a `repr(align(n))` hint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    pub fn repr_align(&mut self, align: usize) -> &mut Self {
+        self.type_def.repr_align(align);
+        self
+    }
+
+    /// Add a `repr(transparent)` hint.
+    pub fn repr_transparent(&mut self) -> &mut Self {
+        self.type_def.repr("transparent");
+        self
+    }
+
     /// Add an arbitrary attribute.
     pub fn attr(&mut self, attribute: impl ToString) -> &mut Self {
         self.type_def.attr(attribute.to_string());
@@ -102,8 +323,664 @@ impl Enum {
         self
     }
 
+    /// Returns the number of variants of the enum.
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    /// Returns `true` if the enum has no variants.
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    /// Returns an iterator over the variants of the enum, in order.
+    pub fn iter_variants(&self) -> impl Iterator<Item = &Variant> {
+        self.variants.iter()
+    }
+
+    /// Returns the variants of the enum, in order.
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    /// Returns a mutable reference to the variant called `name`, if any.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let mut color = Enum::from_names("Color", ["Red", "Green"]);
+    /// color.variant_mut("Red").unwrap().discriminant(1);
+    /// ```
+    pub fn variant_mut(&mut self, name: &str) -> Option<&mut Variant> {
+        self.variants
+            .iter_mut()
+            .find(|variant| variant.name() == name)
+    }
+
+    /// Remove the variant called `name`, returning it if it exists.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let mut color = Enum::from_names("Color", ["Red", "Green", "Grey"]);
+    /// color.new_variant("Gray").deprecated(Some("1.2.0"), None);
+    ///
+    /// // drop the deprecated values
+    /// let deprecated: Vec<_> = color
+    ///     .variants()
+    ///     .iter()
+    ///     .filter(|variant| variant.is_deprecated())
+    ///     .map(|variant| variant.name().clone())
+    ///     .collect();
+    /// for name in &deprecated {
+    ///     color.remove_variant(name);
+    /// }
+    /// assert_eq!(color.len(), 3);
+    /// ```
+    pub fn remove_variant(&mut self, name: &str) -> Option<Variant> {
+        let index = self
+            .variants
+            .iter()
+            .position(|variant| variant.name() == name)?;
+        Some(self.variants.remove(index))
+    }
+
+    /// Return an impl block with accessors for each variant: an
+    /// `is_variant(&self) -> bool` method, and for variants with fields
+    /// `as_variant(&self) -> Option<&T>` and `into_variant(self) -> Option<T>`.
+    ///
+    /// Method names use the snake case name of the variant. Variants with
+    /// several fields return a tuple of them, and the accessors of variants
+    /// gated with [`Variant::cfg`] are gated the same way.
+    ///
+    /// ```
+    /// use codegen::{Enum, Scope};
+    ///
+    /// let mut shape = Enum::new("Shape");
+    /// shape.new_variant("Circle").tuple("f64");
+    /// shape.new_variant("Rect").named("width", "f64").named("height", "f64");
+    /// shape.new_variant("Empty");
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impl(shape.generate_accessors());
+    /// scope.push_enum(shape);
+    /// ```
+    ///
+    /// [`Variant::cfg`]: struct.Variant.html#method.cfg
+    pub fn generate_accessors(&self) -> Impl {
+        let name = self.type_def.ty.name();
+        let mut imp = self.type_def.new_impl();
+
+        for variant in &self.variants {
+            let method = snake_case(variant.name());
+            let fields = variant.fields().iter().collect::<Vec<_>>();
+
+            let is = imp.new_fn(format!("is_{}", method));
+            if let Some(vis) = self.type_def.visibility() {
+                is.vis(vis);
+            }
+            for cfg in variant.cfgs() {
+                is.cfg(cfg);
+            }
+            is.doc(format!(
+                "Returns `true` if the value is a [`{0}::{1}`].",
+                name,
+                variant.name()
+            ))
+            .arg_ref_self()
+            .ret("bool")
+            .line(format!("matches!(self, {})", variant.pattern(name)));
+
+            if fields.is_empty() {
+                continue;
+            }
+
+            let pattern = variant.binding_pattern(name);
+            let tuple = |items: Vec<String>| match items.len() {
+                1 => items.into_iter().next().unwrap(),
+                _ => format!("({})", items.join(", ")),
+            };
+            let value = tuple(variant.bindings());
+
+            let accessors = [
+                ("as", "a reference to the fields", true),
+                ("into", "the fields", false),
+            ];
+            for (prefix, returned, by_ref) in accessors {
+                let types = fields
+                    .iter()
+                    .map(|field| {
+                        if by_ref {
+                            format!("&{}", field.ty)
+                        } else {
+                            field.ty.to_string()
+                        }
+                    })
+                    .collect();
+
+                let mut arms = Block::new("match self");
+                arms.line(format!("{} => Some({}),", pattern, value));
+                if self.variants.len() > 1 {
+                    arms.line("_ => None,");
+                }
+
+                let func = imp.new_fn(format!("{}_{}", prefix, method));
+                if let Some(vis) = self.type_def.visibility() {
+                    func.vis(vis);
+                }
+                for cfg in variant.cfgs() {
+                    func.cfg(cfg);
+                }
+                func.doc(format!(
+                    "Returns {0} of the value if it is a [`{1}::{2}`].",
+                    returned,
+                    name,
+                    variant.name()
+                ));
+                if by_ref {
+                    func.arg_ref_self();
+                } else {
+                    func.arg_self();
+                }
+                func.ret(format!("Option<{}>", tuple(types)))
+                    .push_block(arms);
+            }
+        }
+
+        imp
+    }
+
+    /// Return a `From` impl for each tuple variant with a single field, e.g. `impl From<io::Error> for Error`.
+    ///
+    /// Variants gated with [`Variant::cfg`] get their impl gated the same
+    /// way. Field types shared by several variants are skipped, since their
+    /// conversion would be ambiguous.
+    ///
+    /// ```
+    /// use codegen::{Enum, Scope};
+    ///
+    /// let mut error = Enum::new("Error");
+    /// error.new_variant("Io").tuple("std::io::Error");
+    /// error.new_variant("Parse").tuple("ParseError");
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impls(error.generate_from_impls());
+    /// scope.push_enum(error);
+    /// ```
+    ///
+    /// [`Variant::cfg`]: struct.Variant.html#method.cfg
+    pub fn generate_from_impls(&self) -> Vec<Impl> {
+        let name = self.type_def.ty.name();
+        let sources: Vec<(&Variant, String)> = self
+            .variants
+            .iter()
+            .filter_map(|variant| {
+                let mut fields = variant.fields().iter();
+                match (fields.next(), fields.next()) {
+                    (Some(field), None) if field.name.is_empty() => {
+                        Some((variant, field.ty.to_string()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+
+        let mut impls = Vec::new();
+        for (variant, ty) in &sources {
+            if sources.iter().filter(|(_, other)| other == ty).count() > 1 {
+                continue;
+            }
+
+            let mut imp = self.type_def.new_impl();
+            for cfg in variant.cfgs() {
+                imp.cfg(cfg);
+            }
+            imp.impl_trait(format!("From<{}>", ty))
+                .new_fn("from")
+                .arg("value", ty)
+                .ret("Self")
+                .line(format!("{}::{}(value)", name, variant.name()));
+            impls.push(imp);
+        }
+
+        impls
+    }
+
+    /// Return `Display` and `FromStr` impls, converting each variant to and
+    /// from the string returned by `string`.
+    ///
+    /// Parsing fails with a `Parse{Enum}Error` holding the unknown string,
+    /// which is returned along with the impls, including its `Display` and
+    /// `Error` impls.
+    ///
+    /// ```
+    /// use codegen::{Enum, Scope};
+    ///
+    /// let mut level = Enum::new("Level");
+    /// level.vis("pub");
+    /// level.new_variant("Debug");
+    /// level.new_variant("Info");
+    ///
+    /// let (error, impls) = level.generate_string_impls(|variant| variant.name().to_lowercase());
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_enum(level);
+    /// scope.push_struct(error);
+    /// scope.push_impls(impls);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a variant has fields.
+    pub fn generate_string_impls<F>(&self, string: F) -> (Struct, Vec<Impl>)
+    where
+        F: Fn(&Variant) -> String,
+    {
+        assert!(
+            self.variants.iter().all(|variant| !variant.has_fields()),
+            "string impls can only be generated for enums without fields"
+        );
+
+        let name = self.type_def.ty.name();
+        let error = format!("Parse{}Error", name);
+
+        let mut impls = Vec::new();
+
+        let mut display = self.type_def.new_impl();
+        display
+            .impl_trait("std::fmt::Display")
+            .new_fn("fmt")
+            .arg_ref_self()
+            .arg("f", "&mut std::fmt::Formatter<'_>")
+            .ret("std::fmt::Result")
+            .push_block(self.match_variants(
+                "self",
+                |variant| format!("f.write_str({:?})", string(variant)),
+                None,
+            ));
+        impls.push(display);
+
+        let mut arms = Block::new("match s");
+        for variant in &self.variants {
+            for cfg in variant.cfg_lines() {
+                arms.line(cfg);
+            }
+            arms.line(format!(
+                "{:?} => Ok({}::{}),",
+                string(variant),
+                name,
+                variant.name()
+            ));
+        }
+        arms.line(format!("_ => Err({}(s.to_string())),", error));
+
+        let mut from_str = self.type_def.new_impl();
+        from_str
+            .impl_trait("std::str::FromStr")
+            .associate_type("Err", &error)
+            .new_fn("from_str")
+            .arg("s", "&str")
+            .ret("Result<Self, Self::Err>")
+            .push_block(arms);
+        impls.push(from_str);
+
+        let mut error_struct = Struct::new(&error);
+        if let Some(vis) = self.type_def.visibility() {
+            error_struct.vis(vis);
+        }
+        error_struct
+            .doc(format!(
+                "The error returned when parsing a [`{}`] fails.",
+                name
+            ))
+            .derive("Debug")
+            .derive("Clone")
+            .derive("PartialEq")
+            .derive("Eq")
+            .tuple_field(None, "String");
+
+        let mut error_display = Impl::new(&error);
+        error_display
+            .impl_trait("std::fmt::Display")
+            .new_fn("fmt")
+            .arg_ref_self()
+            .arg("f", "&mut std::fmt::Formatter<'_>")
+            .ret("std::fmt::Result")
+            .line(format!("write!(f, \"unknown {}: {{:?}}\", self.0)", name));
+
+        let mut error_impl = Impl::new(&error);
+        error_impl.impl_trait("std::error::Error");
+
+        impls.push(error_display);
+        impls.push(error_impl);
+
+        (error_struct, impls)
+    }
+
+    /// Set the field holding the variant name, with a
+    /// `#[serde(tag = "...")]` attribute.
+    ///
+    /// Without [`serde_content`] the enum is internally tagged, and with it
+    /// adjacently tagged. Calling this again replaces the previous tag.
+    ///
+    /// ```
+    /// use codegen::{Enum, RenameRule};
+    ///
+    /// let mut event = Enum::new("Event");
+    /// event.derive("serde::Serialize");
+    /// event
+    ///     .serde_tag("type")
+    ///     .serde_content("data")
+    ///     .serde_rename_all(RenameRule::Camel);
+    /// event.new_variant("Created").tuple("Item");
+    /// event.new_variant("Deleted").tuple("u64").serde_rename("removed");
+    /// ```
+    ///
+    /// [`serde_content`]: #method.serde_content
+    pub fn serde_tag(&mut self, tag: impl ToString) -> &mut Self {
+        self.type_def.serde("tag", tag);
+        self
+    }
+
+    /// Set the field holding the variant fields of an adjacently tagged enum,
+    /// with a `#[serde(content = "...")]` attribute.
+    ///
+    /// # Panics
+    ///
+    /// Formatting the enum panics if no [`serde_tag`] is set, or if the tag
+    /// and the content have the same name.
+    ///
+    /// [`serde_tag`]: #method.serde_tag
+    pub fn serde_content(&mut self, content: impl ToString) -> &mut Self {
+        self.type_def.serde("content", content);
+        self
+    }
+
+    /// Set how the variant names are serialized, with a
+    /// `#[serde(rename_all = "...")]` attribute.
+    ///
+    /// # Panics
+    ///
+    /// Formatting the enum panics if two variants are serialized with the
+    /// same name, once the rule and the [`Variant::serde_rename`] names are
+    /// applied.
+    ///
+    /// [`Variant::serde_rename`]: struct.Variant.html#method.serde_rename
+    pub fn serde_rename_all(&mut self, rule: RenameRule) -> &mut Self {
+        self.type_def.serde("rename_all", rule.as_str());
+        self.rename_all = Some(rule);
+        self
+    }
+
+    /// Set the primitive representation of the enum to `repr`, e.g. `u8`, and
+    /// return `TryFrom<u8>` and `From<Enum> for u8` impls.
+    ///
+    /// Converting an integer that isn't the discriminant of a variant fails
+    /// with the integer as the error.
+    ///
+    /// ```
+    /// use codegen::{Enum, Scope};
+    ///
+    /// let mut opcode = Enum::new("Opcode");
+    /// opcode.new_variant("Read").discriminant("0x01");
+    /// opcode.new_variant("Write").discriminant("0x02");
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impls(opcode.generate_int_conversions("u8"));
+    /// scope.push_enum(opcode);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a variant has fields, or if the enum already
+    /// has another primitive representation.
+    pub fn generate_int_conversions(&mut self, repr: &str) -> Vec<Impl> {
+        assert!(
+            self.variants.iter().all(|variant| !variant.has_fields()),
+            "integer conversions can only be generated for enums without fields"
+        );
+        match self.type_def.primitive_repr() {
+            Some(existing) => assert!(
+                existing == repr,
+                "enum already has the `{}` representation",
+                existing
+            ),
+            None => self.type_def.repr(repr),
+        }
+
+        let ty = self.type_def.ty.clone();
+        let name = ty.name();
+
+        let mut arms = Block::new("match value");
+        for variant in &self.variants {
+            for cfg in variant.cfg_lines() {
+                arms.line(cfg);
+            }
+            arms.line(format!(
+                "x if x == {0}::{1} as {2} => Ok({0}::{1}),",
+                name,
+                variant.name(),
+                repr
+            ));
+        }
+        arms.line("_ => Err(value),");
+
+        let mut try_from = self.type_def.new_impl();
+        try_from
+            .impl_trait(format!("TryFrom<{}>", repr))
+            .associate_type("Error", repr)
+            .new_fn("try_from")
+            .arg("value", repr)
+            .ret("Result<Self, Self::Error>")
+            .push_block(arms);
+        let mut into = Impl::new(repr);
+        into.impl_trait(format!("From<{}>", ty))
+            .new_fn("from")
+            .arg("value", &ty)
+            .ret("Self")
+            .line(format!("value as {}", repr));
+
+        vec![try_from, into]
+    }
+
+    /// Return a `match` over the variants of the enum, with a `todo!()` arm
+    /// for each variant.
+    ///
+    /// The arms of variants gated with [`Variant::cfg`] get the same
+    /// `#[cfg(...)]` attributes, and `#[non_exhaustive]` enums get a
+    /// trailing `_` arm so the match compiles in other crates.
+    ///
+    /// ```
+    /// use codegen::{Enum, Function};
+    ///
+    /// let mut event = Enum::new("Event");
+    /// event.new_variant("Closed");
+    /// event.new_variant("Key").tuple("char");
+    ///
+    /// let mut handle = Function::new("handle");
+    /// handle.arg("event", "Event").push_block(event.match_skeleton("event"));
+    /// ```
+    ///
+    /// [`Variant::cfg`]: struct.Variant.html#method.cfg
+    pub fn match_skeleton(&self, scrutinee: impl ToString) -> Block {
+        self.match_variants(scrutinee, |_| "todo!()".to_string(), Some("todo!()"))
+    }
+
+    /// Return a `match` over the variants of the enum, with the arm bodies
+    /// returned by `arm`.
+    ///
+    /// The patterns bind the fields of the variants, tuple fields as `f0`,
+    /// `f1`, ... and named fields by name, so the bodies can use them.
+    /// `#[non_exhaustive]` enums get a trailing `_ => wildcard` arm, and the
+    /// arms of variants gated with [`Variant::cfg`] are gated the same way.
+    ///
+    /// ```
+    /// use codegen::{Enum, Function};
+    ///
+    /// let mut expr = Enum::new("Expr");
+    /// expr.new_variant("Lit").tuple("i64");
+    /// expr.new_variant("Add").tuple("Box<Expr>").tuple("Box<Expr>");
+    ///
+    /// let mut visit = Function::new("visit_expr");
+    /// visit.arg_mut_self().arg("expr", "&Expr");
+    /// visit.push_block(expr.match_with(
+    ///     "expr",
+    ///     |variant| format!("self.visit_{}()", variant.name().to_lowercase()),
+    ///     "unreachable!()",
+    /// ));
+    /// ```
+    ///
+    /// [`Variant::cfg`]: struct.Variant.html#method.cfg
+    pub fn match_with<F>(&self, scrutinee: impl ToString, mut arm: F, wildcard: &str) -> Block
+    where
+        F: FnMut(&Variant) -> String,
+    {
+        self.match_patterns(
+            scrutinee,
+            Variant::binding_pattern,
+            &mut arm,
+            Some(wildcard),
+        )
+    }
+
+    /// Return a `match` over the variants of the enum, with the arms returned
+    /// by `arm`, followed by a `_ => wildcard` arm if the enum is
+    /// `#[non_exhaustive]`.
+    ///
+    /// Matches in the crate defining the enum don't need the wildcard, which
+    /// would be an unreachable pattern there, so it can be omitted with `None`.
+    pub(crate) fn match_variants<F>(
+        &self,
+        scrutinee: impl ToString,
+        mut arm: F,
+        wildcard: Option<&str>,
+    ) -> Block
+    where
+        F: FnMut(&Variant) -> String,
+    {
+        self.match_patterns(scrutinee, Variant::pattern, &mut arm, wildcard)
+    }
+
+    /// Return a `match` over the variants of the enum, with the patterns
+    /// returned by `pattern`.
+    fn match_patterns(
+        &self,
+        scrutinee: impl ToString,
+        pattern: fn(&Variant, &str) -> String,
+        arm: &mut dyn FnMut(&Variant) -> String,
+        wildcard: Option<&str>,
+    ) -> Block {
+        let path = self.type_def.ty.name();
+
+        let mut block = Block::new(format!("match {}", scrutinee.to_string()));
+        for variant in &self.variants {
+            for cfg in variant.cfg_lines() {
+                block.line(cfg);
+            }
+            block.line(format!("{} => {},", pattern(variant, path), arm(variant)));
+        }
+        if let Some(wildcard) = wildcard.filter(|_| self.is_non_exhaustive()) {
+            block.line(format!("_ => {},", wildcard));
+        }
+
+        block
+    }
+
+    /// Call `f` on every type used by the enum, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.type_def.visit_types_mut(f);
+        for variant in &mut self.variants {
+            variant.visit_types_mut(f);
+        }
+    }
+
+    /// Asserts that the serde attributes of the enum are consistent.
+    fn validate_serde(&self) {
+        let tag = self.type_def.get_serde("tag");
+        if let Some(content) = self.type_def.get_serde("content") {
+            assert!(tag.is_some(), "serde `content` requires a `tag`");
+            assert!(
+                tag != Some(content),
+                "serde `tag` and `content` can't have the same name"
+            );
+        } else if tag.is_some() {
+            for variant in &self.variants {
+                let fields = variant.fields();
+                assert!(
+                    !matches!(fields, Fields::Tuple(..)) || fields.iter().count() == 1,
+                    "internally tagged enums can't have tuple variant `{}`",
+                    variant.name()
+                );
+            }
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        for variant in &self.variants {
+            let name = match (variant.get_serde_rename(), self.rename_all) {
+                (Some(rename), _) => rename.clone(),
+                (None, Some(rule)) => rule.apply_to_variant(variant.name()),
+                (None, None) => variant.name().clone(),
+            };
+            assert!(
+                !names.contains(&name),
+                "several variants are serialized as `{}`",
+                name
+            );
+            names.push(name);
+        }
+    }
+
+    /// Panics if the explicit discriminants are invalid for the
+    /// representation of the enum.
+    fn validate_discriminants(&self) {
+        if self.variants.iter().all(|v| v.get_discriminant().is_none()) {
+            return;
+        }
+
+        let repr = self.type_def.primitive_repr();
+        assert!(
+            repr.is_some()
+                || self.type_def.has_repr("C")
+                || self.variants.iter().all(|v| !v.has_fields()),
+            "enums with fields need a primitive representation to have explicit discriminants"
+        );
+
+        // values of the variants, when they are known
+        let mut values: Vec<(i128, &String)> = Vec::new();
+        let mut next = Some(0);
+        for variant in &self.variants {
+            let value = match variant.get_discriminant() {
+                Some(discriminant) => parse_int(discriminant),
+                None => next,
+            };
+
+            if let Some(value) = value {
+                if let Some(repr) = repr {
+                    assert!(
+                        fits_repr(value, repr),
+                        "discriminant of `{}` doesn't fit in `{}`",
+                        variant.name(),
+                        repr
+                    );
+                }
+                if let Some((_, other)) = values.iter().find(|(v, _)| *v == value) {
+                    panic!(
+                        "`{}` and `{}` have the same discriminant",
+                        other,
+                        variant.name()
+                    );
+                }
+                values.push((value, variant.name()));
+            }
+
+            next = value.and_then(|value| value.checked_add(1));
+        }
+    }
+
     /// Formats the enum using the given formatter.
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        self.validate_discriminants();
+        self.validate_serde();
         self.type_def.fmt_head("enum", &[], fmt)?;
 
         fmt.block(|fmt| {
@@ -115,3 +992,119 @@ impl Enum {
         })
     }
 }
+
+/// Parse an integer literal such as `-1`, `0x10` or `1_000u32`.
+fn parse_int(literal: &str) -> Option<i128> {
+    let literal: String = literal
+        .chars()
+        .filter(|c| *c != '_' && !c.is_whitespace())
+        .collect();
+    let (negative, literal) = match literal.strip_prefix('-') {
+        Some(literal) => (true, literal),
+        None => (false, &literal[..]),
+    };
+
+    let (radix, digits) = match literal.get(..2) {
+        Some("0x") => (16, &literal[2..]),
+        Some("0o") => (8, &literal[2..]),
+        Some("0b") => (2, &literal[2..]),
+        _ => (10, literal),
+    };
+
+    // strip a type suffix, e.g. `u8`
+    let digits = match digits.find(['u', 'i']) {
+        Some(index) => &digits[..index],
+        None => digits,
+    };
+
+    let value = i128::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+/// Returns the snake case version of a camel case `name`, e.g. `http_error`
+/// for `HTTPError`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+
+    snake
+}
+
+/// Returns whether `value` fits in the primitive representation `repr`.
+fn fits_repr(value: i128, repr: &str) -> bool {
+    let (min, max) = match repr {
+        "u8" => (0, u8::MAX as i128),
+        "u16" => (0, u16::MAX as i128),
+        "u32" => (0, u32::MAX as i128),
+        "u64" | "usize" => (0, u64::MAX as i128),
+        "u128" => (0, i128::MAX),
+        "i8" => (i8::MIN as i128, i8::MAX as i128),
+        "i16" => (i16::MIN as i128, i16::MAX as i128),
+        "i32" => (i32::MIN as i128, i32::MAX as i128),
+        "i64" | "isize" => (i64::MIN as i128, i64::MAX as i128),
+        _ => (i128::MIN, i128::MAX),
+    };
+
+    min <= value && value <= max
+}
+
+#[test]
+fn parse_int_literals() {
+    assert_eq!(parse_int("42"), Some(42));
+    assert_eq!(parse_int("-1"), Some(-1));
+    assert_eq!(parse_int("0x1F"), Some(31));
+    assert_eq!(parse_int("0b1010_0000"), Some(160));
+    assert_eq!(parse_int("1_000u32"), Some(1000));
+    assert_eq!(parse_int("Other::A as u8"), None);
+}
+
+#[test]
+fn snake_case_names() {
+    assert_eq!(snake_case("Read"), "read");
+    assert_eq!(snake_case("ReadWrite"), "read_write");
+    assert_eq!(snake_case("HTTPError"), "http_error");
+    assert_eq!(snake_case("Utf8Error"), "utf8_error");
+    assert_eq!(snake_case("V2"), "v2");
+}
+
+#[test]
+fn rename_rules() {
+    let names: Vec<_> = [
+        RenameRule::Lower,
+        RenameRule::Upper,
+        RenameRule::Pascal,
+        RenameRule::Camel,
+        RenameRule::Snake,
+        RenameRule::ScreamingSnake,
+        RenameRule::Kebab,
+        RenameRule::ScreamingKebab,
+    ]
+    .iter()
+    .map(|rule| rule.apply_to_variant("HttpError"))
+    .collect();
+
+    assert_eq!(
+        names,
+        [
+            "httperror",
+            "HTTPERROR",
+            "HttpError",
+            "httpError",
+            "http_error",
+            "HTTP_ERROR",
+            "http-error",
+            "HTTP-ERROR",
+        ]
+    );
+}
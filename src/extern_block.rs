@@ -0,0 +1,102 @@
+use std::fmt::{self, Write};
+
+use crate::formatter::Formatter;
+use crate::function::Function;
+
+use crate::r#type::Type;
+
+/// Defines an `extern` block declaring foreign functions, e.g.
+/// `extern "C" { ... }`.
+#[derive(Debug, Clone)]
+pub struct ExternBlock {
+    /// The ABI of the declared functions
+    abi: String,
+
+    /// Block attributes, e.g., `#[link(name = "m")]`.
+    attributes: Vec<String>,
+
+    /// Declared functions
+    fns: Vec<Function>,
+}
+
+impl ExternBlock {
+    /// Return a new `extern` block for the given ABI, e.g. `"C"`.
+    pub fn new(abi: impl ToString) -> Self {
+        ExternBlock {
+            abi: abi.to_string(),
+            attributes: Vec::new(),
+            fns: Vec::new(),
+        }
+    }
+
+    /// Returns the ABI of the block.
+    pub fn abi(&self) -> &String {
+        &self.abi
+    }
+
+    /// Add an attribute to the block.
+    ///
+    /// ```
+    /// use codegen::ExternBlock;
+    ///
+    /// let mut block = ExternBlock::new("C");
+    ///
+    /// // add a `#[link(name = "m")]` attribute
+    /// block.attr("link(name = \"m\")");
+    /// ```
+    pub fn attr(&mut self, attribute: impl ToString) -> &mut Self {
+        self.attributes.push(attribute.to_string());
+        self
+    }
+
+    /// Push a new function declaration, returning a mutable reference to it.
+    ///
+    /// The ABI of the block applies to the function, so the `extern_abi` of
+    /// the function is not formatted.
+    pub fn new_fn(&mut self, name: impl ToString) -> &mut Function {
+        let mut func = Function::new(name);
+        func.body = None;
+
+        self.push_fn(func);
+        self.fns.last_mut().unwrap()
+    }
+
+    /// Push a function declaration.
+    ///
+    /// # Panics
+    ///
+    /// Formatting the block panics if the function has a body, unless it is
+    /// marked as signature only.
+    pub fn push_fn(&mut self, item: Function) -> &mut Self {
+        self.fns.push(item);
+        self
+    }
+
+    /// Call `f` on every type used by the block, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        for func in &mut self.fns {
+            func.visit_types_mut(f);
+        }
+    }
+
+    /// Formats the `extern` block using the given formatter.
+    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        for attr in &self.attributes {
+            writeln!(fmt, "#[{}]", attr)?;
+        }
+
+        write!(fmt, "extern \"{}\"", self.abi)?;
+
+        fmt.block(|fmt| {
+            for (i, func) in self.fns.iter().enumerate() {
+                if i != 0 {
+                    writeln!(fmt)?;
+                }
+
+                func.fmt_foreign(fmt)?;
+            }
+
+            Ok(())
+        })
+    }
+}
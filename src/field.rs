@@ -1,3 +1,9 @@
+use quote::ToTokens;
+
+use crate::r#struct::DebugStyle;
+use crate::tokens::tokens_to_string;
+use crate::type_def::SynAttrs;
+
 use crate::r#type::Type;
 
 /// Defines a struct field.
@@ -20,6 +26,15 @@ pub struct Field {
 
     /// The visibility of the field
     pub visibility: Option<String>,
+
+    /// Whether generated accessors include a getter for the field
+    pub getter: bool,
+
+    /// Whether generated accessors include a setter for the field
+    pub setter: bool,
+
+    /// How a generated `Debug` impl formats the field
+    pub debug: DebugStyle,
 }
 
 impl Field {
@@ -35,10 +50,49 @@ impl Field {
             annotation: Vec::new(),
             value: String::new(),
             visibility: None,
+            getter: true,
+            setter: true,
+            debug: DebugStyle::Show,
         }
     }
 
+    /// Return a tuple field definition with the provided type
+    pub fn tuple<T>(ty: T) -> Self
+    where
+        T: Into<Type>,
+    {
+        Field::new("", ty)
+    }
+
+    /// Return a field definition from a field parsed by `syn`, keeping its
+    /// visibility, docs and attributes.
+    pub(crate) fn from_syn(field: &syn::Field) -> Self {
+        let mut ret = match field.ident {
+            Some(ref ident) => Field::new(ident, Type::from_syn(&field.ty)),
+            None => Field::tuple(Type::from_syn(&field.ty)),
+        };
+        if !matches!(field.vis, syn::Visibility::Inherited) {
+            ret.vis(tokens_to_string(field.vis.to_token_stream()));
+        }
+
+        let attrs = SynAttrs::new(&field.attrs);
+        if let Some(docs) = attrs.docs {
+            ret.doc(docs);
+        }
+        for meta in attrs.rest {
+            ret.attr(tokens_to_string(meta.to_token_stream()));
+        }
+        for raw in attrs.raw {
+            ret.attr(raw);
+        }
+
+        ret
+    }
+
     /// Set field's documentation.
+    ///
+    /// The documentation can contain several paragraphs and lists. Lines that
+    /// don't fit in 80 columns are wrapped when formatting.
     pub fn doc(&mut self, documentation: impl ToString) -> &mut Self {
         self.documentation = documentation.to_string();
         self
@@ -50,7 +104,78 @@ impl Field {
         self
     }
 
+    /// Add an attribute to the field.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// let mut user = Struct::new("User");
+    ///
+    /// // add a `#[serde(rename = "userId")]` attribute
+    /// user.new_field("user_id", "u64").attr("serde(rename = \"userId\")");
+    /// ```
+    ///
+    /// This is the same as adding `#[...]` as an annotation.
+    pub fn attr(&mut self, attribute: impl ToString) -> &mut Self {
+        self.annotation
+            .push(format!("#[{}]", attribute.to_string()));
+        self
+    }
+
+    /// Set the expression the field is initialized with by a generated
+    /// `Default` impl.
+    ///
+    /// Fields without a default value are initialized with
+    /// `Default::default()`. See [`Struct::impl_default`].
+    ///
+    /// [`Struct::impl_default`]: struct.Struct.html#method.impl_default
+    pub fn default_value(&mut self, value: impl ToString) -> &mut Self {
+        self.value = value.to_string();
+        self
+    }
+
+    /// Set whether [`Struct::generate_accessors`] generates a getter for the
+    /// field. This is `true` by default.
+    ///
+    /// [`Struct::generate_accessors`]: struct.Struct.html#method.generate_accessors
+    pub fn set_getter(&mut self, getter: bool) -> &mut Self {
+        self.getter = getter;
+        self
+    }
+
+    /// Set whether [`Struct::generate_accessors`] generates a setter for the
+    /// field. This is `true` by default.
+    ///
+    /// [`Struct::generate_accessors`]: struct.Struct.html#method.generate_accessors
+    pub fn set_setter(&mut self, setter: bool) -> &mut Self {
+        self.setter = setter;
+        self
+    }
+
+    /// Set how [`Struct::impl_debug`] formats the field. This is
+    /// [`DebugStyle::Show`] by default.
+    ///
+    /// [`Struct::impl_debug`]: struct.Struct.html#method.impl_debug
+    /// [`DebugStyle::Show`]: enum.DebugStyle.html#variant.Show
+    pub fn set_debug(&mut self, debug: DebugStyle) -> &mut Self {
+        self.debug = debug;
+        self
+    }
+
     /// Set the visibility of the field
+    ///
+    /// Fields are private unless this is set, regardless of the visibility
+    /// of the struct.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// let mut user = Struct::new("User");
+    /// user.vis("pub");
+    /// user.new_field("name", "String").vis("pub");
+    /// user.new_field("cache", "Cache").vis("pub(crate)");
+    /// user.field("password_hash", "String");
+    /// ```
     pub fn vis(&mut self, visibility: impl ToString) -> &mut Self {
         self.visibility = Some(visibility.to_string());
         self
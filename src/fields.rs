@@ -1,5 +1,6 @@
 use std::fmt::{self, Write};
 
+use crate::docs;
 use crate::field::Field;
 use crate::formatter::Formatter;
 
@@ -9,7 +10,7 @@ use crate::r#type::Type;
 #[derive(Debug, Clone)]
 pub enum Fields {
     Empty,
-    Tuple(Vec<(Option<String> /* visibility */, Type)>),
+    Tuple(Vec<Field>),
     Named(Vec<Field>),
 }
 
@@ -32,14 +33,7 @@ impl Fields {
     where
         T: Into<Type>,
     {
-        self.push_named(Field {
-            name: name.to_string(),
-            ty: ty.into(),
-            documentation: String::new(),
-            annotation: Vec::new(),
-            value: String::new(),
-            visibility: None,
-        })
+        self.push_named(Field::new(name, ty))
     }
 
     pub fn new_named<T>(&mut self, name: impl ToString, ty: T) -> &mut Field
@@ -54,16 +48,29 @@ impl Fields {
         }
     }
 
-    pub fn tuple<T>(&mut self, vis: Option<String>, ty: T) -> &mut Self
-    where
-        T: Into<Type>,
-    {
+    pub fn insert_named(&mut self, index: usize, field: Field) -> &mut Self {
         match *self {
             Fields::Empty => {
-                *self = Fields::Tuple(vec![(vis, ty.into())]);
+                assert!(index == 0, "field index out of bounds");
+                *self = Fields::Named(vec![field]);
+            }
+            Fields::Named(ref mut fields) => {
+                assert!(index <= fields.len(), "field index out of bounds");
+                fields.insert(index, field);
+            }
+            _ => panic!("field list is named"),
+        }
+
+        self
+    }
+
+    pub fn push_tuple(&mut self, field: Field) -> &mut Self {
+        match *self {
+            Fields::Empty => {
+                *self = Fields::Tuple(vec![field]);
             }
             Fields::Tuple(ref mut fields) => {
-                fields.push((vis, ty.into()));
+                fields.push(field);
             }
             _ => panic!("field list is tuple"),
         }
@@ -71,21 +78,104 @@ impl Fields {
         self
     }
 
+    pub fn tuple<T>(&mut self, vis: Option<String>, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        let mut field = Field::tuple(ty);
+        field.visibility = vis;
+        self.push_tuple(field)
+    }
+
+    pub fn new_tuple<T>(&mut self, ty: T) -> &mut Field
+    where
+        T: Into<Type>,
+    {
+        self.push_tuple(Field::tuple(ty));
+        if let Fields::Tuple(ref mut fields) = *self {
+            fields.last_mut().unwrap()
+        } else {
+            unreachable!()
+        }
+    }
+
+    /// Remove the named field called `name`, returning it.
+    pub fn remove_named(&mut self, name: &str) -> Option<Field> {
+        let fields = match *self {
+            Fields::Named(ref mut fields) => fields,
+            _ => return None,
+        };
+        let index = fields.iter().position(|field| field.name == name)?;
+        let field = fields.remove(index);
+
+        if fields.is_empty() {
+            *self = Fields::Empty;
+        }
+
+        Some(field)
+    }
+
+    /// Replace the named field called `name`, returning the previous field.
+    pub fn replace_named(&mut self, name: &str, field: Field) -> Option<Field> {
+        match *self {
+            Fields::Named(ref mut fields) => fields
+                .iter_mut()
+                .find(|f| f.name == name)
+                .map(|f| std::mem::replace(f, field)),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the fields, in the order they are formatted.
+    pub fn iter(&self) -> std::slice::Iter<'_, Field> {
+        match *self {
+            Fields::Empty => [].iter(),
+            Fields::Tuple(ref fields) | Fields::Named(ref fields) => fields.iter(),
+        }
+    }
+
+    /// Sort the fields by the key returned by `f`, keeping the relative order
+    /// of fields with equal keys.
+    pub fn sort_by_key<K, F>(&mut self, f: F) -> &mut Self
+    where
+        K: Ord,
+        F: FnMut(&Field) -> K,
+    {
+        match *self {
+            Fields::Empty => {}
+            Fields::Tuple(ref mut fields) | Fields::Named(ref mut fields) => fields.sort_by_key(f),
+        }
+
+        self
+    }
+
+    /// Call `f` on every type used by the fields, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        match *self {
+            Fields::Empty => {}
+            Fields::Tuple(ref mut fields) | Fields::Named(ref mut fields) => {
+                fields.iter_mut().for_each(|field| field.ty.visit_mut(f))
+            }
+        }
+    }
+
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         match *self {
             Fields::Named(ref fields) => {
                 assert!(!fields.is_empty());
 
-                fmt.block(|fmt| {
+                // like `Formatter::block`, without the trailing newline so
+                // that enum variants can be followed by a `,`
+                if !fmt.is_start_of_line() {
+                    write!(fmt, " ")?;
+                }
+                writeln!(fmt, "{{")?;
+                fmt.indent(|fmt| {
                     for f in fields {
-                        if !f.documentation.is_empty() {
-                            for doc in f.documentation.lines() {
-                                write!(fmt, "/// {}\n", doc)?;
-                            }
-                        }
+                        docs::fmt_wrapped(&f.documentation, fmt)?;
                         if !f.annotation.is_empty() {
                             for ann in &f.annotation {
-                                write!(fmt, "{}\n", ann)?;
+                                writeln!(fmt, "{}", ann)?;
                             }
                         }
                         if let Some(ref visibility) = f.visibility {
@@ -93,24 +183,42 @@ impl Fields {
                         }
                         write!(fmt, "{}: ", f.name)?;
                         f.ty.fmt(fmt)?;
-                        write!(fmt, ",\n")?;
+                        writeln!(fmt, ",")?;
                     }
 
                     Ok(())
                 })?;
+                write!(fmt, "}}")?;
             }
-            Fields::Tuple(ref tys) => {
-                assert!(!tys.is_empty());
+            Fields::Tuple(ref fields) => {
+                assert!(!fields.is_empty());
                 write!(fmt, "(")?;
 
-                for (i, ty) in tys.iter().enumerate() {
-                    if i != 0 {
-                        write!(fmt, ", ")?;
-                    }
-                    if let Some(vis) = ty.0.as_ref() {
-                        write!(fmt, "{} ", vis)?;
+                if fields.iter().any(|f| !f.documentation.is_empty()) {
+                    // documented fields are formatted one per line
+                    writeln!(fmt)?;
+                    fmt.indent(|fmt| {
+                        for f in fields {
+                            docs::fmt_wrapped(&f.documentation, fmt)?;
+                            for ann in &f.annotation {
+                                writeln!(fmt, "{}", ann)?;
+                            }
+                            fmt_tuple_field(f, fmt)?;
+                            writeln!(fmt, ",")?;
+                        }
+
+                        Ok(())
+                    })?;
+                } else {
+                    for (i, f) in fields.iter().enumerate() {
+                        if i != 0 {
+                            write!(fmt, ", ")?;
+                        }
+                        for ann in &f.annotation {
+                            write!(fmt, "{} ", ann)?;
+                        }
+                        fmt_tuple_field(f, fmt)?;
                     }
-                    ty.1.fmt(fmt)?;
                 }
 
                 write!(fmt, ")")?;
@@ -122,6 +230,13 @@ impl Fields {
     }
 }
 
+fn fmt_tuple_field(field: &Field, fmt: &mut Formatter<'_>) -> fmt::Result {
+    if let Some(ref visibility) = field.visibility {
+        write!(fmt, "{} ", visibility)?;
+    }
+    field.ty.fmt(fmt)
+}
+
 #[test]
 fn parse_generic() {
     {
@@ -38,9 +38,9 @@ impl<'a> Formatter<'a> {
             write!(self, " ")?;
         }
 
-        write!(self, "{{\n")?;
+        writeln!(self, "{{")?;
         self.indent(f)?;
-        write!(self, "}}\n")?;
+        writeln!(self, "}}")?;
         Ok(())
     }
 
@@ -55,6 +55,11 @@ impl<'a> Formatter<'a> {
         ret
     }
 
+    /// Returns the number of spaces new lines start with.
+    pub(crate) fn spaces(&self) -> usize {
+        self.spaces
+    }
+
     /// Check if current destination is the start of a new line.
     pub fn is_start_of_line(&self) -> bool {
         self.dst.is_empty() || self.dst.as_bytes().last() == Some(&b'\n')
@@ -62,7 +67,7 @@ impl<'a> Formatter<'a> {
 
     fn push_spaces(&mut self) {
         for _ in 0..self.spaces {
-            self.dst.push_str(" ");
+            self.dst.push(' ');
         }
     }
 }
@@ -74,7 +79,7 @@ impl<'a> fmt::Write for Formatter<'a> {
 
         for line in s.lines() {
             if !first {
-                self.dst.push_str("\n");
+                self.dst.push('\n');
             }
 
             first = false;
@@ -92,13 +97,28 @@ impl<'a> fmt::Write for Formatter<'a> {
         }
 
         if s.as_bytes().last() == Some(&b'\n') {
-            self.dst.push_str("\n");
+            self.dst.push('\n');
         }
 
         Ok(())
     }
 }
 
+/// Returns a `deprecated` attribute, without the surrounding `#[...]`.
+pub(crate) fn deprecated_attr(since: Option<&str>, note: Option<&str>) -> String {
+    let args = since
+        .map(|since| format!("since = {:?}", since))
+        .into_iter()
+        .chain(note.map(|note| format!("note = {:?}", note)))
+        .collect::<Vec<_>>();
+
+    if args.is_empty() {
+        "deprecated".to_string()
+    } else {
+        format!("deprecated({})", args.join(", "))
+    }
+}
+
 /// Format generics.
 pub fn fmt_generics(generics: &[String], fmt: &mut Formatter<'_>) -> fmt::Result {
     if !generics.is_empty() {
@@ -120,17 +140,17 @@ pub fn fmt_generics(generics: &[String], fmt: &mut Formatter<'_>) -> fmt::Result
 /// Format generic bounds.
 pub fn fmt_bounds(bounds: &[Bound], fmt: &mut Formatter<'_>) -> fmt::Result {
     if !bounds.is_empty() {
-        write!(fmt, "\n")?;
+        writeln!(fmt)?;
 
         // Write first bound
         write!(fmt, "where {}: ", bounds[0].name)?;
         fmt_bound_rhs(&bounds[0].bound, fmt)?;
-        write!(fmt, ",\n")?;
+        writeln!(fmt, ",")?;
 
         for bound in &bounds[1..] {
             write!(fmt, "      {}: ", bound.name)?;
             fmt_bound_rhs(&bound.bound, fmt)?;
-            write!(fmt, ",\n")?;
+            writeln!(fmt, ",")?;
         }
     }
 
@@ -1,15 +1,31 @@
 use std::fmt::{self, Write};
 
+use crate::arg::Arg;
 use crate::block::Block;
-use crate::body::Body;
+use crate::body::{self, Body};
 use crate::bound::Bound;
-use crate::docs::Docs;
-use crate::field::Field;
+use crate::cfg::Cfg;
+use crate::docs::{DocSections, Docs};
 use crate::formatter::Formatter;
-use crate::formatter::{fmt_bounds, fmt_generics};
+use crate::formatter::{deprecated_attr, fmt_bounds, fmt_generics};
+use crate::lifetime::Lifetime;
+use crate::receiver::Receiver;
 
 use crate::r#type::Type;
 
+/// Defines the hint of an `#[inline]` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inline {
+    /// `#[inline]`
+    Hint,
+
+    /// `#[inline(always)]`
+    Always,
+
+    /// `#[inline(never)]`
+    Never,
+}
+
 /// Defines a function.
 #[derive(Debug, Clone)]
 pub struct Function {
@@ -19,20 +35,26 @@ pub struct Function {
     /// Function documentation
     docs: Option<Docs>,
 
+    /// Function documentation sections, e.g. `# Errors`
+    doc_sections: DocSections,
+
     /// A lint attribute used to suppress a warning or error
     allow: Option<String>,
 
     /// Function visibility
     vis: Option<String>,
 
+    /// Function lifetime parameters
+    lifetimes: Vec<Lifetime>,
+
     /// Function generics
     generics: Vec<String>,
 
-    /// If the function takes `&self` or `&mut self`
-    arg_self: Option<String>,
+    /// If the function takes `self`, `&self`, `&mut self`, ...
+    arg_self: Option<Receiver>,
 
     /// Function arguments
-    args: Vec<Field>,
+    args: Vec<Arg>,
 
     /// Return type
     ret: Option<Type>,
@@ -43,14 +65,50 @@ pub struct Function {
     /// Body contents
     pub body: Option<Vec<Body>>,
 
+    /// Whether or not only the signature is formatted, ignoring the body
+    signature_only: bool,
+
     /// Function attributes, e.g., `#[no_mangle]`.
     attributes: Vec<String>,
 
+    /// `#[cfg(...)]` predicates the function is gated on
+    cfgs: Vec<Cfg>,
+
+    /// The `#[inline]` attribute
+    inline: Option<Inline>,
+
+    /// Whether or not this function is `#[cold]`
+    cold: bool,
+
+    /// The `#[must_use]` attribute, with an optional message
+    must_use: Option<Option<String>>,
+
+    /// The `#[deprecated]` attribute
+    deprecated: Option<String>,
+
+    /// Whether or not this function is `#[track_caller]`
+    track_caller: bool,
+
+    /// Whether or not this function is `#[no_mangle]`
+    no_mangle: bool,
+
+    /// The `#[export_name = "..."]` symbol of the function
+    export_name: Option<String>,
+
     /// Function `extern` ABI
     extern_abi: Option<String>,
 
+    /// Whether or not the last parameter is `...`
+    variadic: bool,
+
     /// Whether or not this function is `async` or not
     r#async: bool,
+
+    /// Whether or not this function is `const` or not
+    r#const: bool,
+
+    /// Whether or not this function is `unsafe` or not
+    r#unsafe: bool,
 }
 
 impl Function {
@@ -59,20 +117,57 @@ impl Function {
         Function {
             name: name.to_string(),
             docs: None,
+            doc_sections: DocSections::default(),
             allow: None,
             vis: None,
+            lifetimes: vec![],
             generics: vec![],
             arg_self: None,
             args: vec![],
             ret: None,
             bounds: vec![],
             body: Some(vec![]),
+            signature_only: false,
             attributes: vec![],
+            cfgs: vec![],
+            inline: None,
+            cold: false,
+            must_use: None,
+            deprecated: None,
+            track_caller: false,
+            no_mangle: false,
+            export_name: None,
             extern_abi: None,
+            variadic: false,
             r#async: false,
+            r#const: false,
+            r#unsafe: false,
         }
     }
 
+    /// Return a new `#[test]` function.
+    ///
+    /// ```
+    /// use codegen::{Function, Module};
+    ///
+    /// let mut tests = Module::new("tests");
+    /// tests.attr("cfg(test)");
+    /// tests.push_fn(Function::test("parses_empty").line("assert!(parse(\"\").is_ok());").clone());
+    /// ```
+    pub fn test(name: impl ToString) -> Self {
+        let mut func = Function::new(name);
+        func.attr("test");
+        func
+    }
+
+    /// Return a new `async` test function for the given runtime, e.g.
+    /// `#[tokio::test]` for `"tokio"`.
+    pub fn async_test(name: impl ToString, runtime: &str) -> Self {
+        let mut func = Function::new(name);
+        func.attr(&format!("{}::test", runtime)).set_async(true);
+        func
+    }
+
     /// Returns the name of the function
     pub fn name(&self) -> &String {
         &self.name
@@ -84,6 +179,71 @@ impl Function {
         self
     }
 
+    /// Document an argument of the function in the `# Arguments` section.
+    ///
+    /// The sections are formatted after the function documentation, in the
+    /// order `# Arguments`, `# Returns`, `# Errors`, `# Panics`, `# Safety`
+    /// and `# Examples`.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("get_user");
+    /// func.doc("Fetches a user.")
+    ///     .arg("id", "u64")
+    ///     .doc_arg("id", "The id of the user")
+    ///     .doc_errors("Returns an error if the user doesn't exist.")
+    ///     .doc_example("let user = client.get_user(42)?;");
+    /// ```
+    pub fn doc_arg(&mut self, name: impl ToString, description: impl ToString) -> &mut Self {
+        self.doc_sections.arg(name, description);
+        self
+    }
+
+    /// Set the `# Returns` section of the function documentation.
+    pub fn doc_returns(&mut self, description: impl ToString) -> &mut Self {
+        self.doc_sections.returns(description);
+        self
+    }
+
+    /// Set the `# Errors` section of the function documentation.
+    pub fn doc_errors(&mut self, description: impl ToString) -> &mut Self {
+        self.doc_sections.errors(description);
+        self
+    }
+
+    /// Set the `# Panics` section of the function documentation.
+    pub fn doc_panics(&mut self, description: impl ToString) -> &mut Self {
+        self.doc_sections.panics(description);
+        self
+    }
+
+    /// Set the `# Safety` section of the function documentation.
+    pub fn doc_safety(&mut self, description: impl ToString) -> &mut Self {
+        self.doc_sections.safety(description);
+        self
+    }
+
+    /// Add a code block to the `# Examples` section of the function
+    /// documentation.
+    ///
+    /// The code block is marked `ignore`, since the example usually can't
+    /// compile on its own in the generated crate. Use [`doc_example_with`] to
+    /// run it as a doctest.
+    ///
+    /// [`doc_example_with`]: #method.doc_example_with
+    pub fn doc_example(&mut self, code: impl ToString) -> &mut Self {
+        self.doc_example_with("ignore", code)
+    }
+
+    /// Add a code block to the `# Examples` section of the function
+    /// documentation, with the given info string, e.g. `no_run`, or `""` for
+    /// a doctest that runs.
+    pub fn doc_example_with(&mut self, info: impl ToString, code: impl ToString) -> &mut Self {
+        self.doc_sections.example(info, code);
+        self
+    }
+
     /// Specify lint attribute to supress a warning or error.
     pub fn allow(&mut self, allow: impl ToString) -> &mut Self {
         self.allow = Some(allow.to_string());
@@ -97,32 +257,124 @@ impl Function {
     }
 
     /// Set whether this function is async or not
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("fetch");
+    /// func.vis("pub").set_async(true);
+    /// assert!(func.is_async());
+    /// ```
     pub fn set_async(&mut self, r#async: bool) -> &mut Self {
         self.r#async = r#async;
         self
     }
 
+    /// Returns whether this function is async or not
+    pub fn is_async(&self) -> bool {
+        self.r#async
+    }
+
+    /// Set whether this function is const or not
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("table");
+    /// func.vis("pub").set_const(true);
+    /// assert!(func.is_const());
+    /// ```
+    pub fn set_const(&mut self, r#const: bool) -> &mut Self {
+        self.r#const = r#const;
+        self
+    }
+
+    /// Returns whether this function is const or not
+    pub fn is_const(&self) -> bool {
+        self.r#const
+    }
+
+    /// Set whether this function is unsafe or not
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("get_unchecked");
+    /// func.set_unsafe(true);
+    /// assert!(func.is_unsafe());
+    /// ```
+    pub fn set_unsafe(&mut self, r#unsafe: bool) -> &mut Self {
+        self.r#unsafe = r#unsafe;
+        self
+    }
+
+    /// Returns whether this function is unsafe or not
+    pub fn is_unsafe(&self) -> bool {
+        self.r#unsafe
+    }
+
+    /// Add a lifetime parameter to the function.
+    ///
+    /// Lifetimes are formatted before the other generics, regardless of the
+    /// order they are added in, and can be used in bounds:
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("first");
+    /// func.generic("T")
+    ///     .lifetime("'a")
+    ///     .bound("T", "'a")
+    ///     .arg("items", "&'a [T]")
+    ///     .ret("&'a T");
+    /// ```
+    pub fn lifetime(&mut self, lifetime: impl Into<Lifetime>) -> &mut Self {
+        self.lifetimes.push(lifetime.into());
+        self
+    }
+
     /// Add a generic to the function.
+    ///
+    /// Generics starting with `'` are added as lifetimes, see [`lifetime`].
+    ///
+    /// [`lifetime`]: #method.lifetime
     pub fn generic(&mut self, name: impl Into<String>) -> &mut Self {
-        self.generics.push(name.into());
+        let name = name.into();
+        if name.starts_with('\'') {
+            return self.lifetime(name);
+        }
+
+        self.generics.push(name);
         self
     }
 
     /// Add `self` as a function argument.
     pub fn arg_self(&mut self) -> &mut Self {
-        self.arg_self = Some("self".to_string());
-        self
+        self.receiver(Receiver::Value)
     }
 
     /// Add `&self` as a function argument.
     pub fn arg_ref_self(&mut self) -> &mut Self {
-        self.arg_self = Some("&self".to_string());
-        self
+        self.receiver(Receiver::Ref(None))
     }
 
     /// Add `&mut self` as a function argument.
     pub fn arg_mut_self(&mut self) -> &mut Self {
-        self.arg_self = Some("&mut self".to_string());
+        self.receiver(Receiver::RefMut(None))
+    }
+
+    /// Set the `self` argument of the function.
+    ///
+    /// ```
+    /// use codegen::{Function, Receiver};
+    ///
+    /// let mut func = Function::new("poll");
+    ///
+    /// // takes `self: Pin<&mut Self>`
+    /// func.receiver(Receiver::pinned());
+    /// ```
+    pub fn receiver(&mut self, receiver: Receiver) -> &mut Self {
+        self.arg_self = Some(receiver);
         self
     }
 
@@ -131,22 +383,53 @@ impl Function {
     where
         T: Into<Type>,
     {
-        self.args.push(Field {
-            name: name.to_string(),
-            ty: ty.into(),
-            // While a `Field` is used here, both `documentation`, `visibility`
-            // and `annotation` does not make sense for function arguments.
-            // Simply use empty strings.
-            documentation: String::new(),
-            annotation: Vec::new(),
-            value: String::new(),
-            visibility: None,
-        });
+        self.push_arg(Arg::new(name, ty))
+    }
+
+    /// Add a function argument destructured with the given pattern, e.g.
+    /// `(x, y): Point`.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("handle");
+    /// func.arg_pattern("(x, y)", "(u8, u8)")
+    ///     .arg_pattern("Wrapper(inner)", "Wrapper");
+    /// ```
+    pub fn arg_pattern<T>(&mut self, pattern: impl ToString, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.push_arg(Arg::new(pattern, ty))
+    }
+
+    /// Push a new function argument, returning a mutable reference to it.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("handle");
+    ///
+    /// // takes `#[allow(unused)] mut ctx: Context`
+    /// func.new_arg("ctx", "Context").set_mut(true).attr("allow(unused)");
+    /// ```
+    pub fn new_arg<T>(&mut self, name: impl ToString, ty: T) -> &mut Arg
+    where
+        T: Into<Type>,
+    {
+        self.push_arg(Arg::new(name, ty));
+        self.args.last_mut().unwrap()
+    }
 
+    /// Push a function argument.
+    pub fn push_arg(&mut self, arg: Arg) -> &mut Self {
+        self.args.push(arg);
         self
     }
 
     /// Set the function return type.
+    ///
+    /// The unit type `()` is the default return type and is not formatted.
     pub fn ret<T>(&mut self, ty: T) -> &mut Self
     where
         T: Into<Type>,
@@ -155,6 +438,21 @@ impl Function {
         self
     }
 
+    /// Set the function return type to `impl` the given bounds, e.g.
+    /// `impl Iterator<Item = u8> + Send + '_`.
+    ///
+    /// For async functions, this is the type of the awaited value.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("iter");
+    /// func.arg_ref_self().ret_impl("Iterator<Item = &u8> + '_");
+    /// ```
+    pub fn ret_impl(&mut self, bounds: impl ToString) -> &mut Self {
+        self.ret(Type::new(format!("impl {}", bounds.to_string())))
+    }
+
     /// Add a `where` bound to the function.
     pub fn bound<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
     where
@@ -167,6 +465,26 @@ impl Function {
         self
     }
 
+    /// Add a `where` bound to the function from a predicate such as
+    /// `T: Serialize + 'static`.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("store");
+    /// func.generic("T")
+    ///     .arg("value", "T")
+    ///     .where_bound("T: Serialize + 'static");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the predicate doesn't contain a `:`.
+    pub fn where_bound(&mut self, predicate: impl ToString) -> &mut Self {
+        self.bounds.push(Bound::parse(&predicate.to_string()));
+        self
+    }
+
     /// Push a line to the function implementation.
     pub fn line<T>(&mut self, line: T) -> &mut Self
     where
@@ -179,8 +497,115 @@ impl Function {
         self
     }
 
+    /// Push a line to the function implementation, ignoring its indentation.
+    pub fn push_line(&mut self, line: &str) -> &mut Self {
+        self.push_lines(line)
+    }
+
+    /// Push lines to the function implementation, re-indenting them relative
+    /// to the function.
+    ///
+    /// The indentation common to all of the lines is removed, as well as
+    /// leading and trailing blank lines, so snippets can be written as
+    /// indented raw strings.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("main");
+    /// func.push_lines(r#"
+    ///     let x = 1;
+    ///     if x > 0 {
+    ///         println!("positive");
+    ///     }
+    /// "#);
+    /// ```
+    pub fn push_lines(&mut self, lines: &str) -> &mut Self {
+        self.body.get_or_insert(vec![]).extend(body::dedent(lines));
+        self
+    }
+
+    /// Push a `let name = expr?;` line for each of the fallible expressions,
+    /// followed by `Ok(value)`.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("load");
+    /// func.arg("path", "&Path")
+    ///     .ret("io::Result<Config>")
+    ///     .try_chain(
+    ///         [
+    ///             ("text", "fs::read_to_string(path)"),
+    ///             ("config", "parse(&text)"),
+    ///         ],
+    ///         "config",
+    ///     );
+    /// ```
+    pub fn try_chain<I, N, E>(&mut self, steps: I, value: impl ToString) -> &mut Self
+    where
+        I: IntoIterator<Item = (N, E)>,
+        N: ToString,
+        E: ToString,
+    {
+        for (name, expr) in steps {
+            self.line(format!("let {} = {}?;", name.to_string(), expr.to_string()));
+        }
+
+        self.line(format!("Ok({})", value.to_string()))
+    }
+
+    /// Set the function implementation to the given tokens.
+    ///
+    /// The tokens are formatted with one statement per line, indenting
+    /// nested blocks.
+    ///
+    /// ```
+    /// use codegen::Function;
+    /// use quote::quote;
+    ///
+    /// let mut func = Function::new("sum");
+    /// func.arg("items", "&[u64]").ret("u64");
+    /// func.body_tokens(quote! {
+    ///     let mut total = 0;
+    ///     for item in items {
+    ///         total += item;
+    ///     }
+    ///     total
+    /// });
+    /// ```
+    pub fn body_tokens(&mut self, tokens: proc_macro2::TokenStream) -> &mut Self {
+        self.body = Some(vec![Body::Tokens(tokens)]);
+        self
+    }
+
+    /// Set whether only the signature of the function is formatted, followed
+    /// by a `;`, even if it has a body.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("len");
+    /// func.arg_ref_self().ret("usize").line("self.items.len()");
+    ///
+    /// // format `fn len(&self) -> usize;`
+    /// func.set_signature_only(true);
+    /// ```
+    pub fn set_signature_only(&mut self, signature_only: bool) -> &mut Self {
+        self.signature_only = signature_only;
+        self
+    }
+
+    /// Returns whether only the signature of the function is formatted
+    pub fn is_signature_only(&self) -> bool {
+        self.signature_only
+    }
+
     /// Add an attribute to the function.
     ///
+    /// Attributes that were already added are ignored, so adding the same
+    /// attribute twice formats it once.
+    ///
     /// ```
     /// use codegen::Function;
     ///
@@ -190,10 +615,211 @@ impl Function {
     /// func.attr("test");
     /// ```
     pub fn attr(&mut self, attribute: &str) -> &mut Self {
-        self.attributes.push(attribute.to_string());
+        if !self.attributes.iter().any(|attr| attr == attribute) {
+            self.attributes.push(attribute.to_string());
+        }
         self
     }
 
+    /// Only compile the function when the configuration predicate holds.
+    ///
+    /// ```
+    /// use codegen::{Cfg, Function};
+    ///
+    /// let mut func = Function::new("fetch");
+    ///
+    /// // add a `#[cfg(feature = "blocking")]` attribute
+    /// func.cfg("feature = \"blocking\"");
+    ///
+    /// // or, equivalently
+    /// func.cfg(Cfg::feature("blocking"));
+    /// ```
+    ///
+    /// Calling this several times adds one `#[cfg(...)]` per predicate, so
+    /// all of them must hold.
+    pub fn cfg(&mut self, predicate: impl Into<Cfg>) -> &mut Self {
+        self.cfgs.push(predicate.into());
+        self
+    }
+
+    /// Set the `#[inline]` attribute of the function.
+    ///
+    /// ```
+    /// use codegen::{Function, Inline};
+    ///
+    /// let mut func = Function::new("len");
+    ///
+    /// // add a `#[inline(always)]` attribute
+    /// func.inline(Inline::Always);
+    /// ```
+    pub fn inline(&mut self, inline: Inline) -> &mut Self {
+        self.inline = Some(inline);
+        self
+    }
+
+    /// Set whether the function is `#[cold]` or not.
+    pub fn set_cold(&mut self, cold: bool) -> &mut Self {
+        self.cold = cold;
+        self
+    }
+
+    /// Add a `#[must_use]` attribute to the function, with an optional
+    /// message.
+    pub fn must_use(&mut self, message: Option<&str>) -> &mut Self {
+        self.must_use = Some(message.map(str::to_string));
+        self
+    }
+
+    /// Mark the function as `#[deprecated]`, with an optional version and
+    /// note.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("fetch_v1");
+    ///
+    /// // add a `#[deprecated(since = "0.3.0", note = "use `fetch` instead")]` attribute
+    /// func.deprecated(Some("0.3.0"), Some("use `fetch` instead"));
+    /// ```
+    pub fn deprecated(&mut self, since: Option<&str>, note: Option<&str>) -> &mut Self {
+        self.deprecated = Some(deprecated_attr(since, note));
+        self
+    }
+
+    /// Set whether the function is `#[track_caller]` or not, so panics inside
+    /// of it are reported at the location of the caller.
+    ///
+    /// # Panics
+    ///
+    /// Formatting the function panics if it has an `extern` ABI other than
+    /// `"Rust"`.
+    pub fn set_track_caller(&mut self, track_caller: bool) -> &mut Self {
+        self.track_caller = track_caller;
+        self
+    }
+
+    /// Returns whether the function is `#[track_caller]` or not
+    pub fn is_track_caller(&self) -> bool {
+        self.track_caller
+    }
+
+    /// Set whether the function is `#[no_mangle]` or not, so it is exported
+    /// using its name as the symbol.
+    ///
+    /// This is usually combined with an `extern` ABI:
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("plugin_init");
+    /// func.vis("pub").extern_abi("C").set_no_mangle(true);
+    /// ```
+    ///
+    /// Generic functions must be mangled, so rustc ignores the attribute on
+    /// them with a warning.
+    ///
+    /// # Panics
+    ///
+    /// Formatting the function panics if it is declared in an `extern` block.
+    pub fn set_no_mangle(&mut self, no_mangle: bool) -> &mut Self {
+        self.no_mangle = no_mangle;
+        self
+    }
+
+    /// Returns whether the function is `#[no_mangle]` or not
+    pub fn is_no_mangle(&self) -> bool {
+        self.no_mangle
+    }
+
+    /// Export the function as a C symbol with the given name.
+    ///
+    /// This makes the function `pub` and `extern "C"`, and adds
+    /// `#[no_mangle]` if the symbol is the name of the function, or
+    /// `#[export_name = "..."]` otherwise.
+    ///
+    /// ```
+    /// use codegen::Function;
+    ///
+    /// let mut func = Function::new("init");
+    ///
+    /// // format `#[export_name = "mylib_init"] pub extern "C" fn init()`
+    /// func.export_c("mylib_init");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Formatting the function panics in the same cases as with
+    /// [`set_no_mangle`].
+    ///
+    /// [`set_no_mangle`]: #method.set_no_mangle
+    pub fn export_c(&mut self, symbol: &str) -> &mut Self {
+        self.vis("pub").extern_abi("C");
+
+        if symbol == self.name {
+            self.no_mangle = true;
+            self.export_name = None;
+        } else {
+            self.no_mangle = false;
+            self.export_name = Some(symbol.to_string());
+        }
+
+        self
+    }
+
+    /// Returns the attributes of the function in the order they are
+    /// formatted, without the surrounding `#[...]`.
+    ///
+    /// Attributes set with dedicated methods such as [`inline`] are formatted
+    /// before the ones added with [`attr`].
+    ///
+    /// [`inline`]: #method.inline
+    /// [`attr`]: #method.attr
+    pub fn attributes(&self) -> Vec<String> {
+        let mut attributes = Vec::new();
+
+        for cfg in &self.cfgs {
+            attributes.push(format!("cfg({})", cfg));
+        }
+
+        if let Some(ref allow) = self.allow {
+            attributes.push(format!("allow({})", allow));
+        }
+
+        match self.inline {
+            Some(Inline::Hint) => attributes.push("inline".to_string()),
+            Some(Inline::Always) => attributes.push("inline(always)".to_string()),
+            Some(Inline::Never) => attributes.push("inline(never)".to_string()),
+            None => {}
+        }
+
+        if self.cold {
+            attributes.push("cold".to_string());
+        }
+
+        match self.must_use {
+            Some(Some(ref message)) => attributes.push(format!("must_use = {:?}", message)),
+            Some(None) => attributes.push("must_use".to_string()),
+            None => {}
+        }
+
+        attributes.extend(self.deprecated.iter().cloned());
+
+        if self.track_caller {
+            attributes.push("track_caller".to_string());
+        }
+
+        if self.no_mangle {
+            attributes.push("no_mangle".to_string());
+        }
+
+        if let Some(ref symbol) = self.export_name {
+            attributes.push(format!("export_name = {:?}", symbol));
+        }
+
+        attributes.extend(self.attributes.iter().cloned());
+        attributes
+    }
+
     /// Specify an `extern` ABI for the function.
     /// ```
     /// use codegen::Function;
@@ -208,6 +834,33 @@ impl Function {
         self
     }
 
+    /// Set whether the function takes a variadic `...` as its last parameter.
+    ///
+    /// Variadics are only valid for foreign functions, so the function must
+    /// either have an `extern` ABI or be declared in an [`ExternBlock`].
+    ///
+    /// ```
+    /// use codegen::ExternBlock;
+    ///
+    /// let mut block = ExternBlock::new("C");
+    /// block
+    ///     .new_fn("printf")
+    ///     .arg("fmt", "*const c_char")
+    ///     .set_variadic(true)
+    ///     .ret("c_int");
+    /// ```
+    ///
+    /// [`ExternBlock`]: struct.ExternBlock.html
+    pub fn set_variadic(&mut self, variadic: bool) -> &mut Self {
+        self.variadic = variadic;
+        self
+    }
+
+    /// Returns whether the function is variadic or not
+    pub fn is_variadic(&self) -> bool {
+        self.variadic
+    }
+
     /// Push a block to the function implementation
     pub fn push_block(&mut self, block: Block) -> &mut Self {
         self.body.get_or_insert(vec![]).push(Body::Block(block));
@@ -215,46 +868,196 @@ impl Function {
         self
     }
 
+    /// Call `f` on every type used by the function, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        if let Some(Receiver::Typed(ref mut ty)) = self.arg_self {
+            ty.visit_mut(f);
+        }
+        for arg in &mut self.args {
+            arg.visit_types_mut(f);
+        }
+        if let Some(ref mut ret) = self.ret {
+            ret.visit_mut(f);
+        }
+        for bound in &mut self.bounds {
+            bound.visit_types_mut(f);
+        }
+    }
+
     /// Formats the function using the given formatter.
     pub fn fmt(&self, is_trait: bool, fmt: &mut Formatter<'_>) -> fmt::Result {
-        if let Some(ref docs) = self.docs {
-            docs.fmt(fmt)?;
+        if is_trait {
+            assert!(
+                self.vis.is_none(),
+                "trait fns do not have visibility modifiers"
+            );
         }
 
-        if let Some(ref allow) = self.allow {
-            write!(fmt, "#[allow({})]\n", allow)?;
+        self.fmt_head(true, fmt)?;
+
+        if self.signature_only {
+            return writeln!(fmt, ";");
         }
 
-        for attr in self.attributes.iter() {
-            write!(fmt, "#[{}]\n", attr)?;
+        match self.body {
+            Some(ref body) => fmt.block(|fmt| {
+                for b in body {
+                    b.fmt(fmt)?;
+                }
+
+                Ok(())
+            }),
+            None => {
+                if !is_trait {
+                    panic!("impl blocks must define fn bodies");
+                }
+
+                writeln!(fmt, ";")
+            }
         }
+    }
 
-        if is_trait {
+    /// Returns the receiver of the function, if it takes `self`.
+    pub(crate) fn get_receiver(&self) -> Option<&Receiver> {
+        self.arg_self.as_ref()
+    }
+
+    /// Returns whether the arguments or the return type of the function use
+    /// `Self` itself, rather than one of its associated items such as
+    /// `Self::Error`.
+    pub(crate) fn uses_self_type(&self) -> bool {
+        let uses_self = |ty: &Type| {
+            let ty = ty.to_string();
+            ty.match_indices("Self").any(|(i, _)| {
+                let before = ty[..i].chars().next_back();
+                let after = &ty[i + "Self".len()..];
+                !before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+                    && !after.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+                    && !after.starts_with("::")
+            })
+        };
+
+        self.args.iter().any(|arg| uses_self(arg.ty())) || self.ret.as_ref().is_some_and(uses_self)
+    }
+
+    /// Returns why the function keeps its trait from being used as `dyn
+    /// Trait`, if it does.
+    ///
+    /// Functions bounded with `where Self: Sized` are left out of trait
+    /// objects, so they never do.
+    pub(crate) fn dyn_incompatibility(&self) -> Option<&'static str> {
+        let is_impl_trait = |ty: &Type| ty.to_string().contains("impl ");
+
+        if self.bounds.iter().any(Bound::is_self_sized) {
+            None
+        } else if self.arg_self.is_none() {
+            Some("has no `self` receiver")
+        } else if !self.generics.is_empty() || self.args.iter().any(|arg| is_impl_trait(arg.ty())) {
+            Some("has generic parameters")
+        } else if self.uses_self_type() {
+            Some("takes or returns `Self`")
+        } else if self.r#async {
+            Some("is `async`")
+        } else if self.ret.as_ref().is_some_and(is_impl_trait) {
+            Some("returns `impl Trait`")
+        } else {
+            None
+        }
+    }
+
+    /// Return a copy of the function for an impl block, with a body calling
+    /// the same function on `receiver`, e.g. `(**self)`, or on `path` if the
+    /// function doesn't take `self`.
+    ///
+    /// The docs of the function are not copied.
+    pub(crate) fn forwarding(&self, receiver: &str, path: &str) -> Function {
+        let args: Vec<&str> = self.args.iter().map(|arg| arg.name().as_str()).collect();
+        let mut call = match self.arg_self {
+            Some(_) => format!("{}.{}({})", receiver, self.name, args.join(", ")),
+            None => format!("{}::{}({})", path, self.name, args.join(", ")),
+        };
+        if self.r#async {
+            call.push_str(".await");
+        }
+        if self.r#unsafe {
+            call = format!("unsafe {{ {} }}", call);
+        }
+
+        let mut ret = self.clone();
+        ret.docs = None;
+        ret.doc_sections = DocSections::default();
+        ret.signature_only = false;
+        ret.body = Some(vec![Body::String(call)]);
+        ret
+    }
+
+    /// Formats the function as a declaration inside of an `extern` block.
+    pub(crate) fn fmt_foreign(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        assert!(
+            self.signature_only || self.body.iter().all(Vec::is_empty),
+            "fns in extern blocks can't have bodies"
+        );
+        assert!(
+            !self.no_mangle && self.export_name.is_none(),
+            "fns in extern blocks can't be exported"
+        );
+
+        self.fmt_head(false, fmt)?;
+        writeln!(fmt, ";")
+    }
+
+    fn fmt_head(&self, with_abi: bool, fmt: &mut Formatter<'_>) -> fmt::Result {
+        if with_abi {
             assert!(
-                self.vis.is_none(),
-                "trait fns do not have visibility modifiers"
+                !self.track_caller || self.extern_abi.iter().all(|abi| abi == "Rust"),
+                "`#[track_caller]` requires the Rust ABI"
             );
         }
 
+        if !self.doc_sections.is_empty() {
+            self.doc_sections.fmt(self.docs.as_ref(), fmt)?;
+        } else if let Some(ref docs) = self.docs {
+            docs.fmt(fmt)?;
+        }
+
+        for attr in self.attributes() {
+            writeln!(fmt, "#[{}]", attr)?;
+        }
+
         if let Some(ref vis) = self.vis {
             write!(fmt, "{} ", vis)?;
         }
 
-        if let Some(ref extern_abi) = self.extern_abi {
-            write!(fmt, "extern \"{extern_abi}\" ", extern_abi = extern_abi)?;
+        // qualifiers must be in the order `const async unsafe extern`
+        if self.r#const {
+            write!(fmt, "const ")?;
         }
 
         if self.r#async {
             write!(fmt, "async ")?;
         }
 
+        if self.r#unsafe {
+            write!(fmt, "unsafe ")?;
+        }
+
+        if let Some(ref extern_abi) = self.extern_abi.as_ref().filter(|_| with_abi) {
+            write!(fmt, "extern \"{extern_abi}\" ", extern_abi = extern_abi)?;
+        }
+
         write!(fmt, "fn {}", self.name)?;
-        fmt_generics(&self.generics, fmt)?;
+        let generics = self
+            .lifetimes
+            .iter()
+            .map(|lifetime| format!("'{}", lifetime.name()))
+            .chain(self.generics.iter().cloned())
+            .collect::<Vec<_>>();
+        fmt_generics(&generics, fmt)?;
 
         write!(fmt, "(")?;
 
         if let Some(ref s) = self.arg_self {
-            write!(fmt, "{}", s)?;
+            s.fmt(fmt)?;
         }
 
         for (i, arg) in self.args.iter().enumerate() {
@@ -262,34 +1065,29 @@ impl Function {
                 write!(fmt, ", ")?;
             }
 
-            write!(fmt, "{}: ", arg.name)?;
-            arg.ty.fmt(fmt)?;
+            arg.fmt(fmt)?;
+        }
+
+        if self.variadic {
+            debug_assert!(
+                !with_abi || self.extern_abi.is_some(),
+                "variadic fns must have an extern ABI"
+            );
+
+            if self.arg_self.is_some() || !self.args.is_empty() {
+                write!(fmt, ", ")?;
+            }
+
+            write!(fmt, "...")?;
         }
 
         write!(fmt, ")")?;
 
-        if let Some(ref ret) = self.ret {
+        if let Some(ret) = self.ret.as_ref().filter(|ret| !ret.is_unit()) {
             write!(fmt, " -> ")?;
             ret.fmt(fmt)?;
         }
 
-        fmt_bounds(&self.bounds, fmt)?;
-
-        match self.body {
-            Some(ref body) => fmt.block(|fmt| {
-                for b in body {
-                    b.fmt(fmt)?;
-                }
-
-                Ok(())
-            }),
-            None => {
-                if !is_trait {
-                    panic!("impl blocks must define fn bodies");
-                }
-
-                write!(fmt, ";\n")
-            }
-        }
+        fmt_bounds(&self.bounds, fmt)
     }
 }
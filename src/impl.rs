@@ -1,11 +1,24 @@
+use std::cmp::Ordering;
 use std::fmt::{self, Write};
 
+use crate::associated_type::ImplAssociatedType;
+use crate::body;
 use crate::bound::Bound;
+use crate::cfg::Cfg;
+use crate::docs::{self, Docs};
 use crate::field::Field;
 use crate::formatter::{fmt_bounds, fmt_generics, Formatter};
 use crate::function::Function;
+use crate::lifetime::Lifetime;
 
-use crate::r#type::Type;
+use crate::r#type::{param_order, Type};
+
+/// An item of the body of an impl block.
+#[derive(Debug, Clone)]
+enum ImplItem {
+    Fn(Box<Function>),
+    Macro(String),
+}
 
 /// Defines an impl block.
 #[derive(Debug, Clone)]
@@ -13,6 +26,9 @@ pub struct Impl {
     /// The struct being implemented
     target: Type,
 
+    /// Impl level lifetimes
+    lifetimes: Vec<Lifetime>,
+
     /// Impl level generics
     generics: Vec<String>,
 
@@ -23,14 +39,33 @@ pub struct Impl {
     assoc_csts: Vec<Field>,
 
     /// Associated types
-    assoc_tys: Vec<Field>,
+    assoc_tys: Vec<ImplAssociatedType>,
 
     /// Bounds
     bounds: Vec<Bound>,
 
-    fns: Vec<Function>,
+    /// Functions and macro invocations, in order
+    items: Vec<ImplItem>,
 
     macros: Vec<String>,
+
+    /// Impl block documentation
+    docs: Option<Docs>,
+
+    /// Impl block attributes, e.g., `#[automatically_derived]`.
+    attributes: Vec<String>,
+
+    /// Configuration predicates of `#[cfg(...)]` attributes
+    cfgs: Vec<Cfg>,
+
+    /// Whether the impl block is `unsafe`
+    r#unsafe: bool,
+
+    /// Whether the impl block is a negative impl, e.g. `impl !Send for Foo`
+    negative: bool,
+
+    /// Whether items are formatted sorted by name
+    sorted: bool,
 }
 
 impl Impl {
@@ -41,14 +76,55 @@ impl Impl {
     {
         Impl {
             target: target.into(),
+            lifetimes: Vec::new(),
             generics: Vec::new(),
             impl_trait: None,
             assoc_csts: Vec::new(),
             assoc_tys: Vec::new(),
             bounds: Vec::new(),
-            fns: Vec::new(),
+            items: Vec::new(),
             macros: Vec::new(),
+            docs: None,
+            attributes: Vec::new(),
+            cfgs: Vec::new(),
+            r#unsafe: false,
+            negative: false,
+            sorted: false,
+        }
+    }
+
+    /// Return a blanket impl of `impl_trait` for the generic `param`, e.g.
+    /// `impl<T: Read + ?Sized> ReadExt for T`.
+    ///
+    /// The generic is declared on the impl with `bounds` and used as the
+    /// target of the impl.
+    ///
+    /// ```
+    /// use codegen::Impl;
+    ///
+    /// // `impl<T: std::io::Read + ?Sized> ReadExt for T {}`
+    /// let imp = Impl::blanket("T", "ReadExt", ["std::io::Read", "?Sized"]);
+    /// ```
+    pub fn blanket<T, I>(param: impl ToString, impl_trait: T, bounds: I) -> Self
+    where
+        T: Into<Type>,
+        I: IntoIterator,
+        I::Item: Into<Type>,
+    {
+        let param = param.to_string();
+        let bounds: Vec<String> = bounds
+            .into_iter()
+            .map(|bound| bound.into().to_string())
+            .collect();
+
+        let mut imp = Impl::new(&param);
+        imp.impl_trait(impl_trait);
+        if bounds.is_empty() {
+            imp.generic(param);
+        } else {
+            imp.generic(format!("{}: {}", param, bounds.join(" + ")));
         }
+        imp
     }
 
     /// Returns the target of the impl
@@ -56,26 +132,82 @@ impl Impl {
         &self.target
     }
 
+    /// Returns the trait that the impl block is implementing, if any
+    pub fn get_impl_trait(&self) -> Option<&Type> {
+        self.impl_trait.as_ref()
+    }
+
+    /// Returns whether the impl block is the impl of `impl_trait` for
+    /// `target`, or the inherent impl of `target` if `impl_trait` is `None`.
+    pub(crate) fn is_impl_of(&self, target: &Type, impl_trait: Option<&Type>) -> bool {
+        self.target == *target && self.impl_trait.as_ref() == impl_trait
+    }
+
     /// Returns the key for sorting
     pub fn key_for_sorting(&self) -> &Type {
-        if self.target.generics().is_empty() || self.impl_trait.is_none() {
-            &self.target
-        } else {
-            let impl_type = self.impl_trait.as_ref().unwrap();
-            if impl_type.name() == "From" {
-                impl_type.generics().get(0).unwrap()
-            } else {
-                impl_type
+        match self.impl_trait {
+            Some(ref impl_type) if !self.target.generics().is_empty() => {
+                if impl_type.name() == "From" {
+                    impl_type.generics().first().unwrap()
+                } else {
+                    impl_type
+                }
             }
+            _ => &self.target,
         }
     }
 
+    /// Add a lifetime parameter to the impl block.
+    ///
+    /// Lifetimes are formatted before the other generics, regardless of the
+    /// order they are added in, and are bounded like generics:
+    ///
+    /// ```
+    /// use codegen::Impl;
+    ///
+    /// // `impl<'a, 'b, T> Parser<'a> for Scanner<'b, T> where 'b: 'a`
+    /// let mut imp = Impl::new("Scanner");
+    /// imp.generic("T")
+    ///     .lifetime("'a")
+    ///     .lifetime("'b")
+    ///     .target_generic("'b")
+    ///     .target_generic("T")
+    ///     .impl_trait("Parser<'a>")
+    ///     .bound("'b", "'a");
+    /// ```
+    pub fn lifetime(&mut self, lifetime: impl Into<Lifetime>) -> &mut Self {
+        self.lifetimes.push(lifetime.into());
+        self
+    }
 
     /// Add a generic to the impl block.
     ///
     /// This adds the generic for the block (`impl<T>`) and not the target type.
+    /// Generics starting with `'` are added as lifetimes, see [`lifetime`].
+    ///
+    /// [`lifetime`]: #method.lifetime
     pub fn generic(&mut self, name: impl ToString) -> &mut Self {
-        self.generics.push(name.to_string());
+        let name = name.to_string();
+        if name.starts_with('\'') {
+            return self.lifetime(name);
+        }
+
+        self.generics.push(name);
+        self
+    }
+
+    /// Add a const generic to the impl block, e.g. `impl<const N: usize>`.
+    ///
+    /// Const generics are formatted after the lifetimes and type generics.
+    /// Use [`target_generic`] to pass the parameter to the target type.
+    ///
+    /// [`target_generic`]: #method.target_generic
+    pub fn const_generic<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.generics
+            .push(format!("const {}: {}", name.to_string(), ty.into()));
         self
     }
 
@@ -97,6 +229,119 @@ impl Impl {
         self
     }
 
+    /// Set whether the impl block is `unsafe` or not, for implementations of
+    /// unsafe traits such as `Send`.
+    ///
+    /// ```
+    /// use codegen::Impl;
+    ///
+    /// // `unsafe impl Send for Handle {}`
+    /// let mut imp = Impl::new("Handle");
+    /// imp.impl_trait("Send").set_unsafe(true);
+    /// assert!(imp.is_unsafe());
+    /// ```
+    pub fn set_unsafe(&mut self, r#unsafe: bool) -> &mut Self {
+        self.r#unsafe = r#unsafe;
+        self
+    }
+
+    /// Returns whether the impl block is `unsafe` or not
+    pub fn is_unsafe(&self) -> bool {
+        self.r#unsafe
+    }
+
+    /// Set whether the impl block is a negative impl, e.g. `impl !Send for
+    /// Foo {}`.
+    ///
+    /// Negative impls are unstable and need `#![feature(negative_impls)]` in
+    /// the generated crate.
+    ///
+    /// # Panics
+    ///
+    /// Formatting a negative impl panics if it doesn't implement a trait, if
+    /// it is `unsafe`, or if it has associated items or functions.
+    pub fn set_negative(&mut self, negative: bool) -> &mut Self {
+        self.negative = negative;
+        self
+    }
+
+    /// Returns whether the impl block is a negative impl or not
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Set whether the associated constants, associated types and functions
+    /// of the impl block are formatted sorted by name, instead of in the
+    /// order they were added.
+    ///
+    /// Each kind of item stays grouped, and macro invocations are formatted
+    /// after the functions in the order they were added.
+    ///
+    /// ```
+    /// use codegen::Impl;
+    ///
+    /// let mut imp = Impl::new("Foo");
+    /// imp.set_sorted(true);
+    /// imp.new_fn("up");
+    /// imp.new_fn("down");
+    ///
+    /// // `down` is formatted before `up`
+    /// assert!(imp.is_sorted());
+    /// ```
+    pub fn set_sorted(&mut self, sorted: bool) -> &mut Self {
+        self.sorted = sorted;
+        self
+    }
+
+    /// Returns whether the items of the impl block are formatted sorted by
+    /// name or not
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// Set the impl block documentation.
+    pub fn doc(&mut self, docs: impl ToString) -> &mut Self {
+        self.docs = Some(Docs::new(docs));
+        self
+    }
+
+    /// Add an attribute to the impl block.
+    ///
+    /// ```
+    /// use codegen::Impl;
+    ///
+    /// let mut imp = Impl::new("Foo");
+    ///
+    /// // add a `#[automatically_derived]` attribute
+    /// imp.impl_trait("Clone").attr("automatically_derived");
+    /// ```
+    ///
+    /// Adding the same attribute twice has no effect.
+    pub fn attr(&mut self, attribute: &str) -> &mut Self {
+        if !self.attributes.iter().any(|attr| attr == attribute) {
+            self.attributes.push(attribute.to_string());
+        }
+        self
+    }
+
+    /// Only compile the impl block when the configuration predicate holds.
+    ///
+    /// ```
+    /// use codegen::{Cfg, Impl};
+    ///
+    /// let mut imp = Impl::new("Foo");
+    ///
+    /// // add a `#[cfg(feature = "serde")]` attribute
+    /// imp.impl_trait("serde::Serialize").cfg(Cfg::feature("serde"));
+    /// ```
+    ///
+    /// Calling this several times adds one `#[cfg(...)]` per predicate, so
+    /// all of them must hold.
+    pub fn cfg(&mut self, predicate: impl Into<Cfg>) -> &mut Self {
+        self.cfgs.push(predicate.into());
+        self
+    }
+
     /// Add a macro to the impl block (e.g. `"#[async_trait]"`)
     pub fn r#macro(&mut self, r#macro: impl ToString) -> &mut Self {
         self.macros.push(r#macro.to_string());
@@ -114,35 +359,74 @@ impl Impl {
     where
         T: Into<Type>,
     {
-        self.assoc_csts.push(Field {
-            name: name.to_string(),
-            ty: ty.into(),
-            documentation: String::new(),
-            annotation: Vec::new(),
-            value: value.to_string(),
-            visibility: Some(visibility.to_string()),
-        });
+        let mut cst = Field::new(name, ty);
+        cst.value = value.to_string();
+        cst.visibility = Some(visibility.to_string());
+        self.assoc_csts.push(cst);
 
         self
     }
 
+    /// Push a new associated constant, returning a mutable reference to it to
+    /// set its visibility, docs and attributes.
+    ///
+    /// ```
+    /// use codegen::Impl;
+    ///
+    /// let mut imp = Impl::new("Ping");
+    /// imp.new_const("WIRE_ID", "u16", "0x01")
+    ///     .vis("pub")
+    ///     .doc("The identifier of the message on the wire.");
+    /// ```
+    pub fn new_const<T>(&mut self, name: impl ToString, ty: T, value: impl ToString) -> &mut Field
+    where
+        T: Into<Type>,
+    {
+        let mut cst = Field::new(name, ty);
+        cst.value = value.to_string();
+        self.push_const(cst);
+        self.assoc_csts.last_mut().unwrap()
+    }
+
+    /// Push an associated constant, formatted as `const NAME: Ty = value;`
+    /// from the name, type and value of `item`.
+    pub fn push_const(&mut self, item: Field) -> &mut Self {
+        self.assoc_csts.push(item);
+        self
+    }
+
     /// Set an associated type.
     pub fn associate_type<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
     where
         T: Into<Type>,
     {
-        self.assoc_tys.push(Field {
-            name: name.to_string(),
-            ty: ty.into(),
-            documentation: String::new(),
-            annotation: Vec::new(),
-            value: String::new(),
-            visibility: None,
-        });
+        self.assoc_tys.push(ImplAssociatedType::new(name, ty));
 
         self
     }
 
+    /// Push a new associated type, returning a mutable reference to it to set
+    /// its generics and `where` clause.
+    ///
+    /// ```
+    /// use codegen::Impl;
+    ///
+    /// let mut imp = Impl::new("Buffer");
+    /// imp.impl_trait("Container");
+    ///
+    /// // `type Iter<'a> = std::slice::Iter<'a, u8> where Self: 'a;`
+    /// imp.new_associated_type("Iter", "std::slice::Iter<'a, u8>")
+    ///     .generic("'a")
+    ///     .where_bound("Self: 'a");
+    /// ```
+    pub fn new_associated_type<T>(&mut self, name: impl ToString, ty: T) -> &mut ImplAssociatedType
+    where
+        T: Into<Type>,
+    {
+        self.assoc_tys.push(ImplAssociatedType::new(name, ty));
+        self.assoc_tys.last_mut().unwrap()
+    }
+
     /// Add a `where` bound to the impl block.
     pub fn bound<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
     where
@@ -158,25 +442,111 @@ impl Impl {
     /// Push a new function definition, returning a mutable reference to it.
     pub fn new_fn(&mut self, name: impl ToString) -> &mut Function {
         self.push_fn(Function::new(name));
-        self.fns.last_mut().unwrap()
+        match self.items.last_mut() {
+            Some(ImplItem::Fn(func)) => func,
+            _ => unreachable!(),
+        }
     }
 
     /// Push a function definition.
     pub fn push_fn(&mut self, item: Function) -> &mut Self {
-        self.fns.push(item);
+        self.items.push(ImplItem::Fn(Box::new(item)));
         self
     }
 
+    /// Push a macro invocation to the body of the impl block, e.g.
+    /// `impl_ops!(Meters);`, between the functions pushed before and after
+    /// it.
+    ///
+    /// The leading and trailing blank lines and the common indentation of a
+    /// multi-line invocation are removed, and its lines are indented with the
+    /// body.
+    ///
+    /// ```
+    /// use codegen::Impl;
+    ///
+    /// let mut imp = Impl::new("Stack");
+    /// imp.macro_item(r#"
+    ///     delegate! {
+    ///         to self.inner {
+    ///             pub fn len(&self) -> usize;
+    ///         }
+    ///     }
+    /// "#);
+    /// ```
+    pub fn macro_item(&mut self, invocation: &str) -> &mut Self {
+        self.items
+            .push(ImplItem::Macro(body::dedent_lines(invocation).join("\n")));
+        self
+    }
+
+    /// Call `f` on every type used by the impl block, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.target.visit_mut(f);
+        if let Some(ref mut impl_trait) = self.impl_trait {
+            impl_trait.visit_mut(f);
+        }
+        for cst in &mut self.assoc_csts {
+            cst.ty.visit_mut(f);
+        }
+        for ty in &mut self.assoc_tys {
+            ty.visit_types_mut(f);
+        }
+        for bound in &mut self.bounds {
+            bound.visit_types_mut(f);
+        }
+        for item in &mut self.items {
+            if let ImplItem::Fn(func) = item {
+                func.visit_types_mut(f);
+            }
+        }
+    }
+
     /// Formats the impl block using the given formatter.
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            assert!(
+                self.impl_trait.is_some(),
+                "negative impls must implement a trait"
+            );
+            assert!(!self.r#unsafe, "negative impls can't be unsafe");
+            assert!(
+                self.assoc_csts.is_empty() && self.assoc_tys.is_empty() && self.items.is_empty(),
+                "negative impls can't have items"
+            );
+        }
+
+        if let Some(ref docs) = self.docs {
+            docs.fmt(fmt)?;
+        }
+        for cfg in &self.cfgs {
+            writeln!(fmt, "#[cfg({})]", cfg)?;
+        }
+        for attr in &self.attributes {
+            writeln!(fmt, "#[{}]", attr)?;
+        }
         for m in self.macros.iter() {
-            write!(fmt, "{}\n", m)?;
+            writeln!(fmt, "{}", m)?;
+        }
+        if self.r#unsafe {
+            write!(fmt, "unsafe ")?;
         }
         write!(fmt, "impl")?;
-        fmt_generics(&self.generics[..], fmt)?;
+        let mut generics = self.generics.clone();
+        generics.sort_by_key(|generic| param_order(generic));
+        let generics = self
+            .lifetimes
+            .iter()
+            .map(|lifetime| format!("'{}", lifetime.name()))
+            .chain(generics)
+            .collect::<Vec<_>>();
+        fmt_generics(&generics[..], fmt)?;
 
         if let Some(ref t) = self.impl_trait {
             write!(fmt, " ")?;
+            if self.negative {
+                write!(fmt, "!")?;
+            }
             t.fmt(fmt)?;
             write!(fmt, " for")?;
         }
@@ -186,34 +556,61 @@ impl Impl {
 
         fmt_bounds(&self.bounds, fmt)?;
 
+        // marker trait impls, e.g. `unsafe impl Send for Foo {}`
+        if self.assoc_csts.is_empty() && self.assoc_tys.is_empty() && self.items.is_empty() {
+            if !fmt.is_start_of_line() {
+                write!(fmt, " ")?;
+            }
+            return writeln!(fmt, "{{}}");
+        }
+
+        let mut assoc_csts: Vec<&Field> = self.assoc_csts.iter().collect();
+        let mut assoc_tys: Vec<&ImplAssociatedType> = self.assoc_tys.iter().collect();
+        let mut items: Vec<&ImplItem> = self.items.iter().collect();
+        if self.sorted {
+            assoc_csts.sort_by(|a, b| a.name.cmp(&b.name));
+            assoc_tys.sort_by(|a, b| a.name().cmp(b.name()));
+            items.sort_by(|a, b| match (a, b) {
+                (ImplItem::Fn(a), ImplItem::Fn(b)) => a.name().cmp(b.name()),
+                (ImplItem::Fn(_), ImplItem::Macro(_)) => Ordering::Less,
+                (ImplItem::Macro(_), ImplItem::Fn(_)) => Ordering::Greater,
+                (ImplItem::Macro(_), ImplItem::Macro(_)) => Ordering::Equal,
+            });
+        }
+
         fmt.block(|fmt| {
             // format associated constants
-            if !self.assoc_csts.is_empty() {
-                for cst in &self.assoc_csts {
+            if !assoc_csts.is_empty() {
+                for cst in &assoc_csts {
+                    docs::fmt_wrapped(&cst.documentation, fmt)?;
+                    for ann in &cst.annotation {
+                        writeln!(fmt, "{}", ann)?;
+                    }
                     if let Some(vis) = &cst.visibility {
                         write!(fmt, "{} ", vis)?;
                     }
                     write!(fmt, "const {}: ", cst.name)?;
                     cst.ty.fmt(fmt)?;
-                    write!(fmt, " = {};\n", cst.value)?;
+                    writeln!(fmt, " = {};", cst.value)?;
                 }
             }
 
             // format associated types
-            if !self.assoc_tys.is_empty() {
-                for ty in &self.assoc_tys {
-                    write!(fmt, "type {} = ", ty.name)?;
-                    ty.ty.fmt(fmt)?;
-                    write!(fmt, ";\n")?;
+            if !assoc_tys.is_empty() {
+                for ty in &assoc_tys {
+                    ty.fmt(fmt)?;
                 }
             }
 
-            for (i, func) in self.fns.iter().enumerate() {
-                if i != 0 || !self.assoc_tys.is_empty() {
-                    write!(fmt, "\n")?;
+            for (i, item) in items.iter().enumerate() {
+                if i != 0 || !self.assoc_tys.is_empty() || !self.assoc_csts.is_empty() {
+                    writeln!(fmt)?;
                 }
 
-                func.fmt(false, fmt)?;
+                match item {
+                    ImplItem::Fn(func) => func.fmt(false, fmt)?,
+                    ImplItem::Macro(invocation) => writeln!(fmt, "{}", invocation)?,
+                }
             }
 
             Ok(())
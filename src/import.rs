@@ -1,6 +1,7 @@
 /// Defines an import (`use` statement).
 #[derive(Debug, Clone)]
 pub struct Import {
+    #[allow(dead_code)]
     line: String,
 
     /// Function visibility
@@ -47,4 +48,4 @@ fn parse_alias() {
         let import = Import::new("foo", "Bar", None);
         assert_eq!(import.line, "foo::Bar");
     }
-}
\ No newline at end of file
+}
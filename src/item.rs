@@ -1,3 +1,5 @@
+use crate::bench_group::BenchGroup;
+use crate::extern_block::ExternBlock;
 use crate::function::Function;
 use crate::module::Module;
 
@@ -11,10 +13,12 @@ use crate::r#type_alias::TypeAlias;
 pub enum Item {
     Module(Module),
     Struct(Struct),
-    Function(Function),
+    Function(Box<Function>),
     Trait(Trait),
     Enum(Enum),
     Impl(Impl),
     Raw(String),
     TypeAlias(TypeAlias),
+    ExternBlock(ExternBlock),
+    BenchGroup(BenchGroup),
 }
@@ -23,20 +23,29 @@
 //! println!("{}", scope.to_string());
 //! ```
 
+mod arg;
 mod associated_const;
 mod associated_type;
+mod bench_group;
 mod block;
 mod body;
 mod bound;
+mod builder_gen;
+mod cfg;
+mod closure;
 mod docs;
+mod extern_block;
 mod field;
 mod fields;
 mod formatter;
 mod function;
 mod import;
 mod item;
+mod lifetime;
 mod module;
+mod receiver;
 mod scope;
+mod tokens;
 mod type_def;
 mod variant;
 
@@ -47,16 +56,24 @@ mod r#trait;
 mod r#type;
 mod type_alias;
 
+pub use arg::*;
 pub use associated_const::*;
 pub use associated_type::*;
+pub use bench_group::*;
 pub use block::*;
+pub use builder_gen::*;
+pub use cfg::*;
+pub use closure::*;
+pub use extern_block::*;
 pub use field::*;
 pub use formatter::*;
 pub use function::*;
 pub use import::*;
-pub use type_alias::*;
+pub use lifetime::*;
 pub use module::*;
+pub use receiver::*;
 pub use scope::*;
+pub use type_alias::*;
 pub use variant::*;
 
 pub use r#enum::*;
@@ -0,0 +1,51 @@
+use std::fmt::{self, Write};
+
+use crate::formatter::Formatter;
+
+/// Defines a lifetime, e.g. `'a` or `'static`.
+#[derive(Debug, Clone)]
+pub struct Lifetime {
+    name: String,
+}
+
+impl Lifetime {
+    /// Return a new lifetime with the given name.
+    ///
+    /// The leading `'` is optional, so both `"a"` and `"'a"` produce `'a`.
+    pub fn new(name: impl ToString) -> Self {
+        let name = name.to_string();
+        let name = name.trim_start_matches('\'');
+
+        Lifetime {
+            name: name.to_string(),
+        }
+    }
+
+    /// Returns the name of the lifetime, without the leading `'`.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Formats the lifetime using the given formatter.
+    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        write!(fmt, "'{}", self.name)
+    }
+}
+
+impl<'a> From<&'a str> for Lifetime {
+    fn from(src: &'a str) -> Self {
+        Lifetime::new(src)
+    }
+}
+
+impl From<String> for Lifetime {
+    fn from(src: String) -> Self {
+        Lifetime::new(src)
+    }
+}
+
+impl<'a> From<&'a Lifetime> for Lifetime {
+    fn from(src: &'a Lifetime) -> Self {
+        src.clone()
+    }
+}
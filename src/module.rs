@@ -1,6 +1,7 @@
 use std::fmt::{self, Display, Write};
 
 use crate::docs::Docs;
+use crate::extern_block::ExternBlock;
 use crate::formatter::Formatter;
 use crate::function::Function;
 use crate::scope::Scope;
@@ -9,6 +10,7 @@ use crate::r#enum::Enum;
 use crate::r#impl::Impl;
 use crate::r#struct::Struct;
 use crate::r#trait::Trait;
+use crate::r#type::Type;
 
 /// Defines a module.
 #[derive(Debug, Clone)]
@@ -52,11 +54,22 @@ impl Module {
         self
     }
 
+    /// Set the module documentation.
+    pub fn doc(&mut self, docs: impl ToString) -> &mut Self {
+        self.docs = Some(Docs::new(docs));
+        self
+    }
+
     /// Import a type into the module's scope.
     ///
     /// This results in a new `use` statement being added to the beginning of the
     /// module.
-    pub fn import(&mut self, path: impl ToString, ty: impl ToString, alias: Option<&str>) -> &mut Self {
+    pub fn import(
+        &mut self,
+        path: impl ToString,
+        ty: impl ToString,
+        alias: Option<&str>,
+    ) -> &mut Self {
         self.scope.new_import(path, ty, alias);
         self
     }
@@ -108,6 +121,27 @@ impl Module {
         self.scope.get_or_new_module(name)
     }
 
+    /// Returns a reference to the impl of `impl_trait` for `target`, or to
+    /// the inherent impl of `target` if `impl_trait` is `None`, if it exists
+    /// in this module.
+    pub fn get_impl(&self, target: &str, impl_trait: Option<&str>) -> Option<&Impl> {
+        self.scope.get_impl(target, impl_trait)
+    }
+
+    /// Returns a mutable reference to the impl of `impl_trait` for `target`,
+    /// or to the inherent impl of `target` if `impl_trait` is `None`, if it
+    /// exists in this module.
+    pub fn get_impl_mut(&mut self, target: &str, impl_trait: Option<&str>) -> Option<&mut Impl> {
+        self.scope.get_impl_mut(target, impl_trait)
+    }
+
+    /// Returns a mutable reference to the impl of `impl_trait` for `target`,
+    /// or to the inherent impl of `target` if `impl_trait` is `None`,
+    /// creating it if it does not exist.
+    pub fn get_or_new_impl(&mut self, target: &str, impl_trait: Option<&str>) -> &mut Impl {
+        self.scope.get_or_new_impl(target, impl_trait)
+    }
+
     /// Push a module definition.
     ///
     /// # Panics
@@ -136,6 +170,18 @@ impl Module {
         self
     }
 
+    /// Push a unit struct for each of the names, calling `f` on each of them
+    /// to configure it.
+    pub fn push_markers<I, F>(&mut self, names: I, f: F) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+        F: FnMut(&mut Struct),
+    {
+        self.scope.push_markers(names, f);
+        self
+    }
+
     /// Push a new function definition, returning a mutable reference to it.
     pub fn new_fn(&mut self, name: impl ToString) -> &mut Function {
         self.scope.new_fn(name)
@@ -169,6 +215,15 @@ impl Module {
         self
     }
 
+    /// Push several `impl` blocks.
+    pub fn push_impls<I>(&mut self, items: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Impl>,
+    {
+        self.scope.push_impls(items);
+        self
+    }
+
     /// Push a new trait
     pub fn new_trait(&mut self, name: impl ToString) -> &mut Trait {
         self.scope.new_trait(name)
@@ -180,10 +235,31 @@ impl Module {
         self
     }
 
+    /// Push a new `extern` block for the given ABI, returning a mutable
+    /// reference to it.
+    pub fn new_extern_block(&mut self, abi: impl ToString) -> &mut ExternBlock {
+        self.scope.new_extern_block(abi)
+    }
+
+    /// Push an `extern` block.
+    pub fn push_extern_block(&mut self, item: ExternBlock) -> &mut Self {
+        self.scope.push_extern_block(item);
+        self
+    }
+
+    /// Call `f` on every type used by the module, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.scope.visit_types_mut(f);
+    }
+
     /// Formats the module using the given formatter.
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(ref docs) = self.docs {
+            docs.fmt(fmt)?;
+        }
+
         for attr in &self.attributes {
-            writeln!(fmt, "#[{}] ", attr)?;
+            writeln!(fmt, "#[{}]", attr)?;
         }
 
         if let Some(ref vis) = self.vis {
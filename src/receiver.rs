@@ -0,0 +1,60 @@
+use std::fmt::{self, Write};
+
+use crate::formatter::Formatter;
+use crate::lifetime::Lifetime;
+
+use crate::r#type::Type;
+
+/// Defines the `self` argument of a method.
+#[derive(Debug, Clone)]
+pub enum Receiver {
+    /// `self`
+    Value,
+
+    /// `mut self`
+    MutValue,
+
+    /// `&self`, or `&'a self` with a lifetime
+    Ref(Option<Lifetime>),
+
+    /// `&mut self`, or `&'a mut self` with a lifetime
+    RefMut(Option<Lifetime>),
+
+    /// A receiver with an explicit type, e.g. `self: Box<Self>`
+    Typed(Type),
+}
+
+impl Receiver {
+    /// Return a `self: Box<Self>` receiver.
+    pub fn boxed() -> Self {
+        Receiver::Typed(Type::new("Box<Self>"))
+    }
+
+    /// Return a `self: Pin<&mut Self>` receiver.
+    pub fn pinned() -> Self {
+        Receiver::Typed(Type::new("Pin<&mut Self>"))
+    }
+
+    /// Formats the receiver using the given formatter.
+    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Receiver::Value => write!(fmt, "self"),
+            Receiver::MutValue => write!(fmt, "mut self"),
+            Receiver::Ref(ref lifetime) | Receiver::RefMut(ref lifetime) => {
+                write!(fmt, "&")?;
+                if let Some(ref lifetime) = *lifetime {
+                    lifetime.fmt(fmt)?;
+                    write!(fmt, " ")?;
+                }
+                if let Receiver::RefMut(_) = *self {
+                    write!(fmt, "mut ")?;
+                }
+                write!(fmt, "self")
+            }
+            Receiver::Typed(ref ty) => {
+                write!(fmt, "self: ")?;
+                ty.fmt(fmt)
+            }
+        }
+    }
+}
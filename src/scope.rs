@@ -3,7 +3,9 @@ use std::fmt::{self, Debug, Display, Write};
 
 use indexmap::IndexMap;
 
+use crate::bench_group::BenchGroup;
 use crate::docs::Docs;
+use crate::extern_block::ExternBlock;
 use crate::formatter::Formatter;
 use crate::function::Function;
 use crate::import::Import;
@@ -14,6 +16,7 @@ use crate::r#enum::Enum;
 use crate::r#impl::Impl;
 use crate::r#struct::Struct;
 use crate::r#trait::Trait;
+use crate::r#type::Type;
 use crate::type_alias::TypeAlias;
 
 /// Defines a scope.
@@ -45,20 +48,30 @@ impl Scope {
     ///
     /// This results in a new `use` statement being added to the beginning of
     /// the scope.
-    pub fn new_import(&mut self, path: impl ToString, ty: impl ToString, alias: Option<&str>) -> &mut Import {
+    pub fn new_import(
+        &mut self,
+        path: impl ToString,
+        ty: impl ToString,
+        alias: Option<&str>,
+    ) -> &mut Import {
         // handle cases where the caller wants to refer to a type namespaced
         // within the containing namespace, like "a::B".
         let ty = ty.to_string();
-        let ty = ty.split("::").next().unwrap_or_else(|| ty.as_str());
+        let ty = ty.split("::").next().unwrap_or(ty.as_str());
         self.imports
             .entry(path.to_string())
-            .or_insert(IndexMap::new())
+            .or_default()
             .entry(ty.to_string())
             .or_insert_with(|| Import::new(path, ty, alias))
     }
 
     /// Push a new import (`use` statement) ad the beginning of the scope
-    pub fn push_import(&mut self, path: impl ToString, ty: impl ToString, alias: Option<&str>) -> &mut Self {
+    pub fn push_import(
+        &mut self,
+        path: impl ToString,
+        ty: impl ToString,
+        alias: Option<&str>,
+    ) -> &mut Self {
         self.new_import(path, ty, alias);
         self
     }
@@ -106,7 +119,7 @@ impl Scope {
         self.items
             .iter()
             .filter_map(|item| match item {
-                &Item::Module(ref module) if module.name == *name => Some(module),
+                Item::Module(module) if module.name == *name => Some(module),
                 _ => None,
             })
             .next()
@@ -159,6 +172,31 @@ impl Scope {
         self
     }
 
+    /// Push a unit struct for each of the names, calling `f` on each of them
+    /// to configure it.
+    ///
+    /// ```
+    /// use codegen::Scope;
+    ///
+    /// let mut scope = Scope::new();
+    ///
+    /// // `pub struct Read;` and `pub struct Write;`
+    /// scope.push_markers(["Read", "Write"], |marker| {
+    ///     marker.vis("pub");
+    /// });
+    /// ```
+    pub fn push_markers<I, F>(&mut self, names: I, mut f: F) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+        F: FnMut(&mut Struct),
+    {
+        for name in names {
+            f(self.new_struct(name));
+        }
+        self
+    }
+
     /// Push a new function definition, returning a mutable reference to it.
     pub fn new_fn(&mut self, name: impl ToString) -> &mut Function {
         self.push_fn(Function::new(name));
@@ -171,10 +209,34 @@ impl Scope {
 
     /// Push a function definition
     pub fn push_fn(&mut self, item: Function) -> &mut Self {
-        self.items.push(Item::Function(item));
+        self.items.push(Item::Function(Box::new(item)));
         self
     }
 
+    /// Push a new `main` function, returning a mutable reference to it.
+    ///
+    /// With a `runtime` such as `Some("tokio")`, the function is an `async`
+    /// entry point for it, e.g. `#[tokio::main] async fn main()`. Set a
+    /// return type to return errors from `main`:
+    ///
+    /// ```
+    /// use codegen::Scope;
+    ///
+    /// let mut scope = Scope::new();
+    /// scope
+    ///     .new_main(Some("tokio"))
+    ///     .ret("Result<(), Box<dyn std::error::Error>>")
+    ///     .line("run().await?;")
+    ///     .line("Ok(())");
+    /// ```
+    pub fn new_main(&mut self, runtime: Option<&str>) -> &mut Function {
+        let main = self.new_fn("main");
+        if let Some(runtime) = runtime {
+            main.attr(&format!("{}::main", runtime)).set_async(true);
+        }
+        main
+    }
+
     /// Push a new trait definition, returning a mutable reference to it.
     pub fn new_trait(&mut self, name: impl ToString) -> &mut Trait {
         self.push_trait(Trait::new(name));
@@ -209,7 +271,7 @@ impl Scope {
 
     /// Push a new `impl` block, returning a mutable reference to it.
     pub fn new_impl(&mut self, target: impl ToString) -> &mut Impl {
-        self.push_impl(Impl::new(target));
+        self.push_impl(Impl::new(Type::new(target)));
 
         match *self.items.last_mut().unwrap() {
             Item::Impl(ref mut v) => v,
@@ -217,12 +279,119 @@ impl Scope {
         }
     }
 
+    /// Returns a reference to the impl of `impl_trait` for `target`, or to
+    /// the inherent impl of `target` if `impl_trait` is `None`, if it exists
+    /// in this scope.
+    pub fn get_impl(&self, target: &str, impl_trait: Option<&str>) -> Option<&Impl> {
+        let target = Type::new(target);
+        let impl_trait = impl_trait.map(Type::new);
+        self.items.iter().find_map(|item| match item {
+            Item::Impl(imp) if imp.is_impl_of(&target, impl_trait.as_ref()) => Some(imp),
+            _ => None,
+        })
+    }
+
+    /// Returns a mutable reference to the impl of `impl_trait` for `target`,
+    /// or to the inherent impl of `target` if `impl_trait` is `None`, if it
+    /// exists in this scope.
+    pub fn get_impl_mut(&mut self, target: &str, impl_trait: Option<&str>) -> Option<&mut Impl> {
+        let target = Type::new(target);
+        let impl_trait = impl_trait.map(Type::new);
+        self.items.iter_mut().find_map(|item| match item {
+            Item::Impl(imp) if imp.is_impl_of(&target, impl_trait.as_ref()) => Some(imp),
+            _ => None,
+        })
+    }
+
+    /// Returns a mutable reference to the impl of `impl_trait` for `target`,
+    /// or to the inherent impl of `target` if `impl_trait` is `None`,
+    /// creating it if it does not exist.
+    ///
+    /// This lets independent generation passes push functions to the same
+    /// impl block.
+    ///
+    /// ```
+    /// use codegen::Scope;
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.get_or_new_impl("User", None).new_fn("id");
+    /// scope.get_or_new_impl("User", None).new_fn("name");
+    /// scope.get_or_new_impl("User", Some("Clone")).new_fn("clone");
+    /// ```
+    pub fn get_or_new_impl(&mut self, target: &str, impl_trait: Option<&str>) -> &mut Impl {
+        if self.get_impl(target, impl_trait).is_some() {
+            return self.get_impl_mut(target, impl_trait).unwrap();
+        }
+
+        let imp = self.new_impl(target);
+        if let Some(impl_trait) = impl_trait {
+            imp.impl_trait(impl_trait);
+        }
+        imp
+    }
+
     /// Push an `impl` block.
     pub fn push_impl(&mut self, item: Impl) -> &mut Self {
         self.items.push(Item::Impl(item));
         self
     }
 
+    /// Push several `impl` blocks, e.g. the ones returned by
+    /// [`Enum::generate_from_impls`].
+    ///
+    /// [`Enum::generate_from_impls`]: struct.Enum.html#method.generate_from_impls
+    pub fn push_impls<I>(&mut self, items: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Impl>,
+    {
+        for item in items {
+            self.push_impl(item);
+        }
+        self
+    }
+
+    /// Push a new `extern` block for the given ABI, returning a mutable
+    /// reference to it.
+    pub fn new_extern_block(&mut self, abi: impl ToString) -> &mut ExternBlock {
+        self.push_extern_block(ExternBlock::new(abi));
+
+        match *self.items.last_mut().unwrap() {
+            Item::ExternBlock(ref mut v) => v,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Push an `extern` block.
+    pub fn push_extern_block(&mut self, item: ExternBlock) -> &mut Self {
+        self.items.push(Item::ExternBlock(item));
+        self
+    }
+
+    /// Push a new group of criterion benchmarks, returning a mutable
+    /// reference to it.
+    ///
+    /// This imports `criterion_group`, `criterion_main` and `Criterion` from
+    /// `criterion`, and formats `criterion_main!` for all of the groups of the
+    /// scope after its other items.
+    pub fn new_bench_group(&mut self, name: impl ToString) -> &mut BenchGroup {
+        self.push_bench_group(BenchGroup::new(name));
+
+        match *self.items.last_mut().unwrap() {
+            Item::BenchGroup(ref mut v) => v,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Push a group of criterion benchmarks.
+    pub fn push_bench_group(&mut self, item: BenchGroup) -> &mut Self {
+        for name in ["criterion_group", "criterion_main", "Criterion"] {
+            self.new_import("criterion", name, None);
+        }
+
+        self.items.push(Item::BenchGroup(item));
+        self
+    }
+
     /// Push a raw string to the scope.
     ///
     /// This string will be included verbatim in the formatted string.
@@ -247,7 +416,54 @@ impl Scope {
         self
     }
 
+    /// Call `f` on every type used in the scope, including the types nested
+    /// in other types and the types used by items of nested modules.
+    ///
+    /// Types are visited from the inside out, so `f` sees `T` before
+    /// `Vec<T>`. The names and generic parameters declared by items, e.g.
+    /// `Foo<T>` in `struct Foo<T>`, and raw items are not visited.
+    ///
+    /// ```
+    /// use codegen::Scope;
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.new_struct("Foo").field("bar", "Vec<OldName>");
+    ///
+    /// scope.map_types(|ty| {
+    ///     if ty.name() == "OldName" {
+    ///         ty.set_name("new::NewName");
+    ///     }
+    /// });
+    ///
+    /// assert!(scope.to_string().contains("bar: Vec<new::NewName>,"));
+    /// ```
+    pub fn map_types<F>(&mut self, mut f: F) -> &mut Self
+    where
+        F: FnMut(&mut Type),
+    {
+        self.visit_types_mut(&mut f);
+        self
+    }
+
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        for item in &mut self.items {
+            match *item {
+                Item::Module(ref mut v) => v.visit_types_mut(f),
+                Item::Struct(ref mut v) => v.visit_types_mut(f),
+                Item::Function(ref mut v) => v.visit_types_mut(f),
+                Item::Trait(ref mut v) => v.visit_types_mut(f),
+                Item::Enum(ref mut v) => v.visit_types_mut(f),
+                Item::Impl(ref mut v) => v.visit_types_mut(f),
+                Item::TypeAlias(ref mut v) => v.visit_types_mut(f),
+                Item::ExternBlock(ref mut v) => v.visit_types_mut(f),
+                Item::BenchGroup(ref mut v) => v.visit_types_mut(f),
+                Item::Raw(_) => {}
+            }
+        }
+    }
+
     /// Return a string representation of the scope.
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         let mut ret = String::new();
 
@@ -286,15 +502,44 @@ impl Scope {
         let mut sorted_items = BTreeMap::<String, Vec<&Item>>::new();
         for item in self.items.iter() {
             match *item {
-                Item::Module(ref v) => sorted_items.entry(format!("{}-module", v.name)).or_default().push(item),
+                Item::Module(ref v) => sorted_items
+                    .entry(format!("{}-module", v.name))
+                    .or_default()
+                    .push(item),
                 // note: purposely use `astruct` instead of `struct` to make sure the struct always comes first in alphabetical order
-                Item::Struct(ref v) => sorted_items.entry(format!("{}-astruct", v.ty().key_for_sorting())).or_default().push(item),
-                Item::Function(ref v) => sorted_items.entry(format!("{}-function", v.name())).or_default().push(item),
-                Item::Trait(ref v) => sorted_items.entry(format!("{}-trait", v.ty().key_for_sorting())).or_default().push(item),
-                Item::Enum(ref v) => sorted_items.entry(format!("{}-enum", v.ty().key_for_sorting())).or_default().push(item),
-                Item::Impl(ref v) => sorted_items.entry(format!("{}-impl", v.key_for_sorting().key_for_sorting())).or_default().push(item),
-                Item::TypeAlias(ref v) => sorted_items.entry(format!("{}-alias", v.type_def().key_for_sorting())).or_default().push(item),
-                _ => {},
+                Item::Struct(ref v) => sorted_items
+                    .entry(format!("{}-astruct", v.ty().key_for_sorting()))
+                    .or_default()
+                    .push(item),
+                Item::Function(ref v) => sorted_items
+                    .entry(format!("{}-function", v.name()))
+                    .or_default()
+                    .push(item),
+                Item::Trait(ref v) => sorted_items
+                    .entry(format!("{}-trait", v.ty().key_for_sorting()))
+                    .or_default()
+                    .push(item),
+                Item::Enum(ref v) => sorted_items
+                    .entry(format!("{}-enum", v.ty().key_for_sorting()))
+                    .or_default()
+                    .push(item),
+                Item::Impl(ref v) => sorted_items
+                    .entry(format!("{}-impl", v.key_for_sorting().key_for_sorting()))
+                    .or_default()
+                    .push(item),
+                Item::TypeAlias(ref v) => sorted_items
+                    .entry(format!("{}-alias", v.type_def().key_for_sorting()))
+                    .or_default()
+                    .push(item),
+                Item::ExternBlock(ref v) => sorted_items
+                    .entry(format!("{}-extern", v.abi()))
+                    .or_default()
+                    .push(item),
+                Item::BenchGroup(ref v) => sorted_items
+                    .entry(format!("{}-bench", v.name()))
+                    .or_default()
+                    .push(item),
+                _ => {}
             }
         }
 
@@ -310,24 +555,41 @@ impl Scope {
                             } else {
                                 has_item = true;
                             }
-                        },
+                        }
                     }
 
                     match *item {
                         Item::Module(ref v) => v.fmt(fmt)?,
                         Item::Struct(ref v) => v.fmt(fmt)?,
-                        Item::Function(ref v) => v.fmt(false, fmt)?,
+                        Item::Function(ref v) => Function::fmt(v, false, fmt)?,
                         Item::Trait(ref v) => v.fmt(fmt)?,
                         Item::Enum(ref v) => v.fmt(fmt)?,
                         Item::Impl(ref v) => v.fmt(fmt)?,
                         Item::TypeAlias(ref v) => v.fmt(fmt)?,
-                        _ => {}, // already printed earlier
+                        Item::ExternBlock(ref v) => v.fmt(fmt)?,
+                        Item::BenchGroup(ref v) => v.fmt(fmt)?,
+                        _ => {} // already printed earlier
                     }
-                    
                 }
             }
         }
 
+        {
+            let groups = self
+                .items
+                .iter()
+                .filter_map(|item| match *item {
+                    Item::BenchGroup(ref v) => Some(v.name().as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            if !groups.is_empty() {
+                writeln!(fmt)?;
+                writeln!(fmt, "criterion_main!({});", groups.join(", "))?;
+            }
+        }
+
         Ok(())
     }
 
@@ -355,8 +617,12 @@ impl Scope {
                 for (ty, import) in imports {
                     if *vis == import.vis {
                         match import.alias.as_ref() {
-                            None => { simple_tys.push(ty); }
-                            Some(alias) => { alias_tys.push(format!("{} as {}", ty, alias)); }
+                            None => {
+                                simple_tys.push(ty);
+                            }
+                            Some(alias) => {
+                                alias_tys.push(format!("{} as {}", ty, alias));
+                            }
                         }
                     }
                 }
@@ -366,7 +632,7 @@ impl Scope {
                         write!(fmt, "{} ", vis)?;
                     }
 
-                    write!(fmt, "use {}::{};\n", path, ty)?;
+                    writeln!(fmt, "use {}::{};", path, ty)?;
                 }
                 if !simple_tys.is_empty() {
                     if let Some(ref vis) = *vis {
@@ -385,9 +651,9 @@ impl Scope {
                             write!(fmt, "{}", ty)?;
                         }
 
-                        write!(fmt, "}};\n")?;
+                        writeln!(fmt, "}};")?;
                     } else if simple_tys.len() == 1 {
-                        write!(fmt, "{};\n", simple_tys[0])?;
+                        writeln!(fmt, "{};", simple_tys[0])?;
                     }
                 }
             }
@@ -399,7 +665,12 @@ impl Scope {
     /// Merge two scopes together
     pub fn append(&mut self, other: &Self) -> &Self {
         self.docs = match (self.docs.as_ref(), other.docs.as_ref()) {
-            (Some(doc_a), Some(doc_b)) => Some(Docs::new("").append(doc_a.to_str()).append(doc_b.to_str()).clone()),
+            (Some(doc_a), Some(doc_b)) => Some(
+                Docs::new("")
+                    .append(doc_a.to_str())
+                    .append(doc_b.to_str())
+                    .clone(),
+            ),
             (Some(doc_a), None) => Some(doc_a.clone()),
             (None, Some(doc_b)) => Some(doc_b.clone()),
             (None, None) => None,
@@ -407,8 +678,8 @@ impl Scope {
         for (key, value) in other.imports.iter() {
             self.imports
                 .entry(key.to_string())
-                .or_insert(IndexMap::new())
-                .extend(value.iter().map(|(a,b)| (a.clone(), b.clone())));
+                .or_default()
+                .extend(value.iter().map(|(a, b)| (a.clone(), b.clone())));
         }
 
         self.items.extend(other.items.iter().cloned());
@@ -416,3 +687,8 @@ impl Scope {
     }
 }
 
+impl Default for Scope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
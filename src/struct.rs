@@ -1,12 +1,64 @@
 use std::fmt::{self, Write};
 
+use crate::block::Block;
+use crate::cfg::Cfg;
 use crate::field::Field;
 use crate::fields::Fields;
 use crate::formatter::Formatter;
+use crate::function::Function;
+use crate::r#impl::Impl;
 use crate::type_def::TypeDef;
 
 use crate::r#type::Type;
 
+/// Defines the getters generated by [`Struct::generate_accessors`].
+///
+/// [`Struct::generate_accessors`]: struct.Struct.html#method.generate_accessors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GetterStyle {
+    /// No getters
+    None,
+
+    /// `fn field(&self) -> &T`
+    Ref,
+
+    /// `fn field(&self) -> &T` and `fn field_mut(&mut self) -> &mut T`
+    RefMut,
+
+    /// `fn field(&self) -> T`, for `Copy` fields
+    Copy,
+}
+
+/// Defines the setters generated by [`Struct::generate_accessors`].
+///
+/// [`Struct::generate_accessors`]: struct.Struct.html#method.generate_accessors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetterStyle {
+    /// No setters
+    None,
+
+    /// `fn set_field(&mut self, field: T)`
+    Plain,
+
+    /// `fn set_field(&mut self, field: T) -> &mut Self`
+    Chained,
+}
+
+/// Defines how [`Struct::impl_debug`] formats a field.
+///
+/// [`Struct::impl_debug`]: struct.Struct.html#method.impl_debug
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugStyle {
+    /// The value of the field
+    Show,
+
+    /// `"<redacted>"` instead of the value of the field
+    Redact,
+
+    /// Nothing, the field is left out
+    Skip,
+}
+
 /// Defines a struct.
 #[derive(Debug, Clone)]
 pub struct Struct {
@@ -25,11 +77,72 @@ impl Struct {
         }
     }
 
+    /// Return a structure definition from a struct parsed by `syn`.
+    ///
+    /// The visibility, generics, `where` clause, docs, derives, `repr` hints
+    /// and fields are kept, so the struct can be modified before being
+    /// formatted again. Other attributes are kept verbatim.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// let item: syn::ItemStruct = syn::parse_str("
+    ///     #[derive(Debug)]
+    ///     pub struct User {
+    ///         pub id: u64,
+    ///     }
+    /// ").unwrap();
+    ///
+    /// let mut user = Struct::from_syn(&item);
+    /// user.derive("Clone").field("name", "String");
+    /// ```
+    pub fn from_syn(item: &syn::ItemStruct) -> Self {
+        let mut ret = Struct {
+            type_def: TypeDef::from_syn(&item.ident, &item.vis, &item.attrs, &item.generics),
+            fields: Fields::Empty,
+        };
+
+        for field in &item.fields {
+            let field = Field::from_syn(field);
+            if field.name.is_empty() {
+                ret.push_tuple_field(field);
+            } else {
+                ret.push_field(field);
+            }
+        }
+
+        ret
+    }
+
     /// Returns a reference to the type
     pub fn ty(&self) -> &Type {
         &self.type_def.ty
     }
 
+    /// Returns an iterator over the fields of the struct, in the order they
+    /// are formatted.
+    ///
+    /// Tuple fields have an empty name.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// let mut user = Struct::new("User");
+    /// user.field("id", "u64").field("name", "String");
+    ///
+    /// let names: Vec<_> = user.fields().map(|field| field.name.as_str()).collect();
+    /// assert_eq!(names, ["id", "name"]);
+    /// ```
+    pub fn fields(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter()
+    }
+
+    /// Rename the struct, keeping its generics.
+    pub fn rename(&mut self, name: impl ToString) -> &mut Self {
+        self.type_def.ty.set_name(name);
+        self
+    }
+
     /// Set the structure visibility.
     pub fn vis(&mut self, vis: impl ToString) -> &mut Self {
         self.type_def.vis(vis);
@@ -38,7 +151,41 @@ impl Struct {
 
     /// Add a generic to the struct.
     pub fn generic(&mut self, name: impl ToString) -> &mut Self {
-        self.type_def.ty.generic(name);
+        self.type_def.ty.generic(Type::new(name));
+        self
+    }
+
+    /// Add a const generic to the struct, e.g. `const N: usize`.
+    ///
+    /// The parameter is formatted after the lifetimes and type generics in
+    /// the definition, and as `N` in [`ty`].
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// // `struct Buffer<T, const N: usize> { data: [T; N] }`
+    /// let mut buffer = Struct::new("Buffer");
+    /// buffer
+    ///     .const_generic("N", "usize")
+    ///     .generic("T")
+    ///     .field("data", "[T; N]");
+    /// ```
+    ///
+    /// [`ty`]: #method.ty
+    pub fn const_generic<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.type_def.const_generic(name, ty);
+        self
+    }
+
+    /// Add a generic with a default type to the struct, e.g. `T = String`.
+    pub fn generic_with_default<T>(&mut self, name: impl ToString, default: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.type_def.generic_with_default(name, default);
         self
     }
 
@@ -51,18 +198,86 @@ impl Struct {
         self
     }
 
+    /// Add a `where` predicate to the struct, e.g. `T: Clone + Send`.
+    ///
+    /// Predicates on the same type are merged, and each type is formatted on
+    /// its own line:
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// // struct Pool<T, F>
+    /// // where T: Clone + Send,
+    /// //       F: Fn() -> T,
+    /// let mut pool = Struct::new("Pool");
+    /// pool.generic("T")
+    ///     .generic("F")
+    ///     .where_bound("T: Clone + Send")
+    ///     .where_bound("F: Fn() -> T")
+    ///     .field("items", "Vec<T>")
+    ///     .field("make", "F");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the predicate doesn't contain a `:`.
+    pub fn where_bound(&mut self, predicate: impl ToString) -> &mut Self {
+        self.type_def.where_bound(&predicate.to_string());
+        self
+    }
+
     /// Set the structure documentation.
     pub fn doc(&mut self, docs: impl ToString) -> &mut Self {
         self.type_def.doc(docs);
         self
     }
 
+    /// Mark the struct as `#[deprecated]`, with an optional version and note.
+    pub fn deprecated(&mut self, since: Option<&str>, note: Option<&str>) -> &mut Self {
+        self.type_def.deprecated(since, note);
+        self
+    }
+
+    /// Add a `#[must_use]` attribute to the struct, with an optional message.
+    ///
+    /// The attribute is formatted after the derives, `repr` and
+    /// `#[deprecated]`, and before the other attributes.
+    pub fn must_use(&mut self, message: Option<&str>) -> &mut Self {
+        self.type_def.must_use(message);
+        self
+    }
+
     /// Add a new type that the struct should derive.
+    ///
+    /// Derives are formatted sorted by name in a single `#[derive(...)]`,
+    /// and can be path-qualified, e.g. `serde::Serialize`. Derives are
+    /// deduplicated by their last path segment, so deriving `Serialize` and
+    /// `serde::Serialize` only formats `Serialize`.
     pub fn derive(&mut self, name: impl ToString) -> &mut Self {
         self.type_def.derive(name);
         self
     }
 
+    /// Derive the types only when the configuration predicate holds, using
+    /// `#[cfg_attr(...)]`.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// let mut model = Struct::new("Model");
+    ///
+    /// // add `#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]`
+    /// model.derive_when("feature = \"serde\"", ["Serialize", "Deserialize"]);
+    /// ```
+    pub fn derive_when<I>(&mut self, predicate: impl Into<Cfg>, derives: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+    {
+        self.type_def.derive_when(predicate, derives);
+        self
+    }
+
     /// Add a new attribute to the struct
     pub fn attr(&mut self, attr: impl ToString) -> &mut Self {
         self.type_def.attr(attr);
@@ -75,12 +290,53 @@ impl Struct {
         self
     }
 
-    /// Specify representation.
+    /// Set whether the struct is `#[non_exhaustive]` or not, so it can get
+    /// new fields without breaking downstream crates.
+    pub fn set_non_exhaustive(&mut self, non_exhaustive: bool) -> &mut Self {
+        self.type_def.set_non_exhaustive(non_exhaustive);
+        self
+    }
+
+    /// Returns whether the struct is `#[non_exhaustive]` or not
+    pub fn is_non_exhaustive(&self) -> bool {
+        self.type_def.is_non_exhaustive()
+    }
+
+    /// Add a representation hint, e.g. `"C"`.
+    ///
+    /// Several hints are combined in a single `#[repr(...)]`.
+    ///
+    /// # Panics
+    ///
+    /// Formatting panics if the hints can't be combined, e.g. `transparent`
+    /// with any other hint.
     pub fn repr(&mut self, repr: impl ToString) -> &mut Self {
         self.type_def.repr(repr);
         self
     }
 
+    /// Add a `repr(align(n))` hint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    pub fn repr_align(&mut self, align: usize) -> &mut Self {
+        self.type_def.repr_align(align);
+        self
+    }
+
+    /// Add a `repr(transparent)` hint.
+    pub fn repr_transparent(&mut self) -> &mut Self {
+        self.type_def.repr("transparent");
+        self
+    }
+
+    /// Add a `repr(packed)` hint.
+    pub fn repr_packed(&mut self) -> &mut Self {
+        self.type_def.repr("packed");
+        self
+    }
+
     /// Add an arbitrary macro.
     pub fn r#macro(&mut self, r#macro: impl ToString) -> &mut Self {
         self.type_def.r#macro(r#macro);
@@ -125,6 +381,107 @@ impl Struct {
         self.fields.new_named(name, ty)
     }
 
+    /// Insert a named field at position `index`, shifting the fields after
+    /// it.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `index` is greater than the number of fields,
+    /// or if the struct has tuple fields.
+    pub fn insert_field(&mut self, index: usize, field: Field) -> &mut Self {
+        self.fields.insert_named(index, field);
+        self
+    }
+
+    /// Sort the fields of the struct alphabetically by name.
+    ///
+    /// The sort is stable, and tuple fields are left as they are.
+    pub fn sort_fields(&mut self) -> &mut Self {
+        if let Fields::Named(..) = self.fields {
+            self.fields.sort_by_key(|field| field.name.clone());
+        }
+        self
+    }
+
+    /// Sort the fields of the struct by the key returned by `f`.
+    ///
+    /// The sort is stable, so fields with equal keys keep the order they were
+    /// added in.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// let mut user = Struct::new("User");
+    /// user.field("name", "String");
+    /// user.new_field("id", "u64").vis("pub");
+    /// user.field("email", "String");
+    ///
+    /// // public fields first
+    /// user.sort_fields_by_key(|field| field.visibility.is_none());
+    /// ```
+    pub fn sort_fields_by_key<K, F>(&mut self, f: F) -> &mut Self
+    where
+        K: Ord,
+        F: FnMut(&Field) -> K,
+    {
+        self.fields.sort_by_key(f);
+        self
+    }
+
+    /// Remove the named field called `name`, returning it if it exists.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// let mut user = Struct::new("User");
+    /// user.field("id", "u64").field("password_hash", "String");
+    ///
+    /// // public version of the model
+    /// let mut public = user.clone();
+    /// public.rename("PublicUser").remove_field("password_hash");
+    /// ```
+    pub fn remove_field(&mut self, name: &str) -> Option<Field> {
+        self.fields.remove_named(name)
+    }
+
+    /// Replace the named field called `name` with `field`, returning the
+    /// previous field if it exists.
+    ///
+    /// The new field keeps the position of the previous one, and nothing is
+    /// replaced if the struct has no field called `name`.
+    pub fn replace_field(&mut self, name: &str, field: Field) -> Option<Field> {
+        self.fields.replace_named(name, field)
+    }
+
+    /// Push a tuple field to the struct.
+    ///
+    /// A struct can either set tuple fields with this function or named fields
+    /// with `push_field`, but not both.
+    pub fn push_tuple_field(&mut self, field: Field) -> &mut Self {
+        self.fields.push_tuple(field);
+        self
+    }
+
+    /// Create a tuple field for the struct, returning a mutable reference to
+    /// it to set its visibility and annotations.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// // `pub struct UserId(pub u64);`
+    /// let mut user_id = Struct::new("UserId");
+    /// user_id.vis("pub").new_tuple_field("u64").vis("pub");
+    /// ```
+    ///
+    /// A struct can either set tuple fields with this function or named fields
+    /// with `new_field`, but not both.
+    pub fn new_tuple_field<T>(&mut self, ty: T) -> &mut Field
+    where
+        T: Into<Type>,
+    {
+        self.fields.new_tuple(ty)
+    }
+
     /// Add a tuple field to the struct.
     ///
     /// A struct can either set tuple fields with this function or named fields
@@ -137,21 +494,684 @@ impl Struct {
         self
     }
 
+    /// Add a `PhantomData` marker for `ty` to the struct.
+    ///
+    /// The marker is a `_marker: PhantomData<T>` field, or a trailing
+    /// `PhantomData<T>` field for tuple structs. Calling this several times
+    /// adds the types to the same marker, as in `PhantomData<(A, B)>`.
+    ///
+    /// Pass a variance-controlling form to opt out of the default covariance
+    /// or of the drop check, e.g. `"fn() -> T"` or `"fn(T) -> T"`.
+    ///
+    /// ```
+    /// use codegen::Struct;
+    ///
+    /// // `struct Id<T> { raw: u64, _marker: PhantomData<fn() -> T> }`
+    /// let mut id = Struct::new("Id");
+    /// id.generic("T").field("raw", "u64").phantom("fn() -> T");
+    /// ```
+    pub fn phantom<T>(&mut self, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        let ty = ty.into();
+        let marker = match self.fields {
+            Fields::Empty => None,
+            Fields::Tuple(ref mut fields) | Fields::Named(ref mut fields) => {
+                fields.iter_mut().find(|field| is_phantom_marker(field))
+            }
+        };
+
+        if let Some(marker) = marker {
+            let mut elems = match marker.ty.generics().first() {
+                Some(inner) => inner
+                    .tuple_elems()
+                    .cloned()
+                    .unwrap_or_else(|| vec![inner.clone()]),
+                None => Vec::new(),
+            };
+            elems.push(ty);
+            marker.ty = match elems.len() {
+                1 => phantom_data(elems.pop().unwrap()),
+                _ => phantom_data(Type::tuple(elems)),
+            };
+        } else if let Fields::Tuple(..) = self.fields {
+            self.fields.tuple(None, phantom_data(ty));
+        } else {
+            self.fields.named("_marker", phantom_data(ty));
+        }
+
+        self
+    }
+
+    /// Add a `PhantomData` marker for the generics and lifetimes of the
+    /// struct that aren't used by any of its fields.
+    ///
+    /// Unused lifetimes are marked as `&'a ()`. Nothing is added when every
+    /// generic is used. See [`phantom`] for how the marker is formatted.
+    ///
+    /// [`phantom`]: #method.phantom
+    pub fn phantom_unused(&mut self) -> &mut Self {
+        let mut names = Vec::new();
+        let mut lifetimes = Vec::new();
+        self.fields.clone().visit_types_mut(&mut |ty| {
+            names.extend(ty.segments().map(str::to_string));
+            lifetimes.extend(ty.lifetimes().iter().map(|l| l.name().clone()));
+            lifetimes.extend(ty.reference_lifetime().map(|l| l.name().clone()));
+        });
+
+        // lifetimes added with `generic("'a")` are kept with the generics
+        for param in self.type_def.generic_params() {
+            match param.strip_prefix('\'') {
+                Some(lifetime) if !lifetimes.iter().any(|l| l == lifetime) => {
+                    let mut marker = Type::reference(Type::unit());
+                    marker.set_reference_lifetime(lifetime);
+                    self.phantom(marker);
+                }
+                None if !param.starts_with("const ") && !names.contains(&param) => {
+                    self.phantom(param);
+                }
+                _ => {}
+            }
+        }
+
+        self
+    }
+
+    /// Return a `Default` impl for the struct.
+    ///
+    /// Each field is initialized with its default value, or with
+    /// `Default::default()` if it has none.
+    ///
+    /// ```
+    /// use codegen::{Scope, Struct};
+    ///
+    /// let mut config = Struct::new("Config");
+    /// config.new_field("retries", "u32").default_value("3");
+    /// config.field("name", "String");
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impl(config.impl_default());
+    /// scope.push_struct(config);
+    /// ```
+    pub fn impl_default(&self) -> Impl {
+        self.impl_default_with(Vec::<(&str, &str)>::new())
+    }
+
+    /// Return a `Default` impl for the struct, initializing the fields named
+    /// in `values` with the given expressions.
+    ///
+    /// The other fields are initialized as in [`impl_default`]. Tuple fields
+    /// are named by their index, e.g. `"0"`.
+    ///
+    /// ```
+    /// use codegen::{Scope, Struct};
+    ///
+    /// let mut client = Struct::new("Client");
+    /// client.field("timeout", "std::time::Duration");
+    /// client.field("agent", "String");
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impl(client.impl_default_with([
+    ///     ("timeout", "std::time::Duration::from_secs(30)"),
+    ///     ("agent", "\"codegen\".to_string()"),
+    /// ]));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the struct has no field named as one of the
+    /// `values`.
+    ///
+    /// [`impl_default`]: #method.impl_default
+    pub fn impl_default_with<I, N, V>(&self, values: I) -> Impl
+    where
+        I: IntoIterator<Item = (N, V)>,
+        N: ToString,
+        V: ToString,
+    {
+        let names: Vec<String> = match self.fields {
+            Fields::Tuple(ref fields) => (0..fields.len()).map(|i| i.to_string()).collect(),
+            _ => self.fields().map(|field| field.name.clone()).collect(),
+        };
+        let mut exprs: Vec<String> = self
+            .fields()
+            .map(|field| {
+                if field.value.is_empty() {
+                    "Default::default()".to_string()
+                } else {
+                    field.value.clone()
+                }
+            })
+            .collect();
+        for (name, value) in values {
+            let name = name.to_string();
+            let index = names
+                .iter()
+                .position(|n| *n == name)
+                .unwrap_or_else(|| panic!("struct has no field `{}`", name));
+            exprs[index] = value.to_string();
+        }
+
+        let mut imp = self.new_impl();
+        imp.impl_trait("Default");
+
+        let mut func = Function::new("default");
+        func.ret("Self");
+
+        match self.fields {
+            Fields::Empty => {
+                func.line("Self");
+            }
+            Fields::Tuple(..) => {
+                func.line(format!("Self({})", exprs.join(", ")));
+            }
+            Fields::Named(..) => {
+                let mut block = Block::new("Self");
+                for (name, expr) in names.iter().zip(&exprs) {
+                    block.line(format!("{}: {},", name, expr));
+                }
+                func.push_block(block);
+            }
+        }
+
+        imp.push_fn(func);
+        imp
+    }
+
+    /// Return an impl block with a `new` constructor for the struct.
+    ///
+    /// The constructor takes an argument for each field without a default
+    /// value, in order, and initializes the other fields with their default
+    /// value. `PhantomData` markers are initialized with `PhantomData`. When
+    /// `into` is set, the arguments are taken as `impl Into<T>` and
+    /// converted. Tuple fields are passed as `f0`, `f1`, etc. The constructor
+    /// has the visibility of the struct.
+    ///
+    /// ```
+    /// use codegen::{Scope, Struct};
+    ///
+    /// let mut user = Struct::new("User");
+    /// user.vis("pub").field("name", "String");
+    /// user.new_field("admin", "bool").default_value("false");
+    ///
+    /// // `pub fn new(name: impl Into<String>) -> Self`
+    /// let mut scope = Scope::new();
+    /// scope.push_impl(user.generate_new(true));
+    /// ```
+    pub fn generate_new(&self, into: bool) -> Impl {
+        let mut imp = self.new_impl();
+        let func = imp.new_fn("new");
+        if let Some(vis) = self.visibility() {
+            func.vis(vis);
+        }
+        func.ret("Self");
+
+        let mut values = Vec::new();
+        for (i, field) in self.fields.iter().enumerate() {
+            let name = match self.fields {
+                Fields::Tuple(..) => format!("f{}", i),
+                _ => field.name.clone(),
+            };
+
+            if !field.value.is_empty() {
+                values.push((name, field.value.clone()));
+            } else if is_phantom_marker(field) {
+                values.push((name, "std::marker::PhantomData".to_string()));
+            } else if into {
+                func.arg(&name, Type::impl_trait(format!("Into<{}>", field.ty)));
+                values.push((name.clone(), format!("{}.into()", name)));
+            } else {
+                func.arg(&name, &field.ty);
+                values.push((name.clone(), name));
+            }
+        }
+
+        match self.fields {
+            Fields::Empty => {
+                func.line("Self");
+            }
+            Fields::Tuple(..) => {
+                let values: Vec<_> = values.into_iter().map(|(_, value)| value).collect();
+                func.line(format!("Self({})", values.join(", ")));
+            }
+            Fields::Named(..) => {
+                let mut block = Block::new("Self");
+                for (name, value) in values {
+                    if name == value {
+                        block.line(format!("{},", name));
+                    } else {
+                        block.line(format!("{}: {},", name, value));
+                    }
+                }
+                func.push_block(block);
+            }
+        }
+
+        imp
+    }
+
+    /// Return a `std::fmt::Display` impl for the struct, formatting it with a
+    /// template referencing its fields, e.g. `"{major}.{minor}.{patch}"`.
+    ///
+    /// Fields are referenced by name, or by index for tuple structs, and can
+    /// have a format spec as in `format!`, e.g. `{ratio:.2}`. Braces are
+    /// escaped by doubling them.
+    ///
+    /// ```
+    /// use codegen::{Scope, Struct};
+    ///
+    /// let mut version = Struct::new("Version");
+    /// version
+    ///     .field("major", "u32")
+    ///     .field("minor", "u32")
+    ///     .field("patch", "u32");
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impl(version.impl_display("v{major}.{minor}.{patch}"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the template references a field that doesn't
+    /// exist, or if it has unbalanced braces.
+    pub fn impl_display(&self, template: &str) -> Impl {
+        let names: Vec<String> = match self.fields {
+            Fields::Tuple(ref fields) => (0..fields.len()).map(|i| i.to_string()).collect(),
+            _ => self.fields().map(|field| field.name.clone()).collect(),
+        };
+
+        let mut format = String::new();
+        let mut args = Vec::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    format.push_str("{{");
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    format.push_str("}}");
+                }
+                '{' => {
+                    let mut arg = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => arg.push(c),
+                            None => panic!("unclosed `{{` in display template"),
+                        }
+                    }
+
+                    let (name, spec) = match arg.find(':') {
+                        Some(index) => arg.split_at(index),
+                        None => (&arg[..], ""),
+                    };
+                    let name = name.trim();
+                    assert!(
+                        names.iter().any(|n| n == name),
+                        "display template references unknown field `{}`",
+                        name
+                    );
+
+                    format.push('{');
+                    format.push_str(spec);
+                    format.push('}');
+                    args.push(format!("self.{}", name));
+                }
+                '}' => panic!("unmatched `}}` in display template"),
+                c => format.push(c),
+            }
+        }
+
+        let mut imp = self.new_impl();
+        imp.impl_trait("std::fmt::Display");
+
+        let mut write = format!("write!(f, {:?}", format);
+        for arg in args {
+            write.push_str(", ");
+            write.push_str(&arg);
+        }
+        write.push(')');
+
+        imp.new_fn("fmt")
+            .arg_ref_self()
+            .arg("f", "&mut std::fmt::Formatter<'_>")
+            .ret("std::fmt::Result")
+            .line(write);
+        imp
+    }
+
+    /// Return a `std::fmt::Debug` impl for the struct, formatting its fields
+    /// as `#[derive(Debug)]` would, except for the fields set to redact or
+    /// skip with [`Field::set_debug`].
+    ///
+    /// Redacted fields are formatted as `"<redacted>"`. When fields are
+    /// skipped, the output ends with `..` to show it's incomplete.
+    ///
+    /// Every type parameter is bound by `std::fmt::Debug`, except the ones
+    /// only used in `PhantomData` fields.
+    ///
+    /// ```
+    /// use codegen::{DebugStyle, Scope, Struct};
+    ///
+    /// let mut credentials = Struct::new("Credentials");
+    /// credentials.field("user", "String");
+    /// credentials
+    ///     .new_field("password", "String")
+    ///     .set_debug(DebugStyle::Redact);
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impl(credentials.impl_debug());
+    /// ```
+    ///
+    /// [`Field::set_debug`]: struct.Field.html#method.set_debug
+    pub fn impl_debug(&self) -> Impl {
+        let name = self.type_def.ty.name();
+
+        let mut imp = self.new_impl();
+        imp.impl_trait("std::fmt::Debug");
+        for param in self.type_def.type_params() {
+            let used = self
+                .fields
+                .iter()
+                .filter(|field| field.ty.last_segment() != "PhantomData")
+                .any(|field| mentions_type(&field.ty, &param));
+            if used {
+                imp.bound(&param, "std::fmt::Debug");
+            }
+        }
+
+        let func = imp
+            .new_fn("fmt")
+            .arg_ref_self()
+            .arg("f", "&mut std::fmt::Formatter<'_>")
+            .ret("std::fmt::Result");
+
+        let tuple = match self.fields {
+            Fields::Empty => {
+                func.line(format!("f.write_str({:?})", name));
+                return imp;
+            }
+            Fields::Tuple(..) => {
+                func.line(format!("f.debug_tuple({:?})", name));
+                true
+            }
+            Fields::Named(..) => {
+                func.line(format!("f.debug_struct({:?})", name));
+                false
+            }
+        };
+
+        let mut skipped = false;
+        for (i, field) in self.fields.iter().enumerate() {
+            let value = match field.debug {
+                DebugStyle::Show if tuple => format!("&self.{}", i),
+                DebugStyle::Show => format!("&self.{}", field.name),
+                DebugStyle::Redact => "&\"<redacted>\"".to_string(),
+                DebugStyle::Skip => {
+                    skipped = true;
+                    continue;
+                }
+            };
+            if tuple {
+                func.line(format!("    .field({})", value));
+            } else {
+                func.line(format!("    .field({:?}, {})", field.name, value));
+            }
+        }
+
+        if skipped {
+            func.line("    .finish_non_exhaustive()");
+        } else {
+            func.line("    .finish()");
+        }
+        imp
+    }
+
+    /// Return an impl block with accessors for the named fields of the
+    /// struct.
+    ///
+    /// Getters are called like the field and setters are called `set_field`.
+    /// The accessors have the visibility of the struct, and fields can opt out
+    /// with [`Field::set_getter`] and [`Field::set_setter`].
+    ///
+    /// ```
+    /// use codegen::{GetterStyle, Scope, SetterStyle, Struct};
+    ///
+    /// let mut user = Struct::new("User");
+    /// user.vis("pub").field("name", "String");
+    /// user.new_field("id", "u64").set_setter(false);
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impl(user.generate_accessors(GetterStyle::Ref, SetterStyle::Chained));
+    /// ```
+    ///
+    /// [`Field::set_getter`]: struct.Field.html#method.set_getter
+    /// [`Field::set_setter`]: struct.Field.html#method.set_setter
+    pub fn generate_accessors(&self, getters: GetterStyle, setters: SetterStyle) -> Impl {
+        let mut imp = self.new_impl();
+
+        let named = match self.fields {
+            Fields::Named(ref fields) => &fields[..],
+            _ => &[],
+        };
+
+        for field in named {
+            let name = &field.name;
+
+            if field.getter && getters != GetterStyle::None {
+                let getter = imp.new_fn(name);
+                if let Some(vis) = self.visibility() {
+                    getter.vis(vis);
+                }
+                if !field.documentation.is_empty() {
+                    getter.doc(&field.documentation);
+                }
+                getter.arg_ref_self();
+
+                if getters == GetterStyle::Copy {
+                    getter.ret(&field.ty).line(format!("self.{}", name));
+                } else {
+                    getter
+                        .ret(Type::reference(&field.ty))
+                        .line(format!("&self.{}", name));
+                }
+            }
+
+            if field.getter && getters == GetterStyle::RefMut {
+                let getter = imp.new_fn(format!("{}_mut", name));
+                if let Some(vis) = self.visibility() {
+                    getter.vis(vis);
+                }
+                getter
+                    .arg_mut_self()
+                    .ret(Type::mut_reference(&field.ty))
+                    .line(format!("&mut self.{}", name));
+            }
+
+            if field.setter && setters != SetterStyle::None {
+                let setter = imp.new_fn(format!("set_{}", name));
+                if let Some(vis) = self.visibility() {
+                    setter.vis(vis);
+                }
+                setter
+                    .arg_mut_self()
+                    .arg(name, &field.ty)
+                    .line(format!("self.{0} = {0};", name));
+
+                if setters == SetterStyle::Chained {
+                    setter.ret("&mut Self").line("self");
+                }
+            }
+        }
+
+        imp
+    }
+
+    /// Return `Deref`, `DerefMut`, `From` and `AsRef` impls for a newtype
+    /// wrapping its `field`, e.g. `"0"` for a tuple struct.
+    ///
+    /// The `From` impl initializes the other fields, which must be
+    /// `PhantomData` markers, with `PhantomData`.
+    ///
+    /// ```
+    /// use codegen::{Scope, Struct};
+    ///
+    /// let mut meters = Struct::new("Meters");
+    /// meters.tuple_field(None, "f64");
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impls(meters.generate_newtype_impls("0"));
+    /// scope.push_struct(meters);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the struct has no such field, or if it has
+    /// other fields than `PhantomData` markers.
+    pub fn generate_newtype_impls(&self, field: &str) -> Vec<Impl> {
+        let (fields, tuple) = match self.fields {
+            Fields::Tuple(ref fields) => (&fields[..], true),
+            Fields::Named(ref fields) => (&fields[..], false),
+            Fields::Empty => (&[][..], false),
+        };
+        let index = if tuple {
+            field.parse::<usize>().ok().filter(|i| *i < fields.len())
+        } else {
+            fields.iter().position(|f| f.name == field)
+        }
+        .unwrap_or_else(|| panic!("struct has no field `{}`", field));
+        let inner = &fields[index].ty;
+        assert!(
+            fields
+                .iter()
+                .enumerate()
+                .all(|(i, f)| i == index || is_phantom_marker(f)),
+            "newtypes can't have other fields than `PhantomData` markers"
+        );
+
+        let mut deref = self.new_impl();
+        deref
+            .impl_trait("std::ops::Deref")
+            .associate_type("Target", inner)
+            .new_fn("deref")
+            .arg_ref_self()
+            .ret("&Self::Target")
+            .line(format!("&self.{}", field));
+
+        let mut deref_mut = self.new_impl();
+        deref_mut
+            .impl_trait("std::ops::DerefMut")
+            .new_fn("deref_mut")
+            .arg_mut_self()
+            .ret("&mut Self::Target")
+            .line(format!("&mut self.{}", field));
+
+        let values: Vec<String> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let value = if i == index {
+                    "value"
+                } else {
+                    "std::marker::PhantomData"
+                };
+                if tuple {
+                    value.to_string()
+                } else {
+                    format!("{}: {}", f.name, value)
+                }
+            })
+            .collect();
+        let mut from = self.new_impl();
+        from.impl_trait(format!("From<{}>", inner))
+            .new_fn("from")
+            .arg("value", inner)
+            .ret("Self")
+            .line(if tuple {
+                format!("Self({})", values.join(", "))
+            } else {
+                format!("Self {{ {} }}", values.join(", "))
+            });
+
+        let mut as_ref = self.new_impl();
+        as_ref
+            .impl_trait(format!("AsRef<{}>", inner))
+            .new_fn("as_ref")
+            .arg_ref_self()
+            .ret(Type::reference(inner))
+            .line(format!("&self.{}", field));
+
+        vec![deref, deref_mut, from, as_ref]
+    }
+
+    /// Return an empty impl block for the struct, with its generics and
+    /// bounds.
+    pub(crate) fn new_impl(&self) -> Impl {
+        self.type_def.new_impl()
+    }
+
+    /// Returns the visibility of the struct.
+    pub(crate) fn visibility(&self) -> Option<&String> {
+        self.type_def.visibility()
+    }
+
+    /// Return an empty struct called `name` with the visibility, generics and
+    /// bounds of this struct.
+    pub(crate) fn companion(&self, name: impl ToString) -> Struct {
+        Struct {
+            type_def: self.type_def.companion(name),
+            fields: Fields::Empty,
+        }
+    }
+
+    /// Call `f` on every type used by the struct, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.type_def.visit_types_mut(f);
+        self.fields.visit_types_mut(f);
+    }
+
     /// Formats the struct using the given formatter.
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
-        self.type_def.fmt_head("struct", &[], fmt)?;
-        self.fields.fmt(fmt)?;
-
         match self.fields {
             Fields::Empty => {
-                write!(fmt, ";\n")?;
+                self.type_def.fmt_head("struct", &[], fmt)?;
+                writeln!(fmt, ";")?;
             }
             Fields::Tuple(..) => {
-                write!(fmt, ";\n")?;
+                self.type_def.fmt_signature("struct", &[], fmt)?;
+                self.fields.fmt(fmt)?;
+                self.type_def.fmt_where(fmt)?;
+                writeln!(fmt, ";")?;
+            }
+            Fields::Named(..) => {
+                self.type_def.fmt_head("struct", &[], fmt)?;
+                self.fields.fmt(fmt)?;
+                writeln!(fmt)?;
             }
-            _ => {}
         }
 
         Ok(())
     }
 }
+
+fn phantom_data(ty: Type) -> Type {
+    let mut phantom = Type::new("std::marker::PhantomData");
+    phantom.generic(ty);
+    phantom
+}
+
+/// Whether the type `name` is used in `ty`, including nested types.
+fn mentions_type(ty: &Type, name: &str) -> bool {
+    let mut found = false;
+    ty.clone().visit_mut(&mut |ty: &mut Type| {
+        found |= ty.generics().is_empty() && ty.name() == name;
+    });
+    found
+}
+
+fn is_phantom_marker(field: &Field) -> bool {
+    (field.name.is_empty() || field.name == "_marker") && field.ty.last_segment() == "PhantomData"
+}
@@ -0,0 +1,478 @@
+use std::fmt::{self, Write};
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+
+use crate::formatter::Formatter;
+
+/// Formats tokens on a single line the way they would usually be written,
+/// e.g. `Foo<'a, { N + 1 }>` instead of `Foo < 'a , { N + 1 } >`.
+pub(crate) fn tokens_to_string(tokens: TokenStream) -> String {
+    let mut ret = String::new();
+    push_tokens(tokens, &mut ret);
+    ret
+}
+
+fn push_tokens(tokens: TokenStream, dst: &mut String) {
+    // whether the previous token ends an operand, e.g. an identifier or a
+    // closing `>`, which makes the next word a separate word
+    let mut after_operand = false;
+    // whether the previous token is a value, which makes the next operator a
+    // binary operator
+    let mut after_value = false;
+    // whether the previous token is a capitalized identifier, which makes the
+    // next `<` the start of generics: `Vec<u8>` but `x < 1`
+    let mut after_type = false;
+    // whether the previous token is a keyword declaring an item, which makes
+    // the next identifier the name of a possibly generic item: `fn foo<T>`
+    let mut after_item = false;
+    // number of unclosed generic `<`
+    let mut generics = 0;
+    // whether we are between the `|` of closure parameters
+    let mut closure_params = false;
+    // punctuation of a multi-character operator such as `->` or `::`
+    let mut op = String::new();
+
+    for token in tokens {
+        match token {
+            TokenTree::Punct(punct) => {
+                op.push(punct.as_char());
+                if punct.spacing() == Spacing::Joint && op != "'" {
+                    continue;
+                }
+                if op == "'" {
+                    // a lifetime, the identifier follows
+                    if after_operand {
+                        dst.push(' ');
+                    }
+                    continue;
+                }
+
+                let binary = matches!(
+                    op.as_str(),
+                    "+" | "-"
+                        | "*"
+                        | "/"
+                        | "%"
+                        | "^"
+                        | "&"
+                        | "=>"
+                        | "=="
+                        | "!="
+                        | "<="
+                        | ">="
+                        | "&&"
+                        | "<<"
+                        | "+="
+                        | "-="
+                        | "*="
+                        | "/="
+                        | "%="
+                        | "^="
+                        | "&="
+                        | "|="
+                        | "<<="
+                        | ">>="
+                );
+                let after_word = after_operand;
+                after_operand = false;
+                match op.as_str() {
+                    "," | ";" | ":" => {
+                        write!(dst, "{} ", op).unwrap();
+                    }
+                    "=" | "->" => {
+                        // e.g. `|x| -> u8`, the closing `|` ends with a space
+                        while dst.ends_with(' ') {
+                            dst.pop();
+                        }
+                        write!(dst, " {} ", op).unwrap();
+                    }
+                    "|" if closure_params => {
+                        dst.push_str("| ");
+                        closure_params = false;
+                    }
+                    "|" | "||" if !after_value => {
+                        // the parameters of a closure, or a closure without
+                        // parameters
+                        if after_word {
+                            // e.g. `move |x|`
+                            dst.push(' ');
+                        }
+                        if op == "|" {
+                            closure_params = true;
+                            dst.push('|');
+                        } else {
+                            dst.push_str("|| ");
+                        }
+                    }
+                    "|" | "||" => {
+                        write!(dst, " {} ", op).unwrap();
+                    }
+                    "<" if after_value && !after_type => {
+                        write!(dst, " {} ", op).unwrap();
+                    }
+                    ">" | ">>" if generics > 0 => {
+                        generics -= op.len().min(generics);
+                        dst.push_str(&op);
+                        after_operand = true;
+                    }
+                    ">" | ">>" if after_value => {
+                        write!(dst, " {} ", op).unwrap();
+                    }
+                    _ if binary && after_value => {
+                        write!(dst, " {} ", op).unwrap();
+                    }
+                    _ => {
+                        // unary operators such as `&T` or `-1`, and paths
+                        if after_word && matches!(op.as_str(), "*" | "&" | "&&" | "-") {
+                            // e.g. `match *x`
+                            dst.push(' ');
+                        }
+                        dst.push_str(&op);
+                        if op == "<" {
+                            generics += 1;
+                        }
+                        after_operand = op == "?";
+                    }
+                }
+                after_value = after_operand;
+                after_type = false;
+                after_item = false;
+                op.clear();
+            }
+            TokenTree::Ident(ref ident) => {
+                if op == "'" {
+                    dst.push('\'');
+                    op.clear();
+                } else if after_operand {
+                    dst.push(' ');
+                }
+                write!(dst, "{}", ident).unwrap();
+
+                let name = ident.to_string();
+                after_operand = true;
+                after_value = !is_keyword(&name);
+                after_type = name.starts_with(char::is_uppercase) || after_item;
+                after_item = matches!(
+                    name.as_str(),
+                    "fn" | "struct" | "enum" | "union" | "trait" | "type"
+                );
+            }
+            TokenTree::Literal(literal) => {
+                if after_operand {
+                    dst.push(' ');
+                }
+                write!(dst, "{}", literal).unwrap();
+                after_operand = true;
+                after_value = true;
+                after_type = false;
+                after_item = false;
+            }
+            TokenTree::Group(group) => {
+                match group.delimiter() {
+                    Delimiter::Parenthesis | Delimiter::Bracket => {
+                        let (open, close) = if group.delimiter() == Delimiter::Parenthesis {
+                            ('(', ')')
+                        } else {
+                            ('[', ']')
+                        };
+                        dst.push(open);
+                        push_tokens(group.stream(), dst);
+                        dst.push(close);
+                    }
+                    Delimiter::Brace => {
+                        if after_operand {
+                            dst.push(' ');
+                        }
+                        if group.stream().is_empty() {
+                            dst.push_str("{}");
+                        } else {
+                            dst.push_str("{ ");
+                            push_tokens(group.stream(), dst);
+                            dst.push_str(" }");
+                        }
+                    }
+                    Delimiter::None => push_tokens(group.stream(), dst),
+                }
+                after_operand = true;
+                after_value = true;
+                after_type = false;
+                after_item = false;
+            }
+        }
+    }
+
+    if !op.is_empty() {
+        dst.push_str(&op);
+    }
+    while dst.ends_with(' ') {
+        dst.pop();
+    }
+}
+
+/// Whether the identifier is a keyword that can be followed by an
+/// expression or a type, such as `match *x` or `for<'a>`.
+fn is_keyword(ident: &str) -> bool {
+    matches!(
+        ident,
+        "as" | "async"
+            | "break"
+            | "const"
+            | "dyn"
+            | "else"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "match"
+            | "move"
+            | "mut"
+            | "ref"
+            | "return"
+            | "static"
+            | "unsafe"
+            | "where"
+            | "while"
+            | "yield"
+    )
+}
+
+/// Formats tokens as the lines of a body.
+///
+/// Statements are written on their own line, and the blocks of functions,
+/// closures and control flow expressions are indented, as well as match arm
+/// bodies containing statements. Everything else is written the same as
+/// `tokens_to_string` does.
+pub(crate) fn fmt_lines(tokens: TokenStream, fmt: &mut Formatter<'_>) -> fmt::Result {
+    fmt_lines_split(tokens, false, fmt)
+}
+
+fn fmt_lines_split(
+    tokens: TokenStream,
+    split_commas: bool,
+    fmt: &mut Formatter<'_>,
+) -> fmt::Result {
+    let mut line = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(ref punct) if ends_line(punct, split_commas) => {
+                line.push(token);
+                writeln!(fmt, "{}", tokens_to_string(line.drain(..).collect()))?;
+            }
+            TokenTree::Group(ref group)
+                if group.delimiter() == Delimiter::Brace
+                    && !precedes_assignment(tokens.peek())
+                    && (starts_block(&line)
+                        || (opens_block(&line) && is_block(group.stream()))) =>
+            {
+                let head = tokens_to_string(line.drain(..).collect());
+                if !head.is_empty() {
+                    write!(fmt, "{} ", head)?;
+                }
+
+                if group.stream().is_empty() {
+                    write!(fmt, "{{}}")?;
+                } else {
+                    writeln!(fmt, "{{")?;
+                    fmt.indent(|fmt| {
+                        fmt_lines_split(group.stream(), has_arms(group.stream()), fmt)
+                    })?;
+                    write!(fmt, "}}")?;
+                }
+
+                match tokens.peek() {
+                    Some(TokenTree::Punct(punct)) if matches!(punct.as_char(), ';' | ',') => {
+                        writeln!(fmt, "{}", punct)?;
+                        tokens.next();
+                    }
+                    // e.g. `} else {` or `}.await`, the rest of the line follows
+                    Some(TokenTree::Ident(ident)) if ident == "else" => write!(fmt, " ")?,
+                    Some(TokenTree::Punct(punct)) if matches!(punct.as_char(), '.' | '?') => {}
+                    _ => writeln!(fmt)?,
+                }
+            }
+            TokenTree::Group(ref group)
+                if group.delimiter() == Delimiter::Brace && split_commas && ends_arrow(&line) =>
+            {
+                // a match arm ending with a block is not followed by a comma
+                line.push(token);
+                if !matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if matches!(punct.as_char(), ',' | '.'))
+                {
+                    writeln!(fmt, "{}", tokens_to_string(line.drain(..).collect()))?;
+                }
+            }
+            _ => line.push(token),
+        }
+    }
+
+    if !line.is_empty() {
+        writeln!(fmt, "{}", tokens_to_string(line.into_iter().collect()))?;
+    }
+
+    Ok(())
+}
+
+fn ends_line(punct: &proc_macro2::Punct, split_commas: bool) -> bool {
+    punct.spacing() == Spacing::Alone
+        && (punct.as_char() == ';' || (split_commas && punct.as_char() == ','))
+}
+
+/// Whether braces following `line` are a block written on its own lines:
+/// the body of a function or closure, of a control flow expression, or a
+/// block starting a statement. Braces following anything else are e.g. a
+/// struct expression or pattern, such as `Foo { a, .. }`.
+fn starts_block(line: &[TokenTree]) -> bool {
+    let Some(last) = line.last() else {
+        return true;
+    };
+    if matches!(last, TokenTree::Punct(punct) if punct.as_char() == '|') {
+        return true;
+    }
+
+    // only the arm body counts, not e.g. the `if` guard of `x if x > 0 =>`
+    let body = (0..=line.len())
+        .rev()
+        .find(|&i| ends_arrow(&line[..i]))
+        .map_or(line, |i| &line[i..]);
+
+    let mut prev = None;
+    body.iter().any(|token| match token {
+        TokenTree::Ident(ident) => {
+            prev = None;
+            matches!(
+                ident.to_string().as_str(),
+                "async"
+                    | "else"
+                    | "fn"
+                    | "for"
+                    | "if"
+                    | "impl"
+                    | "loop"
+                    | "match"
+                    | "mod"
+                    | "trait"
+                    | "unsafe"
+                    | "while"
+            )
+        }
+        TokenTree::Punct(punct) => {
+            // the return type of a closure, e.g. `|x| -> u8 { .. }`
+            let is_arrow = prev == Some('-') && punct.as_char() == '>';
+            prev = Some(punct.as_char()).filter(|_| punct.spacing() == Spacing::Joint);
+            is_arrow
+        }
+        _ => {
+            prev = None;
+            false
+        }
+    })
+}
+
+/// Whether braces following `line` can be a block, which is written on its
+/// own lines when it contains statements or match arms: the body of a match
+/// arm or an assigned block expression, e.g. `let x = { .. };`.
+fn opens_block(line: &[TokenTree]) -> bool {
+    ends_arrow(line)
+        || matches!(line.last(), Some(TokenTree::Punct(punct)) if punct.as_char() == '=')
+}
+
+/// Whether braces followed by `next` are a pattern followed by `=` or `=>`,
+/// e.g. `if let Foo { a } = foo`.
+fn precedes_assignment(next: Option<&TokenTree>) -> bool {
+    matches!(next, Some(TokenTree::Punct(punct)) if punct.as_char() == '=')
+}
+
+/// Whether `line` ends with the `=>` of a match arm.
+fn ends_arrow(line: &[TokenTree]) -> bool {
+    match line {
+        [.., TokenTree::Punct(eq), TokenTree::Punct(gt)] => {
+            eq.as_char() == '=' && eq.spacing() == Spacing::Joint && gt.as_char() == '>'
+        }
+        _ => false,
+    }
+}
+
+/// Whether the contents of braces are statements or match arms, instead of
+/// e.g. the fields of a struct expression.
+fn is_block(tokens: TokenStream) -> bool {
+    has_arms(tokens.clone())
+        || tokens.into_iter().any(|token| match token {
+            TokenTree::Punct(punct) => punct.as_char() == ';',
+            TokenTree::Group(group) => {
+                group.delimiter() == Delimiter::Brace && is_block(group.stream())
+            }
+            _ => false,
+        })
+}
+
+/// Whether the contents of braces are the arms of a `match`.
+fn has_arms(tokens: TokenStream) -> bool {
+    let mut prev = None;
+    for token in tokens {
+        if let TokenTree::Punct(ref punct) = token {
+            if prev == Some('=') && punct.as_char() == '>' {
+                return true;
+            }
+            prev = match punct.spacing() {
+                Spacing::Joint => Some(punct.as_char()),
+                Spacing::Alone => None,
+            };
+        } else {
+            prev = None;
+        }
+    }
+    false
+}
+
+#[test]
+fn fmt_body_lines() {
+    let tokens = quote::quote! {
+        let v = items.iter().map(|x| x + 1).collect::<Vec<_>>();
+        thread::spawn(move || run(&mut *v)?);
+        if v.len() < 2 { return None; }
+        v
+    };
+
+    let mut dst = String::new();
+    fmt_lines(tokens, &mut Formatter::new(&mut dst)).unwrap();
+
+    assert_eq!(
+        dst,
+        "let v = items.iter().map(|x| x + 1).collect::<Vec<_>>();\n\
+         thread::spawn(move || run(&mut *v)?);\n\
+         if v.len() < 2 {\n    return None;\n}\n\
+         v\n"
+    );
+}
+
+#[test]
+fn fmt_body_braces() {
+    let tokens = quote::quote! {
+        let total = match e { E::A { x } => x, E::B => 0 };
+        match f { Foo { a, .. } if a > 0 => { log(a); a } _ => 0 }
+        let p = Point { x: 1, y: 2 };
+        fn inner<'a>(x: &'a u8) -> &'a u8 { let y = x; y }
+    };
+
+    let mut dst = String::new();
+    fmt_lines(tokens, &mut Formatter::new(&mut dst)).unwrap();
+
+    assert_eq!(
+        dst,
+        "let total = match e {\n    E::A { x } => x,\n    E::B => 0\n};\n\
+         match f {\n    Foo { a, .. } if a > 0 => {\n        log(a);\n        a\n    }\n    _ => 0\n}\n\
+         let p = Point { x: 1, y: 2 };\n\
+         fn inner<'a>(x: &'a u8) -> &'a u8 {\n    let y = x;\n    y\n}\n"
+    );
+}
+
+#[test]
+fn empty_braces() {
+    assert_eq!(tokens_to_string(quote::quote! { Foo {} }), "Foo {}");
+    assert_eq!(
+        tokens_to_string(quote::quote! { |x| -> u8 { x } }),
+        "|x| -> u8 { x }"
+    );
+}
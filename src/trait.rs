@@ -5,8 +5,10 @@ use crate::associated_type::AssociatedType;
 use crate::bound::Bound;
 use crate::formatter::{fmt_bound_rhs, Formatter};
 use crate::function::Function;
+use crate::receiver::Receiver;
 use crate::type_def::TypeDef;
 
+use crate::r#impl::Impl;
 use crate::r#type::Type;
 
 /// Define a trait.
@@ -18,7 +20,6 @@ pub struct Trait {
     attributes: Vec<String>,
     associated_tys: Vec<AssociatedType>,
     fns: Vec<Function>,
-    macros: Vec<String>,
 }
 
 impl Trait {
@@ -31,7 +32,6 @@ impl Trait {
             attributes: Vec::new(),
             associated_tys: Vec::new(),
             fns: Vec::new(),
-            macros: Vec::new(),
         }
     }
 
@@ -54,7 +54,38 @@ impl Trait {
 
     /// Add a generic to the trait
     pub fn generic(&mut self, name: impl ToString) -> &mut Self {
-        self.type_def.ty.generic(name);
+        self.type_def.ty.generic(Type::new(name));
+        self
+    }
+
+    /// Add a const generic to the trait, e.g. `const N: usize`.
+    pub fn const_generic<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.type_def.const_generic(name, ty);
+        self
+    }
+
+    /// Add a generic with a default type to the trait, e.g. `T = String`.
+    ///
+    /// The default is only formatted in the definition, so [`ty`] can be used
+    /// as the target of `impl` blocks.
+    ///
+    /// ```
+    /// use codegen::Trait;
+    ///
+    /// let mut trt = Trait::new("Add");
+    /// trt.generic_with_default("Rhs", "Self");
+    /// assert_eq!(trt.ty().to_string(), "Add<Rhs>");
+    /// ```
+    ///
+    /// [`ty`]: #method.ty
+    pub fn generic_with_default<T>(&mut self, name: impl ToString, default: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.type_def.generic_with_default(name, default);
         self
     }
 
@@ -94,6 +125,12 @@ impl Trait {
         self
     }
 
+    /// Mark the trait as `#[deprecated]`, with an optional version and note.
+    pub fn deprecated(&mut self, since: Option<&str>, note: Option<&str>) -> &mut Self {
+        self.type_def.deprecated(since, note);
+        self
+    }
+
     /// Add an associated const. Returns a mutable reference to the new
     /// associated const for futher configuration.
     pub fn associated_const<T>(&mut self, name: impl ToString, ty: T) -> &mut AssociatedConst
@@ -134,6 +171,139 @@ impl Trait {
         self
     }
 
+    /// Returns the reasons the trait can't be used as `dyn Trait`, or an
+    /// empty list if it is dyn compatible.
+    ///
+    /// A trait isn't dyn compatible if it requires `Sized`, has associated
+    /// constants, or has functions that are generic, take no `self`, take or
+    /// return `Self`, are `async`, or return `impl Trait`. Functions bounded
+    /// with `where Self: Sized` are left out of trait objects and don't count.
+    ///
+    /// ```
+    /// use codegen::Trait;
+    ///
+    /// let mut shape = Trait::new("Shape");
+    /// shape.new_fn("area").arg_ref_self().ret("f64");
+    /// shape.new_fn("scaled").arg_ref_self().arg("factor", "f64").ret("Self");
+    /// shape
+    ///     .new_fn("unit")
+    ///     .ret("Self")
+    ///     .bound("Self", "Sized");
+    ///
+    /// assert_eq!(
+    ///     shape.dyn_compatibility_issues(),
+    ///     ["`fn scaled` takes or returns `Self`"]
+    /// );
+    /// ```
+    pub fn dyn_compatibility_issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        let sized = self.parents.iter().any(|parent| parent.name() == "Sized")
+            || self.type_def.bounds().iter().any(Bound::is_self_sized);
+        if sized {
+            issues.push("the trait requires `Self: Sized`".to_string());
+        }
+        for AssociatedConst(ref cst) in &self.associated_consts {
+            issues.push(format!("`const {}` is an associated constant", cst.name));
+        }
+        for func in &self.fns {
+            if let Some(issue) = func.dyn_incompatibility() {
+                issues.push(format!("`fn {}` {}", func.name(), issue));
+            }
+        }
+
+        issues
+    }
+
+    /// Return impls of the trait for `&T`, `Box<T>` and `Arc<T>`,
+    /// forwarding every function, associated type and constant to `T`.
+    ///
+    /// Only the impls that can forward every function are returned: `&T` and
+    /// `Arc<T>` require the functions to take `&self` or no receiver, and
+    /// `Box<T>` accepts `&mut self` too. No impl is returned if a function
+    /// takes or returns `Self`, e.g. a constructor.
+    ///
+    /// ```
+    /// use codegen::{Scope, Trait};
+    ///
+    /// let mut store = Trait::new("Store");
+    /// store
+    ///     .new_fn("get")
+    ///     .arg_ref_self()
+    ///     .arg("key", "&str")
+    ///     .ret("Option<String>");
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push_impls(store.generate_forwarding_impls());
+    /// scope.push_trait(store);
+    /// ```
+    pub fn generate_forwarding_impls(&self) -> Vec<Impl> {
+        let params = self.type_def.generic_params();
+        let inner = if params.iter().any(|param| param == "T") {
+            "Inner"
+        } else {
+            "T"
+        };
+
+        let shared = self.fns.iter().all(|func| {
+            !func.uses_self_type()
+                && matches!(func.get_receiver(), None | Some(Receiver::Ref(None)))
+        });
+        let exclusive = self.fns.iter().all(|func| {
+            !func.uses_self_type()
+                && matches!(
+                    func.get_receiver(),
+                    None | Some(Receiver::Ref(None)) | Some(Receiver::RefMut(None))
+                )
+        });
+
+        let pointers = [
+            (Type::reference(Type::new(inner)), shared),
+            (Type::new(inner).boxed(), exclusive),
+            (Type::new(format!("std::sync::Arc<{}>", inner)), shared),
+        ];
+        let mut impls = Vec::new();
+        for (pointer, forwards) in pointers {
+            if !forwards {
+                continue;
+            }
+
+            let mut imp = self.type_def.new_impl_for(&pointer);
+            imp.generic(format!("{}: {} + ?Sized", inner, self.type_def.ty))
+                .impl_trait(&self.type_def.ty);
+            for AssociatedConst(ref cst) in &self.associated_consts {
+                let ty = cst.bound.first().cloned().unwrap_or_else(|| Type::new("_"));
+                imp.new_const(&cst.name, ty, format!("{}::{}", inner, cst.name));
+            }
+            for AssociatedType(ref ty) in &self.associated_tys {
+                imp.associate_type(&ty.name, format!("{}::{}", inner, ty.name));
+            }
+            for func in &self.fns {
+                imp.push_fn(func.forwarding("(**self)", inner));
+            }
+            impls.push(imp);
+        }
+
+        impls
+    }
+
+    /// Call `f` on every type used by the trait, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.type_def.visit_types_mut(f);
+        for parent in &mut self.parents {
+            parent.visit_mut(f);
+        }
+        for AssociatedConst(ref mut bound) in &mut self.associated_consts {
+            bound.visit_types_mut(f);
+        }
+        for AssociatedType(ref mut bound) in &mut self.associated_tys {
+            bound.visit_types_mut(f);
+        }
+        for func in &mut self.fns {
+            func.visit_types_mut(f);
+        }
+    }
+
     /// Formats the scope using the given formatter.
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         for attr in &self.attributes {
@@ -158,7 +328,7 @@ impl Trait {
                         fmt_bound_rhs(&cst.bound, fmt)?;
                     }
 
-                    write!(fmt, ";\n")?;
+                    writeln!(fmt, ";")?;
                 }
             }
 
@@ -174,13 +344,13 @@ impl Trait {
                         fmt_bound_rhs(&ty.bound, fmt)?;
                     }
 
-                    write!(fmt, ";\n")?;
+                    writeln!(fmt, ";")?;
                 }
             }
 
             for (i, func) in self.fns.iter().enumerate() {
                 if i != 0 || !assoc_tys.is_empty() || !assoc_csts.is_empty() {
-                    write!(fmt, "\n")?;
+                    writeln!(fmt)?;
                 }
 
                 func.fmt(true, fmt)?;
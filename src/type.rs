@@ -1,189 +1,2662 @@
+use std::borrow::Cow;
 use std::fmt::{self, Write};
+use std::hash::{Hash, Hasher};
 
-use crate::formatter::Formatter;
+use quote::ToTokens;
+
+use crate::bound::Bound;
+use crate::formatter::{fmt_bound_rhs, Formatter};
+use crate::lifetime::Lifetime;
+use crate::tokens::tokens_to_string;
 
 /// Defines a type.
 #[derive(Debug, Clone)]
 pub struct Type {
-    name: String,
-    generics: Vec<Type>,
+    kind: Kind,
 }
 
-fn split_name_and_generic(ast: &syn::Type) -> Type {
-    match ast {
-        syn::Type::Path(syn::TypePath { path, .. }) => {
-            let segments = &path.segments;
-            let base_type = segments.iter().map(|seg| seg.ident.to_string()).collect::<Vec<String>>().join("::");
-            let mut new_type = Type::new(&base_type);
-
-            if let syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments { args, .. }) = &segments.last().unwrap().arguments {
-                for arg in args.iter() {
-                    if let syn::GenericArgument::Type(t) = arg {
-                        let generic_type = split_name_and_generic(t);
-                        new_type.generic(generic_type);
-                    } else {
-                        // this isn't correct, but properly parsing the full AST is too tedious and abandoning early here is good enough
-                        return Type {
-                            name: quote::quote! { #ast }.to_string(),
-                            generics: vec![]
+#[derive(Debug, Clone)]
+enum Kind {
+    /// A named type, e.g. `Foo<'a, T>` or `Iterator<Item: Debug>`
+    Path {
+        name: String,
+        lifetimes: Vec<Lifetime>,
+        generics: Vec<Type>,
+        bindings: Vec<(String, Type)>,
+        constraints: Vec<Bound>,
+    },
+
+    /// A reference, e.g. `&'a mut T`
+    Reference {
+        lifetime: Option<Lifetime>,
+        mutable: bool,
+        inner: Box<Type>,
+    },
+
+    /// A raw pointer, e.g. `*const T` or `*mut T`
+    Ptr { mutable: bool, inner: Box<Type> },
+
+    /// A tuple, e.g. `(A, B)` or the unit type `()`
+    Tuple(Vec<Type>),
+
+    /// A fixed size array, e.g. `[T; 16]` or `[T; N]`
+    Array { inner: Box<Type>, len: String },
+
+    /// A dynamically sized slice, e.g. `[T]`
+    Slice(Box<Type>),
+
+    /// A parenthesized type, e.g. `(dyn Trait + Send)` in `&(dyn Trait + Send)`
+    Paren(Box<Type>),
+
+    /// A trait object, e.g. `dyn Trait + Send + 'a`
+    Dyn {
+        bounds: Vec<Type>,
+        lifetimes: Vec<Lifetime>,
+    },
+
+    /// An `impl Trait` type, e.g. `impl Iterator<Item = T> + '_`
+    Impl {
+        bounds: Vec<Type>,
+        lifetimes: Vec<Lifetime>,
+    },
+
+    /// A qualified path, e.g. `<T as Trait>::Output`, `<Vec<T>>::Item`, or
+    /// `T::Item` when there is no trait and the qualified type is a plain path
+    Qualified {
+        qself: Box<Type>,
+        as_trait: Option<Box<Type>>,
+        assoc: Box<Type>,
+    },
+
+    /// The never type `!`
+    Never,
+
+    /// A const generic argument, e.g. `16` in `ArrayVec<u8, 16>`
+    Const(String),
+
+    /// A function pointer, e.g. `unsafe extern "C" fn(*const u8) -> i32`
+    BareFn {
+        r#unsafe: bool,
+        abi: Option<String>,
+        args: Vec<Type>,
+        variadic: bool,
+        ret: Option<Box<Type>>,
+    },
+
+    /// A closure trait with parenthesized arguments, e.g. `FnMut(u8) -> u16`
+    FnTrait {
+        name: String,
+        args: Vec<Type>,
+        ret: Option<Box<Type>>,
+    },
+}
+
+/// Keywords that can't be a segment of a type path.
+const RESERVED_WORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// Returns whether `name` is a plain path such as `T` or `::std::io::Error`,
+/// which doesn't need to be parsed.
+///
+/// Projections such as `T::Item` aren't plain paths, since they are
+/// represented as `Kind::Qualified`.
+fn is_plain_path(name: &str) -> bool {
+    let is_segment = |segment: &str| {
+        let ident = segment.strip_prefix("r#").unwrap_or(segment);
+        let mut chars = ident.chars();
+        chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+            && ident != "_"
+            && !RESERVED_WORDS.contains(&segment)
+    };
+
+    let mut segments = name.split("::").peekable();
+    let projection = segments.next().is_some_and(is_generic_param) && segments.peek().is_some();
+    !projection
+        && name
+            .strip_prefix("::")
+            .unwrap_or(name)
+            .split("::")
+            .all(is_segment)
+}
+
+/// Returns whether `ident` is named like a generic type parameter, e.g. `T`,
+/// `I` or `T2`, which makes `T::Item` an associated item of the parameter
+/// rather than a path.
+fn is_generic_param(ident: &str) -> bool {
+    let mut chars = ident.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase()) && chars.all(|c| c.is_ascii_digit())
+}
+
+/// Replaces the leading segments `from` of `path` with `to`, keeping at least
+/// one segment of `path`.
+fn replace_path_prefix(path: &str, from: &str, to: &str) -> Option<String> {
+    let from = from.trim_end_matches("::");
+    if from.is_empty() {
+        return None;
+    }
+
+    let rest = path.strip_prefix(from)?.strip_prefix("::")?;
+    match to.trim_end_matches("::") {
+        "" => Some(rest.to_string()),
+        to => Some(format!("{}::{}", to, rest)),
+    }
+}
+
+/// Keeps a type that can't be represented by `Kind` as its tokens.
+fn verbatim<T: quote::ToTokens>(ast: &T) -> Type {
+    Type::named(tokens_to_string(ast.to_token_stream()))
+}
+
+fn split_path(path: &syn::Path) -> Option<Type> {
+    split_segments(
+        path.leading_colon.is_some(),
+        &path.segments.iter().collect::<Vec<_>>(),
+    )
+}
+
+fn split_segments(leading_colon: bool, segments: &[&syn::PathSegment]) -> Option<Type> {
+    let (last, rest) = segments.split_last()?;
+
+    // only the generics of the last segment are kept
+    if rest.iter().any(|seg| !seg.arguments.is_empty()) {
+        return None;
+    }
+
+    // `T::Item`: the segments following a generic parameter are an
+    // associated item of it rather than part of a module path
+    if let [param, assoc @ ..] = segments {
+        if !leading_colon
+            && !assoc.is_empty()
+            && is_generic_param(&param.ident.to_string())
+            && !matches!(last.arguments, syn::PathArguments::Parenthesized(_))
+        {
+            return Some(Type {
+                kind: Kind::Qualified {
+                    qself: Box::new(split_segments(false, &[*param])?),
+                    as_trait: None,
+                    assoc: Box::new(split_segments(false, assoc)?),
+                },
+            });
+        }
+    }
+
+    let mut base_type = segments
+        .iter()
+        .map(|seg| seg.ident.to_string())
+        .collect::<Vec<String>>()
+        .join("::");
+    if leading_colon {
+        base_type.insert_str(0, "::");
+    }
+
+    match &last.arguments {
+        syn::PathArguments::Parenthesized(syn::ParenthesizedGenericArguments {
+            inputs,
+            output,
+            ..
+        }) => {
+            return Some(Type {
+                kind: Kind::FnTrait {
+                    name: base_type,
+                    args: inputs.iter().map(split_name_and_generic).collect(),
+                    ret: split_return_type(output),
+                },
+            });
+        }
+        syn::PathArguments::None | syn::PathArguments::AngleBracketed(_) => {}
+    }
+
+    let mut new_type = Type::named(base_type);
+    match &last.arguments {
+        syn::PathArguments::None => {}
+        syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
+            args, ..
+        }) => {
+            for arg in args.iter() {
+                match arg {
+                    syn::GenericArgument::Type(t) => {
+                        new_type.generic(split_name_and_generic(t));
+                    }
+                    syn::GenericArgument::Lifetime(l) => {
+                        new_type.lifetime(l.ident.to_string());
+                    }
+                    syn::GenericArgument::Const(expr) => {
+                        new_type.const_generic(tokens_to_string(expr.to_token_stream()));
+                    }
+                    syn::GenericArgument::Binding(b) => {
+                        new_type.binding(&b.ident, split_name_and_generic(&b.ty));
+                    }
+                    syn::GenericArgument::Constraint(c) => {
+                        let (mut bound, lifetimes) = split_bounds(&c.bounds);
+                        bound.extend(
+                            lifetimes
+                                .iter()
+                                .map(|l| Type::named(format!("'{}", l.name()))),
+                        );
+                        if let Kind::Path {
+                            ref mut constraints,
+                            ..
+                        } = new_type.kind
+                        {
+                            constraints.push(Bound {
+                                name: c.ident.to_string(),
+                                bound,
+                            });
                         }
                     }
                 }
+            }
+        }
+        syn::PathArguments::Parenthesized(_) => unreachable!(),
+    }
+
+    Some(new_type)
+}
+
+fn split_return_type(output: &syn::ReturnType) -> Option<Box<Type>> {
+    match output {
+        syn::ReturnType::Default => None,
+        syn::ReturnType::Type(_, ty) => Some(Box::new(split_name_and_generic(ty))),
+    }
+}
+
+fn split_bounds<'a, I>(bounds: I) -> (Vec<Type>, Vec<Lifetime>)
+where
+    I: IntoIterator<Item = &'a syn::TypeParamBound>,
+{
+    let mut tys = Vec::new();
+    let mut lifetimes = Vec::new();
+
+    for bound in bounds {
+        match bound {
+            syn::TypeParamBound::Trait(syn::TraitBound {
+                paren_token: None,
+                modifier: syn::TraitBoundModifier::None,
+                lifetimes: None,
+                path,
+            }) => tys.push(Type::from_syn_path(path)),
+            // `?Sized` and `for<'a>` bounds are kept as a whole
+            syn::TypeParamBound::Trait(bound) => tys.push(verbatim(bound)),
+            syn::TypeParamBound::Lifetime(l) => lifetimes.push(Lifetime::new(&l.ident)),
+        }
+    }
+
+    (tys, lifetimes)
+}
+
+fn split_name_and_generic(ast: &syn::Type) -> Type {
+    match ast {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => {
+            split_path(path).unwrap_or_else(|| verbatim(ast))
+        }
+        syn::Type::Path(syn::TypePath {
+            qself: Some(syn::QSelf { ty, position, .. }),
+            path,
+        }) => {
+            let segments = path.segments.iter().collect::<Vec<_>>();
+            let (trait_segments, assoc_segments) = segments.split_at(*position);
+
+            let as_trait = match trait_segments {
+                [] => None,
+                _ => match split_segments(path.leading_colon.is_some(), trait_segments) {
+                    Some(ty) => Some(Box::new(ty)),
+                    None => return verbatim(ast),
+                },
             };
-            new_type
+
+            match split_segments(false, assoc_segments) {
+                Some(assoc) => Type {
+                    kind: Kind::Qualified {
+                        qself: Box::new(split_name_and_generic(ty)),
+                        as_trait,
+                        assoc: Box::new(assoc),
+                    },
+                },
+                None => verbatim(ast),
+            }
+        }
+        syn::Type::Reference(syn::TypeReference {
+            lifetime,
+            mutability,
+            elem,
+            ..
+        }) => Type {
+            kind: Kind::Reference {
+                lifetime: lifetime.as_ref().map(|l| Lifetime::new(&l.ident)),
+                mutable: mutability.is_some(),
+                inner: Box::new(split_name_and_generic(elem)),
+            },
+        },
+        syn::Type::Ptr(syn::TypePtr {
+            mutability, elem, ..
+        }) => Type {
+            kind: Kind::Ptr {
+                mutable: mutability.is_some(),
+                inner: Box::new(split_name_and_generic(elem)),
+            },
+        },
+        syn::Type::Array(syn::TypeArray { elem, len, .. }) => Type::array(
+            split_name_and_generic(elem),
+            tokens_to_string(len.to_token_stream()),
+        ),
+        syn::Type::Slice(syn::TypeSlice { elem, .. }) => Type::slice(split_name_and_generic(elem)),
+        syn::Type::Tuple(syn::TypeTuple { elems, .. }) => {
+            Type::tuple(elems.iter().map(split_name_and_generic))
+        }
+        syn::Type::TraitObject(syn::TypeTraitObject { bounds, .. }) => {
+            let (bounds, lifetimes) = split_bounds(bounds);
+            Type {
+                kind: Kind::Dyn { bounds, lifetimes },
+            }
         }
-        _ => {
-            // this isn't correct, but properly parsing the full AST is too tedious and abandoning early here is good enough
+        syn::Type::ImplTrait(syn::TypeImplTrait { bounds, .. }) => {
+            let (bounds, lifetimes) = split_bounds(bounds);
             Type {
-                name: quote::quote! { #ast }.to_string(),
-                generics: vec![]
+                kind: Kind::Impl { bounds, lifetimes },
             }
+        }
+        syn::Type::Paren(syn::TypeParen { elem, .. }) => Type {
+            kind: Kind::Paren(Box::new(split_name_and_generic(elem))),
         },
+        syn::Type::Group(syn::TypeGroup { elem, .. }) => split_name_and_generic(elem),
+        syn::Type::Infer(_) => Type::named("_".to_string()),
+        syn::Type::Never(_) => Type::never(),
+        syn::Type::BareFn(syn::TypeBareFn {
+            lifetimes: None,
+            unsafety,
+            abi,
+            inputs,
+            variadic,
+            output,
+            ..
+        }) => {
+            // argument names are not kept, so keep the whole type verbatim
+            // rather than silently dropping them
+            if inputs
+                .iter()
+                .any(|arg| arg.name.is_some() || !arg.attrs.is_empty())
+            {
+                return verbatim(ast);
+            }
+
+            Type {
+                kind: Kind::BareFn {
+                    r#unsafe: unsafety.is_some(),
+                    abi: abi.as_ref().map(|abi| {
+                        abi.name
+                            .as_ref()
+                            .map_or("C".to_string(), syn::LitStr::value)
+                    }),
+                    args: inputs
+                        .iter()
+                        .map(|arg| split_name_and_generic(&arg.ty))
+                        .collect(),
+                    variadic: variadic.is_some(),
+                    ret: split_return_type(output),
+                },
+            }
+        }
+        _ => verbatim(ast),
+    }
+}
+
+/// Error returned by [`Type::try_new`] when a string can't be parsed as a type.
+///
+/// [`Type::try_new`]: struct.Type.html#method.try_new
+#[derive(Debug, Clone)]
+pub struct ParseTypeError {
+    input: String,
+    message: String,
+}
+
+impl ParseTypeError {
+    /// Returns the string that failed to parse.
+    pub fn input(&self) -> &str {
+        &self.input
     }
 }
+
+impl fmt::Display for ParseTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse `{}` as a type: {}",
+            self.input, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseTypeError {}
+
 impl Type {
     /// Return a new type with the given name.
+    ///
+    /// Strings such as `Vec<T>` or `&'a str` are parsed into their structure.
+    /// If the string can't be parsed as a type, it is kept verbatim instead,
+    /// which allows passing declarations such as `T: Into<String>` to
+    /// generics. Use [`try_new`] to detect invalid types instead.
+    ///
+    /// [`try_new`]: #method.try_new
     pub fn new(name: impl ToString) -> Self {
         let name = name.to_string();
-        if name.contains('<') {
-            split_name_and_generic(&syn::parse_str(&name).unwrap())
-        } else {
-            Type {
-                name,
-                generics: Vec::new(),
-            }
+        match Type::try_new(&name) {
+            Ok(ty) => ty,
+            Err(_) => Type::named(name),
         }
     }
 
-    /// Returns the name of the type
-    pub fn name(&self) -> &String {
-        &self.name
+    /// Return a new type with the given name, or an error if the name can't
+    /// be parsed as a type.
+    ///
+    /// Surrounding whitespace is ignored.
+    ///
+    /// ```
+    /// use codegen::Type;
+    ///
+    /// assert!(Type::try_new("Vec<u8>").is_ok());
+    /// assert!(Type::try_new("Vec<u8").is_err());
+    /// assert!(Type::try_new("foo bar").is_err());
+    /// ```
+    pub fn try_new(name: impl ToString) -> Result<Self, ParseTypeError> {
+        let name = name.to_string();
+        let name = name.trim();
+        if is_plain_path(name) {
+            return Ok(Type::named(name.to_string()));
+        }
+
+        match syn::parse_str(name) {
+            Ok(ast) => Ok(split_name_and_generic(&ast)),
+            Err(err) => Err(ParseTypeError {
+                input: name.to_string(),
+                message: err.to_string(),
+            }),
+        }
     }
 
-    /// Returns the name of the type
-    pub fn generics(&self) -> &Vec<Type> {
-        &self.generics
+    /// Return a new type from a type parsed by `syn`.
+    ///
+    /// The structure of the type is kept, so generics, references, tuples
+    /// etc. can be inspected and modified the same way as for a type created
+    /// with [`new`]. Parts that can't be represented are kept verbatim.
+    /// `Type` also implements `From<&syn::Type>`, so `syn` types can be passed
+    /// to any builder taking a type.
+    ///
+    /// ```
+    /// use codegen::Type;
+    ///
+    /// let ast: syn::Type = syn::parse_str("HashMap<String, Vec<u8>>").unwrap();
+    /// let ty = Type::from_syn(&ast);
+    /// assert_eq!(ty.name(), "HashMap");
+    /// assert_eq!(ty.generics()[1].name(), "Vec");
+    /// ```
+    ///
+    /// [`new`]: #method.new
+    pub fn from_syn(ty: &syn::Type) -> Self {
+        split_name_and_generic(ty)
     }
 
-    /// Returns the key for sorting
-    pub fn key_for_sorting(&self) -> &str {
-        match self.name.rfind("::") {
-            Some(index) => &self.name[index + 2..],
-            None => &self.name,
+    /// Return a new type from a path parsed by `syn`, e.g. the path of a
+    /// trait bound.
+    pub fn from_syn_path(path: &syn::Path) -> Self {
+        split_path(path).unwrap_or_else(|| verbatim(path))
+    }
+
+    fn named(name: String) -> Self {
+        Type {
+            kind: Kind::Path {
+                name,
+                lifetimes: Vec::new(),
+                generics: Vec::new(),
+                bindings: Vec::new(),
+                constraints: Vec::new(),
+            },
         }
     }
 
-    /// Add a generic to the type.
-    pub fn generic<T>(&mut self, ty: T) -> &mut Self
+    /// Return a new shared reference to the given type, e.g. `&T`.
+    pub fn reference<T>(inner: T) -> Self
     where
         T: Into<Type>,
     {
-        // Make sure that the name doesn't already include generics
-        assert!(
-            !self.name.contains("<"),
-            "type name already includes generics"
-        );
-
-        self.generics.push(ty.into());
-        self
+        Type {
+            kind: Kind::Reference {
+                lifetime: None,
+                mutable: false,
+                inner: Box::new(inner.into()),
+            },
+        }
     }
 
-    /// Rewrite the `Type` with the provided path
-    ///
-    /// TODO: Is this needed?
-    pub fn path(&self, path: impl ToString) -> Type {
-        // TODO: This isn't really correct
-        assert!(!self.name.contains("::"));
-
-        let mut name = path.to_string();
-        name.push_str("::");
-        name.push_str(&self.name);
-
+    /// Return a new mutable reference to the given type, e.g. `&mut T`.
+    pub fn mut_reference<T>(inner: T) -> Self
+    where
+        T: Into<Type>,
+    {
         Type {
-            name,
-            generics: self.generics.clone(),
+            kind: Kind::Reference {
+                lifetime: None,
+                mutable: true,
+                inner: Box::new(inner.into()),
+            },
         }
     }
 
-    /// Formats the struct using the given formatter.
-    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
-        write!(fmt, "{}", self.name)?;
-        Type::fmt_slice(&self.generics, fmt)
+    /// Return a new `*const` raw pointer to the given type.
+    pub fn const_ptr<T>(inner: T) -> Self
+    where
+        T: Into<Type>,
+    {
+        Type {
+            kind: Kind::Ptr {
+                mutable: false,
+                inner: Box::new(inner.into()),
+            },
+        }
     }
 
-    fn fmt_slice(generics: &[Type], fmt: &mut Formatter<'_>) -> fmt::Result {
-        if !generics.is_empty() {
-            write!(fmt, "<")?;
-
-            for (i, ty) in generics.iter().enumerate() {
-                if i != 0 {
-                    write!(fmt, ", ")?
-                }
-                ty.fmt(fmt)?;
-            }
-
-            write!(fmt, ">")?;
+    /// Return a new `*mut` raw pointer to the given type.
+    pub fn mut_ptr<T>(inner: T) -> Self
+    where
+        T: Into<Type>,
+    {
+        Type {
+            kind: Kind::Ptr {
+                mutable: true,
+                inner: Box::new(inner.into()),
+            },
         }
-
-        Ok(())
     }
-}
 
-impl<S: ToString> From<S> for Type {
-    fn from(src: S) -> Self {
-        Type::new(src)
+    /// Return a new trait object type for the given trait, e.g. `dyn Trait`.
+    ///
+    /// Additional trait and lifetime bounds can be added with [`bound`] and
+    /// [`lifetime`].
+    ///
+    /// [`bound`]: #method.bound
+    /// [`lifetime`]: #method.lifetime
+    pub fn dyn_trait<T>(bound: T) -> Self
+    where
+        T: Into<Type>,
+    {
+        Type {
+            kind: Kind::Dyn {
+                bounds: vec![bound.into()],
+                lifetimes: Vec::new(),
+            },
+        }
     }
-}
 
-impl<'a> From<&'a Type> for Type {
-    fn from(src: &'a Type) -> Self {
-        src.clone()
+    /// Return a new `impl Trait` type for the given trait.
+    ///
+    /// This can be used both in argument and in return position. Additional
+    /// trait and lifetime bounds can be added with [`bound`] and
+    /// [`lifetime`], e.g. `impl Iterator<Item = T> + '_`.
+    ///
+    /// [`bound`]: #method.bound
+    /// [`lifetime`]: #method.lifetime
+    pub fn impl_trait<T>(bound: T) -> Self
+    where
+        T: Into<Type>,
+    {
+        Type {
+            kind: Kind::Impl {
+                bounds: vec![bound.into()],
+                lifetimes: Vec::new(),
+            },
+        }
     }
-}
 
-#[test]
-fn parse_type() {
+    /// Return a new function pointer type taking the given arguments, e.g.
+    /// `fn(A, B)`.
+    ///
+    /// The return type, unsafety and ABI can be set with [`set_fn_ret`],
+    /// [`set_fn_unsafe`] and [`set_fn_abi`].
+    ///
+    /// [`set_fn_ret`]: #method.set_fn_ret
+    /// [`set_fn_unsafe`]: #method.set_fn_unsafe
+    /// [`set_fn_abi`]: #method.set_fn_abi
+    pub fn fn_ptr<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Type>,
     {
-        let ty = Type::new("u8");
-        assert_eq!(ty.name, "u8");
-        assert!(ty.generics.is_empty());
+        Type {
+            kind: Kind::BareFn {
+                r#unsafe: false,
+                abi: None,
+                args: args.into_iter().map(Into::into).collect(),
+                variadic: false,
+                ret: None,
+            },
+        }
     }
-}
 
-#[test]
-fn parse_generic() {
+    /// Return a new closure trait with parenthesized arguments, e.g.
+    /// `Fn(u8, u8) -> u16`.
+    ///
+    /// `name` is the path of the trait, usually one of `Fn`, `FnMut` or
+    /// `FnOnce`.
+    ///
+    /// ```
+    /// use codegen::Type;
+    ///
+    /// let ty = Type::fn_trait("FnMut", vec!["u8", "u8"], Some("u16"));
+    /// assert_eq!(ty.name(), "FnMut");
+    /// assert_eq!(ty.fn_args().unwrap().len(), 2);
+    /// ```
+    pub fn fn_trait<I, A, R>(name: impl ToString, args: I, ret: Option<R>) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<Type>,
+        R: Into<Type>,
     {
-        let ty = Type::new("Vec<u8>");
-        assert_eq!(ty.name, "Vec");
-        assert_eq!(ty.generics.iter().map(|generic| generic.name().as_str()).collect::<Vec<&str>>().join(" "), "u8");
+        Type {
+            kind: Kind::FnTrait {
+                name: name.to_string(),
+                args: args.into_iter().map(Into::into).collect(),
+                ret: ret.map(|ret| Box::new(ret.into())),
+            },
+        }
     }
+
+    /// Return a new qualified path to the associated item `item` of `qself`
+    /// as implemented for the trait `as_trait`, e.g. `<T as Trait>::Item`.
+    ///
+    /// The item can have generics, e.g. `Iter<'a>` for generic associated
+    /// types.
+    ///
+    /// ```
+    /// use codegen::Type;
+    ///
+    /// let ty = Type::qualified("Vec<T>", "IntoIterator", "IntoIter");
+    /// assert_eq!(ty.qualified_self().unwrap().name(), "Vec");
+    /// assert_eq!(ty.qualified_trait().unwrap().name(), "IntoIterator");
+    /// assert_eq!(ty.name(), "IntoIter");
+    /// ```
+    pub fn qualified<S, T, I>(qself: S, as_trait: T, item: I) -> Self
+    where
+        S: Into<Type>,
+        T: Into<Type>,
+        I: Into<Type>,
     {
-        let ty = Type::new("foo::Vec<u8>");
-        assert_eq!(ty.name, "foo::Vec");
-        assert_eq!(ty.generics.iter().map(|generic| generic.name().as_str()).collect::<Vec<&str>>().join(" "), "u8");
+        Type {
+            kind: Kind::Qualified {
+                qself: Box::new(qself.into()),
+                as_trait: Some(Box::new(as_trait.into())),
+                assoc: Box::new(item.into()),
+            },
+        }
     }
+
+    /// Return the unit type `()`.
+    pub fn unit() -> Self {
+        Type {
+            kind: Kind::Tuple(Vec::new()),
+        }
+    }
+
+    /// Return the never type `!`.
+    pub fn never() -> Self {
+        Type { kind: Kind::Never }
+    }
+
+    /// Return a new tuple type, e.g. `(A, B)`.
+    ///
+    /// An empty list of elements produces the unit type `()`.
+    pub fn tuple<I, T>(elems: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Type>,
     {
-        let ty = Type::new("Vec<Vec<u8>>");
-        assert_eq!(ty.name, "Vec");
-        assert_eq!(ty.generics.iter().map(|generic| generic.name().as_str()).collect::<Vec<&str>>().join(" "), "Vec");
+        Type {
+            kind: Kind::Tuple(elems.into_iter().map(Into::into).collect()),
+        }
     }
+
+    /// Return a new array type, e.g. `[T; 16]`.
+    ///
+    /// The length can be either a literal or a const expression, e.g. `N`.
+    pub fn array<T>(inner: T, len: impl ToString) -> Self
+    where
+        T: Into<Type>,
     {
-        let ty = Type::new("BTreeMap<u8, u8>");
-        assert_eq!(ty.name, "BTreeMap");
-        assert_eq!(ty.generics.iter().map(|generic| generic.name().as_str()).collect::<Vec<&str>>().join(" "), "u8 u8");
+        Type {
+            kind: Kind::Array {
+                inner: Box::new(inner.into()),
+                len: len.to_string(),
+            },
+        }
     }
+
+    /// Return a new slice type, e.g. `[T]`.
+    ///
+    /// Use `Type::reference(Type::slice(ty))` to get `&[T]`.
+    pub fn slice<T>(inner: T) -> Self
+    where
+        T: Into<Type>,
     {
-        let ty = Type::new("BTreeMap<Vec<u8>, BTreeMap<u64, String>>");
-        assert_eq!(ty.name, "BTreeMap");
-        assert_eq!(ty.generics.iter().map(|generic| generic.name().as_str()).collect::<Vec<&str>>().join(" "), "Vec BTreeMap");
+        Type {
+            kind: Kind::Slice(Box::new(inner.into())),
+        }
+    }
 
-        let mut ret = String::new();
-        ty.fmt(&mut Formatter::new(&mut ret)).unwrap();
-        assert_eq!(ret, "BTreeMap<Vec<u8>, BTreeMap<u64, String>>");
+    /// Returns the name of the type
+    ///
+    /// For wrapper types such as references, this is the name of the inner
+    /// type (see [`inner`]). For qualified paths, this is the name of the
+    /// associated item, e.g. `Output` in `<T as Trait>::Output`. Types without
+    /// a name, such as tuples, return an empty string.
+    ///
+    /// [`inner`]: #method.inner
+    pub fn name(&self) -> &String {
+        static NO_NAME: String = String::new();
+
+        match self.kind {
+            Kind::Path { ref name, .. } | Kind::FnTrait { ref name, .. } => name,
+            Kind::Qualified { ref assoc, .. } => assoc.name(),
+            _ => self.inner().map_or(&NO_NAME, Type::name),
+        }
     }
-    {
-        let ty = Type::new("Result<&'a mut Foo<Bar>>");
-        assert_eq!(ty.name, "Result");
-        assert_eq!(ty.generics.iter().map(|generic| generic.name().as_str()).collect::<Vec<&str>>().join(" "), "& 'a mut Foo < Bar >");
+
+    /// Set the name of the type, keeping its generics.
+    ///
+    /// For wrapper types and qualified paths, this sets the name of the type
+    /// returned by [`name`].
+    ///
+    /// [`name`]: #method.name
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type does not have a name, e.g. a tuple.
+    pub fn set_name(&mut self, name: impl ToString) -> &mut Self {
+        match self.kind {
+            Kind::Path {
+                name: ref mut dst, ..
+            }
+            | Kind::FnTrait {
+                name: ref mut dst, ..
+            } => *dst = name.to_string(),
+            Kind::Qualified { ref mut assoc, .. } => {
+                assoc.set_name(name);
+            }
+            _ => {
+                self.inner_mut()
+                    .expect("type does not have a name")
+                    .set_name(name);
+            }
+        }
+        self
     }
-}
\ No newline at end of file
+
+    /// Returns the generics of the type
+    ///
+    /// This includes const generic arguments, in the order they were added.
+    ///
+    /// For wrapper types such as references, these are the generics of the
+    /// inner type.
+    pub fn generics(&self) -> &Vec<Type> {
+        static NO_GENERICS: Vec<Type> = Vec::new();
+
+        match self.kind {
+            Kind::Path { ref generics, .. } => generics,
+            Kind::Qualified { ref assoc, .. } => assoc.generics(),
+            _ => self.inner().map_or(&NO_GENERICS, Type::generics),
+        }
+    }
+
+    /// Returns the lifetime arguments of the type, e.g. `'a` in `Foo<'a>`.
+    ///
+    /// For wrapper types such as references, these are the lifetimes of the
+    /// inner type.
+    pub fn lifetimes(&self) -> &Vec<Lifetime> {
+        static NO_LIFETIMES: Vec<Lifetime> = Vec::new();
+
+        match self.kind {
+            Kind::Path { ref lifetimes, .. } => lifetimes,
+            Kind::Qualified { ref assoc, .. } => assoc.lifetimes(),
+            _ => self.inner().map_or(&NO_LIFETIMES, Type::lifetimes),
+        }
+    }
+
+    /// Returns the inner type of a wrapper type.
+    ///
+    /// This is the referenced type of a reference or raw pointer and the
+    /// element type of an array or slice.
+    pub fn inner(&self) -> Option<&Type> {
+        match self.kind {
+            Kind::Reference { ref inner, .. } => Some(inner),
+            Kind::Ptr { ref inner, .. } => Some(inner),
+            Kind::Array { ref inner, .. } => Some(inner),
+            Kind::Slice(ref inner) | Kind::Paren(ref inner) => Some(inner),
+            _ => None,
+        }
+    }
+
+    fn inner_mut(&mut self) -> Option<&mut Type> {
+        match self.kind {
+            Kind::Reference { ref mut inner, .. } => Some(inner),
+            Kind::Ptr { ref mut inner, .. } => Some(inner),
+            Kind::Array { ref mut inner, .. } => Some(inner),
+            Kind::Slice(ref mut inner) | Kind::Paren(ref mut inner) => Some(inner),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this type is a reference.
+    pub fn is_reference(&self) -> bool {
+        matches!(self.kind, Kind::Reference { .. })
+    }
+
+    /// Returns the lifetime of the reference, e.g. `'a` in `&'a T`.
+    pub fn reference_lifetime(&self) -> Option<&Lifetime> {
+        match self.kind {
+            Kind::Reference { ref lifetime, .. } => lifetime.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Set the lifetime of the reference, e.g. `'a` in `&'a T`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type is not a reference.
+    pub fn set_reference_lifetime<L>(&mut self, lifetime: L) -> &mut Self
+    where
+        L: Into<Lifetime>,
+    {
+        match self.kind {
+            Kind::Reference {
+                lifetime: ref mut dst,
+                ..
+            } => *dst = Some(lifetime.into()),
+            _ => panic!("type is not a reference"),
+        }
+        self
+    }
+
+    /// Returns `true` if this type is a raw pointer.
+    pub fn is_ptr(&self) -> bool {
+        matches!(self.kind, Kind::Ptr { .. })
+    }
+
+    /// Returns the associated type bindings of the type, e.g. `Item = u8` in
+    /// `Iterator<Item = u8>`.
+    pub fn bindings(&self) -> &Vec<(String, Type)> {
+        static NO_BINDINGS: Vec<(String, Type)> = Vec::new();
+
+        match self.kind {
+            Kind::Path { ref bindings, .. } => bindings,
+            Kind::Qualified { ref assoc, .. } => assoc.bindings(),
+            _ => self.inner().map_or(&NO_BINDINGS, Type::bindings),
+        }
+    }
+
+    /// Returns the trait bounds of the type if it is a trait object or an
+    /// `impl Trait` type.
+    pub fn bounds(&self) -> Option<&Vec<Type>> {
+        match self.kind {
+            Kind::Dyn { ref bounds, .. } | Kind::Impl { ref bounds, .. } => Some(bounds),
+            _ => None,
+        }
+    }
+
+    /// Returns the arguments of the type if it is a function pointer or a
+    /// closure trait such as `Fn(u8)`.
+    pub fn fn_args(&self) -> Option<&Vec<Type>> {
+        match self.kind {
+            Kind::BareFn { ref args, .. } | Kind::FnTrait { ref args, .. } => Some(args),
+            _ => None,
+        }
+    }
+
+    /// Returns the return type of the function pointer or closure trait, if
+    /// any.
+    pub fn fn_ret(&self) -> Option<&Type> {
+        match self.kind {
+            Kind::BareFn { ref ret, .. } | Kind::FnTrait { ref ret, .. } => ret.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Set the return type of the function pointer or closure trait.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type is not a function pointer or a
+    /// closure trait.
+    pub fn set_fn_ret<T>(&mut self, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        match self.kind {
+            Kind::BareFn { ref mut ret, .. } | Kind::FnTrait { ref mut ret, .. } => {
+                *ret = Some(Box::new(ty.into()))
+            }
+            _ => panic!("type is not a function pointer"),
+        }
+        self
+    }
+
+    /// Set whether the function pointer is `unsafe` or not.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type is not a function pointer.
+    pub fn set_fn_unsafe(&mut self, r#unsafe: bool) -> &mut Self {
+        match self.kind {
+            Kind::BareFn {
+                r#unsafe: ref mut dst,
+                ..
+            } => *dst = r#unsafe,
+            _ => panic!("type is not a function pointer"),
+        }
+        self
+    }
+
+    /// Specify an `extern` ABI for the function pointer, e.g. `"C"`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type is not a function pointer.
+    pub fn set_fn_abi(&mut self, abi: impl ToString) -> &mut Self {
+        match self.kind {
+            Kind::BareFn {
+                abi: ref mut dst, ..
+            } => *dst = Some(abi.to_string()),
+            _ => panic!("type is not a function pointer"),
+        }
+        self
+    }
+
+    /// Set whether the function pointer is C-variadic, e.g. `fn(*const c_char, ...)`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type is not a function pointer.
+    pub fn set_fn_variadic(&mut self, variadic: bool) -> &mut Self {
+        match self.kind {
+            Kind::BareFn {
+                variadic: ref mut dst,
+                ..
+            } => *dst = variadic,
+            _ => panic!("type is not a function pointer"),
+        }
+        self
+    }
+
+    /// Returns the self type of a qualified path, e.g. `T` in
+    /// `<T as Trait>::Item`.
+    pub fn qualified_self(&self) -> Option<&Type> {
+        match self.kind {
+            Kind::Qualified { ref qself, .. } => Some(qself),
+            _ => None,
+        }
+    }
+
+    /// Returns the trait of a qualified path, e.g. `Trait` in
+    /// `<T as Trait>::Item`.
+    pub fn qualified_trait(&self) -> Option<&Type> {
+        match self.kind {
+            Kind::Qualified { ref as_trait, .. } => as_trait.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the associated item of a qualified path, e.g. `Item` in
+    /// `<T as Trait>::Item`.
+    pub fn qualified_item(&self) -> Option<&Type> {
+        match self.kind {
+            Kind::Qualified { ref assoc, .. } => Some(assoc),
+            _ => None,
+        }
+    }
+
+    /// Returns the expression of a const generic argument, e.g. `16` in
+    /// `ArrayVec<u8, 16>`.
+    pub fn const_expr(&self) -> Option<&String> {
+        match self.kind {
+            Kind::Const(ref expr) => Some(expr),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of the type if it is a tuple.
+    pub fn tuple_elems(&self) -> Option<&Vec<Type>> {
+        match self.kind {
+            Kind::Tuple(ref elems) => Some(elems),
+            _ => None,
+        }
+    }
+
+    /// Returns the length of the type if it is an array.
+    pub fn array_len(&self) -> Option<&String> {
+        match self.kind {
+            Kind::Array { ref len, .. } => Some(len),
+            _ => None,
+        }
+    }
+
+    /// Set the length of the array, either a literal or a const expression.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type is not an array.
+    pub fn set_array_len(&mut self, len: impl ToString) -> &mut Self {
+        match self.kind {
+            Kind::Array {
+                len: ref mut dst, ..
+            } => *dst = len.to_string(),
+            _ => panic!("type is not an array"),
+        }
+        self
+    }
+
+    /// Returns `true` if this type is a slice.
+    pub fn is_slice(&self) -> bool {
+        matches!(self.kind, Kind::Slice(..))
+    }
+
+    /// Returns `true` if this type is the unit type `()`.
+    pub fn is_unit(&self) -> bool {
+        matches!(self.kind, Kind::Tuple(ref elems) if elems.is_empty())
+    }
+
+    /// Returns `true` if this type is the never type `!`.
+    pub fn is_never(&self) -> bool {
+        matches!(self.kind, Kind::Never)
+    }
+
+    /// Returns an iterator over the segments of the type's path, e.g. `std`,
+    /// `vec` and `Vec` for `std::vec::Vec<T>`.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.name()
+            .split("::")
+            .filter(|segment| !segment.is_empty())
+    }
+
+    /// Returns the last segment of the type's path, e.g. `Vec` for
+    /// `std::vec::Vec<T>`.
+    pub fn last_segment(&self) -> &str {
+        self.segments().last().unwrap_or("")
+    }
+
+    /// Returns the key for sorting
+    pub fn key_for_sorting(&self) -> &str {
+        let name = self.name();
+        match name.rfind("::") {
+            Some(index) => &name[index + 2..],
+            None => name,
+        }
+    }
+
+    /// Add a generic to the type.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type cannot take generics, e.g. a tuple.
+    pub fn generic<T>(&mut self, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        match self.kind {
+            Kind::Path {
+                ref name,
+                ref mut generics,
+                ..
+            } => {
+                // Make sure that the name doesn't already include generics
+                assert!(!name.contains('<'), "type name already includes generics");
+
+                generics.push(ty.into());
+            }
+            Kind::Qualified { ref mut assoc, .. } => {
+                assoc.generic(ty);
+            }
+            _ => {
+                self.inner_mut()
+                    .expect("type does not take generics")
+                    .generic(ty);
+            }
+        }
+        self
+    }
+
+    /// Add a const generic argument to the type, e.g. `16` in
+    /// `ArrayVec<u8, 16>`.
+    ///
+    /// The expression can be a literal, a const parameter or a block, e.g.
+    /// `{ N * 2 }`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type cannot take generics, e.g. a tuple.
+    pub fn const_generic(&mut self, expr: impl ToString) -> &mut Self {
+        self.generic(Type {
+            kind: Kind::Const(expr.to_string()),
+        })
+    }
+
+    /// Add a lifetime argument to the type.
+    ///
+    /// Lifetime arguments are always formatted before any generics, e.g.
+    /// `Foo<'a, T>`. For trait objects and `impl Trait` types, this adds a
+    /// lifetime bound instead, e.g. `dyn Trait + 'a`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type cannot take lifetimes, e.g. a tuple.
+    pub fn lifetime<L>(&mut self, lifetime: L) -> &mut Self
+    where
+        L: Into<Lifetime>,
+    {
+        match self.kind {
+            Kind::Path {
+                ref mut lifetimes, ..
+            }
+            | Kind::Dyn {
+                ref mut lifetimes, ..
+            }
+            | Kind::Impl {
+                ref mut lifetimes, ..
+            } => {
+                lifetimes.push(lifetime.into());
+            }
+            Kind::Qualified { ref mut assoc, .. } => {
+                assoc.lifetime(lifetime);
+            }
+            _ => {
+                self.inner_mut()
+                    .expect("type does not take lifetimes")
+                    .lifetime(lifetime);
+            }
+        }
+        self
+    }
+
+    /// Add an associated type binding to the type, e.g. `Item = u8` in
+    /// `Iterator<Item = u8>`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type cannot take generics, e.g. a tuple.
+    pub fn binding<T>(&mut self, name: impl ToString, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        match self.kind {
+            Kind::Path {
+                ref mut bindings, ..
+            } => {
+                bindings.push((name.to_string(), ty.into()));
+            }
+            Kind::Qualified { ref mut assoc, .. } => {
+                assoc.binding(name, ty);
+            }
+            _ => {
+                self.inner_mut()
+                    .expect("type does not take generics")
+                    .binding(name, ty);
+            }
+        }
+        self
+    }
+
+    /// Add a trait bound to the trait object or `impl Trait` type, e.g.
+    /// `Send` in `dyn Trait + Send`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the type is neither a trait object nor an
+    /// `impl Trait` type.
+    pub fn bound<T>(&mut self, ty: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        match self.kind {
+            Kind::Dyn { ref mut bounds, .. } | Kind::Impl { ref mut bounds, .. } => {
+                bounds.push(ty.into())
+            }
+            _ => panic!("type does not take bounds"),
+        }
+        self
+    }
+
+    /// Return a new type referring to the associated item `name` of this
+    /// type, e.g. `T::Item`.
+    ///
+    /// Types that can't be followed by `::` directly, such as `Vec<T>`, are
+    /// wrapped in a qualified path, e.g. `<Vec<T>>::Item`.
+    pub fn associated(&self, name: impl ToString) -> Type {
+        match self.kind {
+            Kind::Qualified {
+                ref qself,
+                ref as_trait,
+                ref assoc,
+            } if assoc.generics().is_empty() && assoc.lifetimes().is_empty() => Type {
+                kind: Kind::Qualified {
+                    qself: qself.clone(),
+                    as_trait: as_trait.clone(),
+                    assoc: Box::new(assoc.associated(name)),
+                },
+            },
+            _ => Type {
+                kind: Kind::Qualified {
+                    qself: Box::new(self.clone()),
+                    as_trait: None,
+                    assoc: Box::new(Type::new(name)),
+                },
+            },
+        }
+    }
+
+    /// Return this type wrapped in an `Option`, e.g. `Option<T>`.
+    pub fn option(&self) -> Type {
+        self.wrap("Option")
+    }
+
+    /// Return this type wrapped in a `Result` with the given error type,
+    /// e.g. `Result<T, E>`.
+    pub fn result<E>(&self, err: E) -> Type
+    where
+        E: Into<Type>,
+    {
+        let mut ret = self.wrap("Result");
+        ret.generic(err);
+        ret
+    }
+
+    /// Return this type wrapped in a `Vec`, e.g. `Vec<T>`.
+    pub fn vec(&self) -> Type {
+        self.wrap("Vec")
+    }
+
+    /// Return this type wrapped in a `Box`, e.g. `Box<T>`.
+    pub fn boxed(&self) -> Type {
+        self.wrap("Box")
+    }
+
+    /// Return this type wrapped in an `Arc`, e.g. `Arc<T>`.
+    ///
+    /// `Arc` is not part of the prelude, so the generated code needs to
+    /// import `std::sync::Arc`.
+    pub fn arc(&self) -> Type {
+        self.wrap("Arc")
+    }
+
+    /// Return this type wrapped in an `Rc`, e.g. `Rc<T>`.
+    ///
+    /// `Rc` is not part of the prelude, so the generated code needs to
+    /// import `std::rc::Rc`.
+    pub fn rc(&self) -> Type {
+        self.wrap("Rc")
+    }
+
+    fn wrap(&self, name: &str) -> Type {
+        let mut ret = Type::named(name.to_string());
+        ret.generic(self);
+        ret
+    }
+
+    /// Return a normalized copy of the type.
+    ///
+    /// Leading `::` are removed from all paths, so that `::std::vec::Vec`
+    /// and `std::vec::Vec` are the same type.
+    pub fn normalize(&self) -> Type {
+        let mut ret = self.clone();
+        ret.visit_mut(&mut |ty| {
+            if let Kind::Path { ref mut name, .. } | Kind::FnTrait { ref mut name, .. } = ty.kind {
+                let trimmed = name.trim_start();
+                if let Some(stripped) = trimmed.strip_prefix("::") {
+                    *name = stripped.trim_start().to_string();
+                }
+            }
+        });
+        ret
+    }
+
+    /// Return a copy of the type where the generics are formatted as
+    /// parameters of a definition.
+    ///
+    /// Generics named in `consts` are formatted as const parameters, e.g.
+    /// `const N: usize`, generics named in `defaults` are formatted with their
+    /// default, e.g. `T = String`, and the parameters are ordered as lifetimes,
+    /// types, then consts.
+    pub(crate) fn with_generic_params(
+        &self,
+        defaults: &[(String, Type)],
+        consts: &[(String, Type)],
+    ) -> Type {
+        let mut ret = self.clone();
+        if let Kind::Path {
+            ref mut generics, ..
+        } = ret.kind
+        {
+            for generic in generics.iter_mut() {
+                let mut param = match consts.iter().find(|(name, _)| name == generic.name()) {
+                    Some((name, ty)) => format!("const {}: {}", name, ty),
+                    None => generic.name().clone(),
+                };
+                if let Some((_, default)) = defaults.iter().find(|(name, _)| name == generic.name())
+                {
+                    param = format!("{} = {}", param, default);
+                }
+                if param != *generic.name() {
+                    *generic = Type::named(param);
+                }
+            }
+            generics.sort_by_key(|generic| param_order(generic.name()));
+        }
+        ret
+    }
+
+    /// Call `f` on this type and all of the types nested in it.
+    pub(crate) fn visit_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut Type) + ?Sized,
+    {
+        match self.kind {
+            Kind::Path {
+                ref mut generics,
+                ref mut bindings,
+                ref mut constraints,
+                ..
+            } => {
+                generics.iter_mut().for_each(|ty| ty.visit_mut(f));
+                bindings.iter_mut().for_each(|(_, ty)| ty.visit_mut(f));
+                constraints
+                    .iter_mut()
+                    .for_each(|bound| bound.visit_types_mut(f));
+            }
+            Kind::Reference { ref mut inner, .. }
+            | Kind::Ptr { ref mut inner, .. }
+            | Kind::Array { ref mut inner, .. }
+            | Kind::Slice(ref mut inner)
+            | Kind::Paren(ref mut inner) => inner.visit_mut(f),
+            Kind::Tuple(ref mut elems) => elems.iter_mut().for_each(|ty| ty.visit_mut(f)),
+            Kind::Dyn { ref mut bounds, .. } | Kind::Impl { ref mut bounds, .. } => {
+                bounds.iter_mut().for_each(|ty| ty.visit_mut(f));
+            }
+            Kind::Qualified {
+                ref mut qself,
+                ref mut as_trait,
+                ref mut assoc,
+            } => {
+                qself.visit_mut(f);
+                if let Some(ref mut as_trait) = *as_trait {
+                    as_trait.visit_mut(f);
+                }
+                assoc.visit_mut(f);
+            }
+            Kind::BareFn {
+                ref mut args,
+                ref mut ret,
+                ..
+            }
+            | Kind::FnTrait {
+                ref mut args,
+                ref mut ret,
+                ..
+            } => {
+                args.iter_mut().for_each(|ty| ty.visit_mut(f));
+                if let Some(ref mut ret) = *ret {
+                    ret.visit_mut(f);
+                }
+            }
+            Kind::Never | Kind::Const(_) => {}
+        }
+
+        f(self);
+    }
+
+    /// Returns the normalized string representation of the type, ignoring
+    /// any formatting differences in verbatim types.
+    fn canonical(&self) -> String {
+        let mut ret = String::new();
+        self.normalize().fmt(&mut Formatter::new(&mut ret)).unwrap();
+
+        // only keep whitespace that separates two words, e.g. `dyn Trait`
+        let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+        let mut canonical = String::with_capacity(ret.len());
+        let mut chars = ret.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c.is_whitespace() {
+                let prev = canonical.chars().last().is_some_and(is_word);
+                let next = chars.peek().is_some_and(|&c| is_word(c));
+                if prev && next && !canonical.ends_with(' ') {
+                    canonical.push(' ');
+                }
+            } else {
+                canonical.push(c);
+            }
+        }
+        canonical
+    }
+
+    /// Return a copy of the type with `prefix` removed from the start of
+    /// every path in it, e.g. `crate::foo::Bar` becomes `Bar` for the prefix
+    /// `crate::foo`.
+    ///
+    /// Paths that don't start with all segments of `prefix` are unchanged.
+    pub fn strip_prefix(&self, prefix: impl ToString) -> Type {
+        self.replace_prefix(prefix, "")
+    }
+
+    /// Return a copy of the type with `from` replaced by `to` at the start of
+    /// every path in it, e.g. `crate::foo::Bar` becomes `super::Bar` when
+    /// replacing `crate::foo` with `super`.
+    ///
+    /// Paths that don't start with all segments of `from` are unchanged.
+    pub fn replace_prefix(&self, from: impl ToString, to: impl ToString) -> Type {
+        let from = from.to_string();
+        let to = to.to_string();
+
+        let mut ret = self.clone();
+        ret.visit_mut(&mut |ty| {
+            if let Kind::Path { ref mut name, .. } | Kind::FnTrait { ref mut name, .. } = ty.kind {
+                if let Some(replaced) = replace_path_prefix(name, &from, &to) {
+                    *name = replaced;
+                }
+            }
+        });
+        ret
+    }
+
+    /// Rewrite the `Type` with the provided path
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the path of the type is already absolute or
+    /// the type does not have a path, e.g. a tuple.
+    pub fn path(&self, path: impl ToString) -> Type {
+        let mut ret = self.clone();
+
+        match ret.kind {
+            Kind::Path { ref mut name, .. } | Kind::FnTrait { ref mut name, .. } => {
+                assert!(!name.starts_with("::"), "type path is already absolute");
+
+                name.insert_str(0, "::");
+                name.insert_str(0, &path.to_string());
+            }
+            _ => {
+                let inner = ret.inner_mut().expect("type does not have a path");
+                *inner = inner.path(path);
+            }
+        }
+
+        ret
+    }
+
+    /// Formats the struct using the given formatter.
+    pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Path {
+                ref name,
+                ref lifetimes,
+                ref generics,
+                ref bindings,
+                ref constraints,
+            } => {
+                write!(fmt, "{}", name)?;
+                Type::fmt_args(lifetimes, generics, bindings, constraints, fmt)
+            }
+            Kind::Reference {
+                ref lifetime,
+                mutable,
+                ref inner,
+            } => {
+                write!(fmt, "&")?;
+                if let Some(ref lifetime) = *lifetime {
+                    lifetime.fmt(fmt)?;
+                    write!(fmt, " ")?;
+                }
+                if mutable {
+                    write!(fmt, "mut ")?;
+                }
+                inner.fmt(fmt)
+            }
+            Kind::Ptr { mutable, ref inner } => {
+                if mutable {
+                    write!(fmt, "*mut ")?;
+                } else {
+                    write!(fmt, "*const ")?;
+                }
+                inner.fmt(fmt)
+            }
+            Kind::Tuple(ref elems) => {
+                write!(fmt, "(")?;
+                for (i, ty) in elems.iter().enumerate() {
+                    if i != 0 {
+                        write!(fmt, ", ")?
+                    }
+                    ty.fmt(fmt)?;
+                }
+                if elems.len() == 1 {
+                    write!(fmt, ",")?;
+                }
+                write!(fmt, ")")
+            }
+            Kind::Array { ref inner, ref len } => {
+                write!(fmt, "[")?;
+                inner.fmt(fmt)?;
+                write!(fmt, "; {}]", len)
+            }
+            Kind::Slice(ref inner) => {
+                write!(fmt, "[")?;
+                inner.fmt(fmt)?;
+                write!(fmt, "]")
+            }
+            Kind::Paren(ref inner) => {
+                write!(fmt, "(")?;
+                inner.fmt(fmt)?;
+                write!(fmt, ")")
+            }
+            Kind::Dyn {
+                ref bounds,
+                ref lifetimes,
+            } => {
+                write!(fmt, "dyn ")?;
+                Type::fmt_bounds(bounds, lifetimes, fmt)
+            }
+            Kind::Impl {
+                ref bounds,
+                ref lifetimes,
+            } => {
+                write!(fmt, "impl ")?;
+                Type::fmt_bounds(bounds, lifetimes, fmt)
+            }
+            Kind::Qualified {
+                ref qself,
+                ref as_trait,
+                ref assoc,
+            } => {
+                if as_trait.is_none() && qself.is_projection_base() {
+                    qself.fmt(fmt)?;
+                    write!(fmt, "::")?;
+                } else {
+                    write!(fmt, "<")?;
+                    qself.fmt(fmt)?;
+                    if let Some(ref as_trait) = *as_trait {
+                        write!(fmt, " as ")?;
+                        as_trait.fmt(fmt)?;
+                    }
+                    write!(fmt, ">::")?;
+                }
+                assoc.fmt(fmt)
+            }
+            Kind::Never => write!(fmt, "!"),
+            Kind::Const(ref expr) => write!(fmt, "{}", expr),
+            Kind::BareFn {
+                r#unsafe,
+                ref abi,
+                ref args,
+                variadic,
+                ref ret,
+            } => {
+                if r#unsafe {
+                    write!(fmt, "unsafe ")?;
+                }
+                if let Some(ref abi) = *abi {
+                    write!(fmt, "extern \"{}\" ", abi)?;
+                }
+                write!(fmt, "fn(")?;
+                for (i, ty) in args.iter().enumerate() {
+                    if i != 0 {
+                        write!(fmt, ", ")?
+                    }
+                    ty.fmt(fmt)?;
+                }
+                if variadic {
+                    if !args.is_empty() {
+                        write!(fmt, ", ")?
+                    }
+                    write!(fmt, "...")?;
+                }
+                write!(fmt, ")")?;
+                if let Some(ref ret) = *ret {
+                    write!(fmt, " -> ")?;
+                    ret.fmt(fmt)?;
+                }
+                Ok(())
+            }
+            Kind::FnTrait {
+                ref name,
+                ref args,
+                ref ret,
+            } => {
+                write!(fmt, "{}(", name)?;
+                for (i, ty) in args.iter().enumerate() {
+                    if i != 0 {
+                        write!(fmt, ", ")?
+                    }
+                    ty.fmt(fmt)?;
+                }
+                write!(fmt, ")")?;
+                if let Some(ref ret) = *ret {
+                    write!(fmt, " -> ")?;
+                    ret.fmt(fmt)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the type formatted for use in expression position, see
+    /// [`fmt_turbofish`].
+    ///
+    /// [`fmt_turbofish`]: #method.fmt_turbofish
+    pub fn turbofish(&self) -> String {
+        let mut ret = String::new();
+        self.fmt_turbofish(&mut Formatter::new(&mut ret)).unwrap();
+        ret
+    }
+
+    /// Formats the type for use in expression position, e.g. `Vec::<u8>` so
+    /// that `Vec::<u8>::new()` can be generated in a function body.
+    ///
+    /// Only the outermost path uses the turbofish syntax, nested generics are
+    /// formatted as usual. Types that aren't paths, such as references or
+    /// slices, are wrapped in angle brackets, e.g. `<[u8]>`.
+    ///
+    /// ```
+    /// use codegen::Type;
+    ///
+    /// assert_eq!(Type::new("Vec<Option<u8>>").turbofish(), "Vec::<Option<u8>>");
+    /// assert_eq!(Type::new("&str").turbofish(), "<&str>");
+    /// ```
+    pub fn fmt_turbofish(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Path {
+                ref name,
+                ref lifetimes,
+                ref generics,
+                ref bindings,
+                ref constraints,
+            } => {
+                write!(fmt, "{}", name)?;
+                if !lifetimes.is_empty()
+                    || !generics.is_empty()
+                    || !bindings.is_empty()
+                    || !constraints.is_empty()
+                {
+                    write!(fmt, "::")?;
+                }
+                Type::fmt_args(lifetimes, generics, bindings, constraints, fmt)
+            }
+            Kind::Qualified {
+                ref qself,
+                ref as_trait,
+                ref assoc,
+            } => {
+                if as_trait.is_none() && qself.is_projection_base() {
+                    qself.fmt(fmt)?;
+                    write!(fmt, "::")?;
+                } else {
+                    write!(fmt, "<")?;
+                    qself.fmt(fmt)?;
+                    if let Some(ref as_trait) = *as_trait {
+                        write!(fmt, " as ")?;
+                        as_trait.fmt(fmt)?;
+                    }
+                    write!(fmt, ">::")?;
+                }
+                assoc.fmt_turbofish(fmt)
+            }
+            _ => {
+                write!(fmt, "<")?;
+                self.fmt(fmt)?;
+                write!(fmt, ">")
+            }
+        }
+    }
+
+    fn fmt_args(
+        lifetimes: &[Lifetime],
+        generics: &[Type],
+        bindings: &[(String, Type)],
+        constraints: &[Bound],
+        fmt: &mut Formatter<'_>,
+    ) -> fmt::Result {
+        if !lifetimes.is_empty()
+            || !generics.is_empty()
+            || !bindings.is_empty()
+            || !constraints.is_empty()
+        {
+            write!(fmt, "<")?;
+
+            for (i, lifetime) in lifetimes.iter().enumerate() {
+                if i != 0 {
+                    write!(fmt, ", ")?
+                }
+                lifetime.fmt(fmt)?;
+            }
+
+            for (i, ty) in generics.iter().enumerate() {
+                if i != 0 || !lifetimes.is_empty() {
+                    write!(fmt, ", ")?
+                }
+                ty.fmt(fmt)?;
+            }
+
+            for (i, (name, ty)) in bindings.iter().enumerate() {
+                if i != 0 || !lifetimes.is_empty() || !generics.is_empty() {
+                    write!(fmt, ", ")?
+                }
+                write!(fmt, "{} = ", name)?;
+                ty.fmt(fmt)?;
+            }
+
+            for (i, constraint) in constraints.iter().enumerate() {
+                if i != 0 || !lifetimes.is_empty() || !generics.is_empty() || !bindings.is_empty() {
+                    write!(fmt, ", ")?
+                }
+                write!(fmt, "{}: ", constraint.name)?;
+                fmt_bound_rhs(&constraint.bound, fmt)?;
+            }
+
+            write!(fmt, ">")?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether `self` can be followed by `::` without being wrapped in
+    /// `<...>`, e.g. `T` or `T::Item`.
+    fn is_projection_base(&self) -> bool {
+        match self.kind {
+            Kind::Path {
+                ref lifetimes,
+                ref generics,
+                ref bindings,
+                ref constraints,
+                ..
+            } => {
+                lifetimes.is_empty()
+                    && generics.is_empty()
+                    && bindings.is_empty()
+                    && constraints.is_empty()
+            }
+            Kind::Qualified {
+                ref qself,
+                as_trait: None,
+                ref assoc,
+            } => qself.is_projection_base() && assoc.is_projection_base(),
+            _ => false,
+        }
+    }
+
+    fn fmt_bounds(bounds: &[Type], lifetimes: &[Lifetime], fmt: &mut Formatter<'_>) -> fmt::Result {
+        for (i, ty) in bounds.iter().enumerate() {
+            if i != 0 {
+                write!(fmt, " + ")?
+            }
+            ty.fmt(fmt)?;
+        }
+
+        for (i, lifetime) in lifetimes.iter().enumerate() {
+            if i != 0 || !bounds.is_empty() {
+                write!(fmt, " + ")?
+            }
+            lifetime.fmt(fmt)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Types are compared by their normalized representation, see
+/// [`Type::normalize`].
+///
+/// [`Type::normalize`]: struct.Type.html#method.normalize
+impl PartialEq for Type {
+    fn eq(&self, other: &Type) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Eq for Type {}
+
+impl Hash for Type {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
+}
+
+/// Returns the position of a generic parameter in a definition: lifetimes
+/// come first, then types, then consts.
+pub(crate) fn param_order(param: &str) -> u8 {
+    if param.starts_with('\'') {
+        0
+    } else if param.starts_with("const ") {
+        2
+    } else {
+        1
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ret = String::new();
+        Type::fmt(self, &mut Formatter::new(&mut ret))?;
+        f.write_str(&ret)
+    }
+}
+
+/// Types kept verbatim that aren't valid Rust tokens, e.g. `(u8`, are
+/// emitted as a `compile_error!` invocation naming the type.
+#[cfg(feature = "tokens")]
+impl quote::ToTokens for Type {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let ty = self.to_string();
+        match ty.parse::<proc_macro2::TokenStream>() {
+            Ok(ty) => tokens.extend(ty),
+            Err(_) => {
+                let message = format!("`{}` is not a valid type", ty);
+                tokens.extend(quote::quote! { compile_error!(#message) });
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Type {
+    fn from(src: &'a str) -> Self {
+        Type::new(src)
+    }
+}
+
+impl From<String> for Type {
+    fn from(src: String) -> Self {
+        Type::new(src)
+    }
+}
+
+impl<'a> From<&'a String> for Type {
+    fn from(src: &'a String) -> Self {
+        Type::new(src)
+    }
+}
+
+impl<'a> From<&'a &'a str> for Type {
+    fn from(src: &'a &'a str) -> Self {
+        Type::new(src)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Type {
+    fn from(src: Cow<'a, str>) -> Self {
+        Type::new(src)
+    }
+}
+
+impl From<Box<str>> for Type {
+    fn from(src: Box<str>) -> Self {
+        Type::new(src)
+    }
+}
+
+impl<'a> From<&'a Type> for Type {
+    fn from(src: &'a Type) -> Self {
+        src.clone()
+    }
+}
+
+impl<'a> From<&'a syn::Type> for Type {
+    fn from(src: &'a syn::Type) -> Self {
+        Type::from_syn(src)
+    }
+}
+
+impl From<syn::Path> for Type {
+    fn from(src: syn::Path) -> Self {
+        Type::from_syn_path(&src)
+    }
+}
+
+#[cfg(test)]
+fn to_string(ty: &Type) -> String {
+    let mut ret = String::new();
+    ty.fmt(&mut Formatter::new(&mut ret)).unwrap();
+    ret
+}
+
+#[test]
+fn parse_type() {
+    {
+        let ty = Type::new("u8");
+        assert_eq!(ty.name(), "u8");
+        assert!(ty.generics().is_empty());
+    }
+}
+
+#[test]
+fn parse_generic() {
+    {
+        let ty = Type::new("Vec<u8>");
+        assert_eq!(ty.name(), "Vec");
+        assert_eq!(
+            ty.generics()
+                .iter()
+                .map(|generic| generic.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "u8"
+        );
+    }
+    {
+        let ty = Type::new("foo::Vec<u8>");
+        assert_eq!(ty.name(), "foo::Vec");
+        assert_eq!(
+            ty.generics()
+                .iter()
+                .map(|generic| generic.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "u8"
+        );
+    }
+    {
+        let ty = Type::new("Vec<Vec<u8>>");
+        assert_eq!(ty.name(), "Vec");
+        assert_eq!(
+            ty.generics()
+                .iter()
+                .map(|generic| generic.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "Vec"
+        );
+    }
+    {
+        let ty = Type::new("BTreeMap<u8, u8>");
+        assert_eq!(ty.name(), "BTreeMap");
+        assert_eq!(
+            ty.generics()
+                .iter()
+                .map(|generic| generic.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "u8 u8"
+        );
+    }
+    {
+        let ty = Type::new("BTreeMap<Vec<u8>, BTreeMap<u64, String>>");
+        assert_eq!(ty.name(), "BTreeMap");
+        assert_eq!(
+            ty.generics()
+                .iter()
+                .map(|generic| generic.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "Vec BTreeMap"
+        );
+        assert_eq!(to_string(&ty), "BTreeMap<Vec<u8>, BTreeMap<u64, String>>");
+    }
+    {
+        let ty = Type::new("Result<&'a mut Foo<Bar>>");
+        assert_eq!(ty.name(), "Result");
+        assert_eq!(
+            ty.generics()
+                .iter()
+                .map(|generic| generic.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "Foo"
+        );
+        assert_eq!(to_string(&ty), "Result<&'a mut Foo<Bar>>");
+    }
+}
+
+#[test]
+fn parse_lifetime() {
+    {
+        let ty = Type::new("Foo<'a, 'static, T>");
+        assert_eq!(ty.name(), "Foo");
+        assert_eq!(
+            ty.lifetimes()
+                .iter()
+                .map(|lifetime| lifetime.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "a static"
+        );
+        assert_eq!(
+            ty.generics()
+                .iter()
+                .map(|generic| generic.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "T"
+        );
+        assert_eq!(to_string(&ty), "Foo<'a, 'static, T>");
+    }
+    {
+        let ty = Type::new("&'a str");
+        assert_eq!(ty.name(), "str");
+        assert_eq!(to_string(&ty), "&'a str");
+    }
+    {
+        let ty = Type::new("&mut Cow<'a, str>");
+        assert_eq!(ty.name(), "Cow");
+        assert_eq!(
+            ty.lifetimes()
+                .iter()
+                .map(|lifetime| lifetime.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "a"
+        );
+        assert_eq!(to_string(&ty), "&mut Cow<'a, str>");
+    }
+    {
+        let mut ty = Type::new("Foo");
+        ty.lifetime("'a").generic("T");
+        assert_eq!(to_string(&ty), "Foo<'a, T>");
+    }
+}
+
+#[test]
+fn build_reference() {
+    {
+        let ty = Type::reference("str");
+        assert!(ty.is_reference());
+        assert_eq!(to_string(&ty), "&str");
+    }
+    {
+        let mut ty = Type::mut_reference("HashMap<K, V>");
+        ty.set_reference_lifetime("a");
+        assert_eq!(ty.name(), "HashMap");
+        assert_eq!(
+            ty.generics()
+                .iter()
+                .map(|generic| generic.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "K V"
+        );
+        assert_eq!(ty.reference_lifetime().unwrap().name(), "a");
+        assert_eq!(ty.inner().unwrap().name(), "HashMap");
+        assert_eq!(to_string(&ty), "&'a mut HashMap<K, V>");
+    }
+    {
+        let mut ty = Type::reference(Type::reference("u8"));
+        ty.set_reference_lifetime("'b");
+        assert_eq!(to_string(&ty), "&'b &u8");
+    }
+}
+
+#[test]
+fn parse_tuple() {
+    {
+        let ty = Type::new("()");
+        assert!(ty.is_unit());
+        assert_eq!(to_string(&ty), "()");
+    }
+    {
+        let ty = Type::new("(u8,)");
+        assert_eq!(ty.tuple_elems().unwrap().len(), 1);
+        assert_eq!(to_string(&ty), "(u8,)");
+    }
+    {
+        let ty = Type::new("(A, Vec<B>, (C, D))");
+        let elems = ty.tuple_elems().unwrap();
+        assert_eq!(
+            elems
+                .iter()
+                .map(|elem| elem.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "A Vec "
+        );
+        assert_eq!(elems[1].generics()[0].name(), "B");
+        assert_eq!(to_string(&ty), "(A, Vec<B>, (C, D))");
+    }
+    {
+        let ty = Type::new("Result<(), (u8, u8)>");
+        assert!(ty.generics()[0].is_unit());
+        assert_eq!(to_string(&ty), "Result<(), (u8, u8)>");
+    }
+    {
+        let ty = Type::tuple(vec![Type::reference("str"), Type::new("usize")]);
+        assert_eq!(to_string(&ty), "(&str, usize)");
+        assert_eq!(to_string(&Type::tuple(Vec::<Type>::new())), "()");
+    }
+}
+
+#[test]
+fn parse_array_and_slice() {
+    {
+        let ty = Type::new("[u8; 32]");
+        assert_eq!(ty.name(), "u8");
+        assert_eq!(ty.array_len().unwrap(), "32");
+        assert_eq!(to_string(&ty), "[u8; 32]");
+    }
+    {
+        let ty = Type::new("&'a [Option<T>]");
+        assert_eq!(ty.name(), "Option");
+        assert!(ty.inner().unwrap().is_slice());
+        assert_eq!(to_string(&ty), "&'a [Option<T>]");
+    }
+    {
+        let ty = Type::new("Box<[[f32; N]]>");
+        assert_eq!(to_string(&ty), "Box<[[f32; N]]>");
+    }
+    {
+        let mut ty = Type::array("u8", 4);
+        assert_eq!(to_string(&ty), "[u8; 4]");
+
+        ty.set_array_len("N * 2");
+        assert_eq!(to_string(&ty), "[u8; N * 2]");
+    }
+    {
+        let ty = Type::reference(Type::slice("u16"));
+        assert_eq!(to_string(&ty), "&[u16]");
+    }
+}
+
+#[test]
+fn parse_ptr() {
+    {
+        let ty = Type::new("*const c_char");
+        assert!(ty.is_ptr());
+        assert_eq!(ty.name(), "c_char");
+        assert_eq!(to_string(&ty), "*const c_char");
+    }
+    {
+        let ty = Type::new("*mut *mut Foo<T>");
+        assert_eq!(ty.name(), "Foo");
+        assert_eq!(to_string(&ty), "*mut *mut Foo<T>");
+    }
+    {
+        let ty = Type::new("Option<&[*const u8]>");
+        assert_eq!(to_string(&ty), "Option<&[*const u8]>");
+    }
+    {
+        assert_eq!(to_string(&Type::const_ptr("u8")), "*const u8");
+        assert_eq!(
+            to_string(&Type::mut_ptr(Type::const_ptr("c_void"))),
+            "*mut *const c_void"
+        );
+    }
+}
+
+#[test]
+fn parse_dyn_trait() {
+    {
+        let ty = Type::new("Box<dyn Error + Send + Sync + 'static>");
+        let bounds = ty.generics()[0].bounds().unwrap();
+        assert_eq!(
+            bounds
+                .iter()
+                .map(|bound| bound.name().as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            "Error Send Sync"
+        );
+        assert_eq!(to_string(&ty), "Box<dyn Error + Send + Sync + 'static>");
+    }
+    {
+        let ty = Type::new("&'a mut dyn Iterator<Item = &'a u8>");
+        let bound = &ty.inner().unwrap().bounds().unwrap()[0];
+        assert_eq!(bound.bindings()[0].0, "Item");
+        assert_eq!(to_string(&ty), "&'a mut dyn Iterator<Item = &'a u8>");
+    }
+    {
+        let mut ty = Type::dyn_trait("Iterator<Item = u8>");
+        ty.bound("Send").lifetime("'static");
+        assert_eq!(to_string(&ty), "dyn Iterator<Item = u8> + Send + 'static");
+    }
+    {
+        let mut ty = Type::new("Stream");
+        ty.generic("T").binding("Error", "io::Error").lifetime("a");
+        assert_eq!(to_string(&ty), "Stream<'a, T, Error = io::Error>");
+    }
+}
+
+#[test]
+fn parse_impl_trait() {
+    {
+        let ty = Type::new("impl Iterator<Item = Foo> + '_");
+        let bounds = ty.bounds().unwrap();
+        assert_eq!(bounds[0].name(), "Iterator");
+        assert_eq!(bounds[0].bindings()[0].1.name(), "Foo");
+        assert_eq!(to_string(&ty), "impl Iterator<Item = Foo> + '_");
+    }
+    {
+        let ty = Type::new("Pin<Box<impl Future<Output = ()> + Send>>");
+        assert_eq!(to_string(&ty), "Pin<Box<impl Future<Output = ()> + Send>>");
+    }
+    {
+        let mut ty = Type::impl_trait("AsRef<str>");
+        ty.bound("Send").lifetime("a");
+        assert_eq!(to_string(&ty), "impl AsRef<str> + Send + 'a");
+    }
+}
+
+#[test]
+fn parse_fn_ptr() {
+    {
+        let ty = Type::new("unsafe extern \"C\" fn(*const u8) -> i32");
+        assert_eq!(ty.fn_args().unwrap()[0].name(), "u8");
+        assert_eq!(ty.fn_ret().unwrap().name(), "i32");
+        assert_eq!(to_string(&ty), "unsafe extern \"C\" fn(*const u8) -> i32");
+    }
+    {
+        let ty = Type::new("Option<fn(&str, usize)>");
+        assert_eq!(to_string(&ty), "Option<fn(&str, usize)>");
+    }
+    {
+        let ty = Type::new("unsafe extern \"C\" fn(*const c_char, ...) -> c_int");
+        assert_eq!(
+            to_string(&ty),
+            "unsafe extern \"C\" fn(*const c_char, ...) -> c_int"
+        );
+    }
+    {
+        let mut ty = Type::fn_ptr(vec![Type::mut_ptr("c_void")]);
+        ty.set_fn_unsafe(true)
+            .set_fn_abi("system")
+            .set_fn_ret("u32");
+        assert_eq!(
+            to_string(&ty),
+            "unsafe extern \"system\" fn(*mut c_void) -> u32"
+        );
+    }
+}
+
+#[test]
+fn parse_const_generic() {
+    {
+        let ty = Type::new("ArrayVec<u8, 16>");
+        assert_eq!(ty.name(), "ArrayVec");
+        assert_eq!(ty.generics()[0].name(), "u8");
+        assert_eq!(ty.generics()[1].const_expr().unwrap(), "16");
+        assert_eq!(to_string(&ty), "ArrayVec<u8, 16>");
+    }
+    {
+        let ty = Type::new("Matrix<f32, { N * 2 }, M>");
+        assert_eq!(to_string(&ty), "Matrix<f32, { N * 2 }, M>");
+    }
+    {
+        let mut ty = Type::new("Buffer");
+        ty.generic("T").const_generic(64);
+        assert_eq!(to_string(&ty), "Buffer<T, 64>");
+    }
+}
+
+#[test]
+fn parse_qualified_path() {
+    {
+        let ty = Type::new("<T as Add<U>>::Output");
+        assert_eq!(ty.name(), "Output");
+        assert_eq!(to_string(&ty), "<T as Add<U>>::Output");
+    }
+    {
+        let ty = Type::new("Option<<I as IntoIterator>::Item>");
+        assert_eq!(ty.generics()[0].name(), "Item");
+        assert_eq!(to_string(&ty), "Option<<I as IntoIterator>::Item>");
+    }
+    {
+        let ty = Type::new("<Vec<T>>::IntoIter");
+        assert_eq!(to_string(&ty), "<Vec<T>>::IntoIter");
+    }
+    {
+        let ty = Type::new("Vec<T::Item>");
+        let item = &ty.generics()[0];
+        assert_eq!(item.name(), "Item");
+        assert_eq!(item.qualified_self().unwrap().name(), "T");
+        assert!(item.qualified_trait().is_none());
+        assert_eq!(to_string(&ty), "Vec<T::Item>");
+    }
+    {
+        let ty = Type::new("Self::Error");
+        assert!(ty.qualified_self().is_none());
+        assert_eq!(ty.name(), "Self::Error");
+        assert_eq!(to_string(&ty), "Self::Error");
+    }
+    {
+        let ty = Type::new("Outer::Inner<u8>");
+        assert!(ty.qualified_self().is_none());
+        assert_eq!(ty.name(), "Outer::Inner");
+        assert_eq!(ty.generics()[0].name(), "u8");
+        assert_eq!(to_string(&ty), "Outer::Inner<u8>");
+    }
+    {
+        let ty = Type::new("<T as Iterator>::Item");
+        assert_eq!(ty.qualified_self().unwrap().name(), "T");
+        assert_eq!(ty.qualified_trait().unwrap().name(), "Iterator");
+        assert_eq!(ty.qualified_item().unwrap().name(), "Item");
+        assert_eq!(to_string(&ty), "<T as Iterator>::Item");
+    }
+    {
+        let ty = Type::new("T::Item::Key");
+        assert_eq!(ty.name(), "Item::Key");
+        assert_eq!(ty.qualified_self().unwrap().name(), "T");
+        assert_eq!(to_string(&ty), "T::Item::Key");
+        assert_eq!(Type::new("std::io::Error").name(), "std::io::Error");
+    }
+    {
+        let ty = Type::new("T").associated("Item");
+        assert_eq!(ty.qualified_self().unwrap().name(), "T");
+        assert_eq!(to_string(&ty), "T::Item");
+        assert_eq!(
+            to_string(&Type::new("Vec<T>").associated("Item")),
+            "<Vec<T>>::Item"
+        );
+        assert_eq!(
+            to_string(&Type::new("<T as Trait>::Assoc").associated("Item")),
+            "<T as Trait>::Assoc::Item"
+        );
+    }
+}
+
+#[test]
+fn parse_unit_and_never() {
+    assert!(Type::new("()").is_unit());
+    assert!(Type::new("!").is_never());
+    assert!(Type::unit().is_unit());
+    assert_eq!(to_string(&Type::unit()), "()");
+    assert_eq!(to_string(&Type::never()), "!");
+    assert_eq!(to_string(&Type::new("Result<!, ()>")), "Result<!, ()>");
+}
+
+#[test]
+fn wrap_type() {
+    let ty = Type::new("Foo<T>");
+    assert_eq!(to_string(&ty.option()), "Option<Foo<T>>");
+    assert_eq!(to_string(&ty.result("MyError")), "Result<Foo<T>, MyError>");
+    assert_eq!(to_string(&ty.vec()), "Vec<Foo<T>>");
+    assert_eq!(to_string(&ty.boxed()), "Box<Foo<T>>");
+    assert_eq!(to_string(&ty.arc()), "Arc<Foo<T>>");
+    assert_eq!(to_string(&ty.rc()), "Rc<Foo<T>>");
+    assert_eq!(
+        to_string(&Type::dyn_trait("Error").boxed().result(Type::unit())),
+        "Result<Box<dyn Error>, ()>"
+    );
+    assert_eq!(ty.option().generics()[0].name(), "Foo");
+}
+
+#[test]
+fn parse_invalid() {
+    {
+        let err = Type::try_new("HashMap<K, V").unwrap_err();
+        assert_eq!(err.input(), "HashMap<K, V");
+        assert!(err
+            .to_string()
+            .starts_with("failed to parse `HashMap<K, V` as a type"));
+    }
+    {
+        let ty = Type::new("T: Into<String>");
+        assert_eq!(ty.name(), "T: Into<String>");
+        assert_eq!(to_string(&ty), "T: Into<String>");
+    }
+    for input in ["", "   ", "foo bar", "123", "fn", "a::", "::", "_::Foo"] {
+        assert!(Type::try_new(input).is_err(), "`{}` is not a type", input);
+    }
+    {
+        let ty = Type::try_new("  &str ").unwrap();
+        assert!(ty.is_reference());
+        assert_eq!(to_string(&ty), "&str");
+    }
+    {
+        let ty = Type::try_new(" ::std::io::Error").unwrap();
+        assert_eq!(ty.name(), "::std::io::Error");
+        assert_eq!(to_string(&Type::try_new("r#type").unwrap()), "r#type");
+        assert_eq!(
+            to_string(&Type::try_new("Self::Item").unwrap()),
+            "Self::Item"
+        );
+    }
+}
+
+#[test]
+fn normalize_and_compare() {
+    {
+        let ty = Type::new("::std::vec::Vec<::std::string::String>");
+        assert_eq!(to_string(&ty), "::std::vec::Vec<::std::string::String>");
+        assert_eq!(
+            to_string(&ty.normalize()),
+            "std::vec::Vec<std::string::String>"
+        );
+        assert_eq!(ty, Type::new("std::vec::Vec<std::string::String>"));
+    }
+    {
+        assert_eq!(
+            Type::new("::core::fmt::Debug"),
+            Type::new("core::fmt::Debug")
+        );
+        assert_eq!(
+            Type::new("<T as ::core::ops::Add>::Output"),
+            Type::new("<T as core::ops::Add>::Output")
+        );
+        assert_eq!(Type::new("Foo < 'a , T >"), Type::new("Foo<'a, T>"));
+        assert_eq!(Type::new("T : Into<String>"), Type::new("T: Into <String>"));
+        assert_ne!(Type::new("Vec<u8>"), Type::new("Vec<u16>"));
+        assert_ne!(Type::new("&mut T"), Type::new("&T"));
+    }
+    {
+        let mut set = std::collections::HashSet::new();
+        set.insert(Type::new("::std::vec::Vec<u8>"));
+        set.insert(Type::new("std::vec::Vec<u8>"));
+        set.insert(Type::new("std::vec::Vec<u8>").normalize());
+        assert_eq!(set.len(), 1);
+    }
+}
+
+#[test]
+fn path_manipulation() {
+    {
+        let ty = Type::new("crate::foo::Bar<crate::foo::Baz>");
+        assert_eq!(
+            ty.segments().collect::<Vec<&str>>(),
+            ["crate", "foo", "Bar"]
+        );
+        assert_eq!(ty.last_segment(), "Bar");
+        assert_eq!(to_string(&ty.strip_prefix("crate::foo")), "Bar<Baz>");
+        assert_eq!(
+            to_string(&ty.replace_prefix("crate::foo", "super")),
+            "super::Bar<super::Baz>"
+        );
+        assert_eq!(
+            to_string(&ty.replace_prefix("crate::fo", "super")),
+            "crate::foo::Bar<crate::foo::Baz>"
+        );
+        assert_eq!(
+            to_string(&ty.strip_prefix("crate::foo::Bar")),
+            "crate::foo::Bar<crate::foo::Baz>"
+        );
+    }
+    {
+        let ty = Type::new("&[crate::a::B]");
+        assert_eq!(ty.last_segment(), "B");
+        assert_eq!(to_string(&ty.strip_prefix("crate::")), "&[a::B]");
+    }
+    {
+        let ty = Type::new("b::C");
+        assert_eq!(to_string(&ty.path("a")), "a::b::C");
+        assert_eq!(Type::tuple(Vec::<Type>::new()).last_segment(), "");
+    }
+}
+
+#[test]
+fn build_qualified_path() {
+    {
+        let mut ty = Type::qualified("T", "Add<Rhs>", "Output");
+        assert_eq!(to_string(&ty), "<T as Add<Rhs>>::Output");
+
+        ty.lifetime("a");
+        assert_eq!(to_string(&ty), "<T as Add<Rhs>>::Output<'a>");
+    }
+    {
+        let ty = Type::qualified(Type::reference("Self"), "IntoIterator", "Item");
+        assert_eq!(ty, Type::new("<&Self as IntoIterator>::Item"));
+    }
+    {
+        let ty = Type::new("<Self as LendingIterator>::Item<'a>");
+        assert_eq!(ty.qualified_self().unwrap().name(), "Self");
+        assert_eq!(ty.qualified_trait().unwrap().name(), "LendingIterator");
+        assert_eq!(ty.qualified_item().unwrap().lifetimes()[0].name(), "a");
+        assert_eq!(to_string(&ty), "<Self as LendingIterator>::Item<'a>");
+    }
+}
+
+#[test]
+fn parse_fn_trait() {
+    {
+        let ty = Type::new("Box<dyn Fn(u8, u8) -> u16>");
+        let bound = &ty.generics()[0].bounds().unwrap()[0];
+        assert_eq!(bound.name(), "Fn");
+        assert_eq!(bound.fn_args().unwrap().len(), 2);
+        assert_eq!(bound.fn_ret().unwrap().name(), "u16");
+        assert_eq!(to_string(&ty), "Box<dyn Fn(u8, u8) -> u16>");
+    }
+    {
+        let ty = Type::new("impl FnOnce() + Send");
+        assert_eq!(to_string(&ty), "impl FnOnce() + Send");
+    }
+    {
+        let ty = Type::new("std::ops::FnMut(&str)");
+        assert_eq!(ty.name(), "std::ops::FnMut");
+        assert_eq!(to_string(&ty.strip_prefix("std::ops")), "FnMut(&str)");
+    }
+    {
+        let mut ty = Type::fn_trait("Fn", vec![Type::reference("T")], None::<Type>);
+        assert_eq!(to_string(&ty), "Fn(&T)");
+
+        ty.set_fn_ret("bool");
+        assert_eq!(ty, Type::new("Fn(&T) -> bool"));
+    }
+}
+
+#[test]
+fn fmt_turbofish() {
+    assert_eq!(Type::new("Vec<u8>").turbofish(), "Vec::<u8>");
+    assert_eq!(Type::new("String").turbofish(), "String");
+    assert_eq!(
+        Type::new("HashMap<K, Vec<V>>").turbofish(),
+        "HashMap::<K, Vec<V>>"
+    );
+    assert_eq!(Type::new("Cow<'a, str>").turbofish(), "Cow::<'a, str>");
+    assert_eq!(
+        Type::new("<T as Trait>::Assoc<u8>").turbofish(),
+        "<T as Trait>::Assoc::<u8>"
+    );
+    assert_eq!(Type::new("[u8]").turbofish(), "<[u8]>");
+    assert_eq!(Type::new("(A, B)").turbofish(), "<(A, B)>");
+}
+
+#[test]
+fn from_strings() {
+    let name = "Vec<u8>";
+    let types: [Type; 5] = [
+        name.into(),
+        (&name).into(),
+        name.to_string().into(),
+        Cow::Borrowed(name).into(),
+        Box::<str>::from(name).into(),
+    ];
+    for ty in &types {
+        assert_eq!(to_string(ty), "Vec<u8>");
+    }
+}
+
+#[test]
+fn from_syn() {
+    {
+        let ast: syn::Type = syn::parse_str("&'a mut [Option<(u8, u16)>; 4]").unwrap();
+        let ty = Type::from_syn(&ast);
+        assert_eq!(ty, Type::new("&'a mut [Option<(u8, u16)>; 4]"));
+        assert_eq!(ty.inner().unwrap().array_len().unwrap(), "4");
+    }
+    {
+        let path: syn::Path = syn::parse_str("std::iter::Iterator<Item = u8>").unwrap();
+        let ty = Type::from_syn_path(&path);
+        assert_eq!(ty.name(), "std::iter::Iterator");
+        assert_eq!(ty.bindings()[0].0, "Item");
+    }
+    {
+        // generics on inner segments can't be split, so the path is kept verbatim
+        let path: syn::Path = syn::parse_str("Vec<u8>::Item").unwrap();
+        assert_eq!(to_string(&Type::from_syn_path(&path)), "Vec<u8>::Item");
+    }
+    {
+        let ast: syn::Type = syn::parse_str("HashMap<String, Vec<u8>>").unwrap();
+        let ty: Type = (&ast).into();
+        assert_eq!(ty, Type::from_syn(&ast));
+        assert_eq!(ty.generics()[1].name(), "Vec");
+
+        let path: syn::Path = syn::parse_str("std::io::Result<()>").unwrap();
+        let ty: Type = path.into();
+        assert_eq!(ty.name(), "std::io::Result");
+        assert!(ty.generics()[0].is_unit());
+    }
+}
+
+#[test]
+fn parse_complete() {
+    {
+        let ty = Type::new("impl Iterator<Item: Debug + 'a>");
+        assert_eq!(to_string(&ty), "impl Iterator<Item: Debug + 'a>");
+    }
+    {
+        let ty = Type::new("&(dyn Any + Send)");
+        assert_eq!(ty.name(), "");
+        assert_eq!(
+            ty.inner().unwrap().inner().unwrap().bounds().unwrap().len(),
+            2
+        );
+        assert_eq!(to_string(&ty), "&(dyn Any + Send)");
+    }
+    {
+        let ty = Type::new("Box<dyn for<'a> Fn(&'a u8) -> &'a u8 + Send + 'static>");
+        let bounds = ty.generics()[0].bounds().unwrap();
+        assert_eq!(to_string(&bounds[0]), "for<'a> Fn(&'a u8) -> &'a u8");
+        assert_eq!(bounds[1].name(), "Send");
+        assert_eq!(
+            to_string(&ty),
+            "Box<dyn for<'a> Fn(&'a u8) -> &'a u8 + Send + 'static>"
+        );
+    }
+    {
+        let ty = Type::new("ArrayVec<u8, { N * 2 - 1 }>");
+        assert_eq!(ty.generics()[1].const_expr().unwrap(), "{ N * 2 - 1 }");
+        assert_eq!(to_string(&Type::new("[u8; -1]")), "[u8; -1]");
+    }
+    {
+        let ty = Type::new("Vec<_>");
+        assert_eq!(ty.generics()[0].name(), "_");
+    }
+    {
+        let ty = Type::new("for<'a> fn(&'a str) -> Cow<'a, str>");
+        assert_eq!(to_string(&ty), "for<'a> fn(&'a str) -> Cow<'a, str>");
+    }
+}
+
+#[test]
+fn display() {
+    let ty = Type::new("HashMap<&'a str, Vec<u8>>");
+    assert_eq!(ty.to_string(), "HashMap<&'a str, Vec<u8>>");
+    assert_eq!(format!("expected `{}`", Type::unit()), "expected `()`");
+}
+
+#[cfg(feature = "tokens")]
+#[test]
+fn to_tokens() {
+    let ty = Type::new("Vec<Option<u8>>");
+    let tokens = quote::quote! { let x: #ty = Vec::new(); };
+    assert_eq!(
+        tokens.to_string(),
+        "let x : Vec < Option < u8 >> = Vec :: new () ;"
+    );
+
+    let ty = Type::new("(u8");
+    let tokens = quote::quote! { #ty };
+    assert_eq!(
+        tokens.to_string(),
+        "compile_error ! (\"`(u8` is not a valid type\")"
+    );
+}
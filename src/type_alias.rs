@@ -19,12 +19,18 @@ impl TypeAlias {
         }
     }
 
+    /// Call `f` on every type used by the type alias, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.type_def.visit_types_mut(f);
+        self.ty.visit_mut(f);
+    }
+
     /// Format a TypeAlias for usage in Rust
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         self.type_def.fmt_head("type", &[], fmt)?;
         write!(fmt, " = ")?;
         self.ty.fmt(fmt)?;
-        write!(fmt, ";\n")?;
+        writeln!(fmt, ";")?;
         Ok(())
     }
 
@@ -41,7 +47,16 @@ impl TypeAlias {
 
     /// Add a generic to the TypeAlias.
     pub fn generic(&mut self, name: impl ToString) -> &mut Self {
-        self.type_def.ty.generic(name);
+        self.type_def.ty.generic(Type::new(name));
+        self
+    }
+
+    /// Add a generic with a default type to the type alias, e.g. `T = String`.
+    pub fn generic_with_default<T>(&mut self, name: impl ToString, default: T) -> &mut Self
+    where
+        T: Into<Type>,
+    {
+        self.type_def.generic_with_default(name, default);
         self
     }
 
@@ -1,8 +1,13 @@
 use std::fmt::{self, Write};
 
+use quote::ToTokens;
+
 use crate::bound::Bound;
+use crate::cfg::Cfg;
 use crate::docs::Docs;
-use crate::formatter::{fmt_bounds, Formatter};
+use crate::formatter::{deprecated_attr, fmt_bounds, Formatter};
+use crate::r#impl::Impl;
+use crate::tokens::tokens_to_string;
 
 use crate::r#type::Type;
 
@@ -15,10 +20,16 @@ pub struct TypeDef {
     derive: Vec<String>,
     allow: Vec<String>,
     attributes: Vec<String>,
-    repr: Option<String>,
+    deprecated: Option<String>,
+    must_use: Option<Option<String>>,
+    non_exhaustive: bool,
+    serde: Vec<(&'static str, String)>,
+    repr: Vec<String>,
     bounds: Vec<Bound>,
     macros: Vec<String>,
     cfg_attrs: Vec<String>,
+    generic_defaults: Vec<(String, Type)>,
+    const_generics: Vec<(String, Type)>,
 }
 
 impl TypeDef {
@@ -31,10 +42,123 @@ impl TypeDef {
             derive: Vec::new(),
             allow: Vec::new(),
             attributes: Vec::new(),
-            repr: None,
+            deprecated: None,
+            must_use: None,
+            non_exhaustive: false,
+            serde: Vec::new(),
+            repr: Vec::new(),
             bounds: Vec::new(),
             macros: Vec::new(),
             cfg_attrs: Vec::new(),
+            generic_defaults: Vec::new(),
+            const_generics: Vec::new(),
+        }
+    }
+
+    /// Return a type definition from the parts of an item parsed by `syn`.
+    ///
+    /// The visibility, generics, `where` clause, docs, derives, `repr` hints
+    /// and `#[allow]` and `#[non_exhaustive]` attributes are kept. Other
+    /// attributes are kept verbatim.
+    pub fn from_syn(
+        ident: &syn::Ident,
+        vis: &syn::Visibility,
+        attrs: &[syn::Attribute],
+        generics: &syn::Generics,
+    ) -> Self {
+        let mut ret = TypeDef::new(ident);
+
+        if !matches!(vis, syn::Visibility::Inherited) {
+            ret.vis(tokens_to_string(vis.to_token_stream()));
+        }
+
+        let attrs = SynAttrs::new(attrs);
+        if let Some(docs) = attrs.docs {
+            ret.doc(docs);
+        }
+        for meta in attrs.rest {
+            match meta {
+                syn::Meta::List(ref list) if list.path.is_ident("derive") => {
+                    for nested in &list.nested {
+                        ret.derive(tokens_to_string(nested.to_token_stream()));
+                    }
+                }
+                syn::Meta::List(ref list) if list.path.is_ident("repr") => {
+                    for nested in &list.nested {
+                        ret.repr(tokens_to_string(nested.to_token_stream()));
+                    }
+                }
+                syn::Meta::List(ref list) if list.path.is_ident("allow") => {
+                    for nested in &list.nested {
+                        ret.allow(tokens_to_string(nested.to_token_stream()));
+                    }
+                }
+                syn::Meta::Path(ref path) if path.is_ident("non_exhaustive") => {
+                    ret.set_non_exhaustive(true);
+                }
+                meta => {
+                    ret.attr(tokens_to_string(meta.to_token_stream()));
+                }
+            }
+        }
+        for raw in attrs.raw {
+            ret.attr(raw);
+        }
+
+        for param in &generics.params {
+            match param {
+                syn::GenericParam::Lifetime(def) => {
+                    ret.ty.generic(Type::new(def.lifetime.to_string()));
+                    for bound in &def.bounds {
+                        ret.bound(def.lifetime.to_string(), bound.to_string());
+                    }
+                }
+                syn::GenericParam::Type(param) => {
+                    let name = param.ident.to_string();
+                    match param.default {
+                        Some(ref default) => {
+                            ret.generic_with_default(&name, Type::from_syn(default));
+                        }
+                        None => {
+                            ret.ty.generic(Type::new(&name));
+                        }
+                    }
+                    if !param.bounds.is_empty() {
+                        let bounds = tokens_to_string(param.bounds.to_token_stream());
+                        ret.where_bound(&format!("{}: {}", name, bounds));
+                    }
+                }
+                syn::GenericParam::Const(param) => {
+                    ret.const_generic(&param.ident, Type::from_syn(&param.ty));
+                    if let Some(ref default) = param.default {
+                        let default = tokens_to_string(default.to_token_stream());
+                        ret.set_generic_default(&param.ident, default);
+                    }
+                }
+            }
+        }
+        if let Some(ref where_clause) = generics.where_clause {
+            for predicate in &where_clause.predicates {
+                ret.where_bound(&tokens_to_string(predicate.to_token_stream()));
+            }
+        }
+
+        ret
+    }
+
+    /// Return an empty type definition called `name` with the visibility,
+    /// generics and bounds of this one.
+    pub fn companion(&self, name: impl ToString) -> TypeDef {
+        let mut ty = self.ty.clone();
+        ty.set_name(name);
+
+        TypeDef {
+            ty,
+            vis: self.vis.clone(),
+            bounds: self.bounds.clone(),
+            generic_defaults: self.generic_defaults.clone(),
+            const_generics: self.const_generics.clone(),
+            ..TypeDef::new("")
         }
     }
 
@@ -42,16 +166,33 @@ impl TypeDef {
         self.vis = Some(vis.to_string());
     }
 
+    pub fn visibility(&self) -> Option<&String> {
+        self.vis.as_ref()
+    }
+
     pub fn bound<T>(&mut self, name: impl ToString, ty: T)
     where
         T: Into<Type>,
     {
-        self.bounds.push(Bound {
+        self.push_bound(Bound {
             name: name.to_string(),
             bound: vec![ty.into()],
         });
     }
 
+    pub fn where_bound(&mut self, predicate: &str) {
+        self.push_bound(Bound::parse(predicate));
+    }
+
+    /// Push a bound, merging it with the existing bound on the same type so
+    /// that `T: Clone` and `T: Send` are formatted as `T: Clone + Send`.
+    fn push_bound(&mut self, bound: Bound) {
+        match self.bounds.iter_mut().find(|b| b.name == bound.name) {
+            Some(existing) => existing.bound.extend(bound.bound),
+            None => self.bounds.push(bound),
+        }
+    }
+
     pub fn r#macro(&mut self, r#macro: impl ToString) {
         self.macros.push(r#macro.to_string());
     }
@@ -60,6 +201,36 @@ impl TypeDef {
         self.attributes.push(attr.to_string());
     }
 
+    pub fn deprecated(&mut self, since: Option<&str>, note: Option<&str>) {
+        self.deprecated = Some(deprecated_attr(since, note));
+    }
+
+    pub fn must_use(&mut self, message: Option<&str>) {
+        self.must_use = Some(message.map(str::to_string));
+    }
+
+    pub fn set_non_exhaustive(&mut self, non_exhaustive: bool) {
+        self.non_exhaustive = non_exhaustive;
+    }
+
+    pub fn is_non_exhaustive(&self) -> bool {
+        self.non_exhaustive
+    }
+
+    /// Set a `#[serde(key = "value")]` argument, replacing its previous value.
+    pub fn serde(&mut self, key: &'static str, value: impl ToString) {
+        let value = value.to_string();
+        match self.serde.iter_mut().find(|(k, _)| *k == key) {
+            Some(existing) => existing.1 = value,
+            None => self.serde.push((key, value)),
+        }
+    }
+
+    /// Returns the value of a `#[serde(key = "value")]` argument, if set.
+    pub fn get_serde(&self, key: &str) -> Option<&String> {
+        self.serde.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
     pub fn doc(&mut self, docs: impl ToString) {
         self.docs = Some(Docs::new(docs));
     }
@@ -68,23 +239,163 @@ impl TypeDef {
         self.derive.push(name.to_string());
     }
 
+    pub fn derive_when<I>(&mut self, predicate: impl Into<Cfg>, derives: I)
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+    {
+        let derives = derives
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        self.cfg_attr(format!(
+            "{}, derive({})",
+            predicate.into(),
+            derives.join(", ")
+        ));
+    }
+
     pub fn allow(&mut self, allow: impl ToString) {
         self.allow.push(allow.to_string());
     }
 
     pub fn repr(&mut self, repr: impl ToString) {
-        self.repr = Some(repr.to_string());
+        let repr = repr.to_string();
+        if !self.repr.contains(&repr) {
+            self.repr.push(repr);
+        }
+    }
+
+    pub fn repr_align(&mut self, align: usize) {
+        assert!(
+            align.is_power_of_two() && align <= 1 << 29,
+            "`repr(align)` must be a power of two up to 2^29, got {}",
+            align
+        );
+        self.repr(format!("align({})", align));
     }
 
     pub fn cfg_attr(&mut self, cfg_attr: impl ToString) {
         self.cfg_attrs.push(cfg_attr.to_string());
     }
 
+    /// Add a generic with a default, which is only formatted in the head of
+    /// the definition.
+    pub fn generic_with_default<T>(&mut self, name: impl ToString, default: T)
+    where
+        T: Into<Type>,
+    {
+        let name = name.to_string();
+        self.ty.generic(Type::new(&name));
+        self.set_generic_default(name, default);
+    }
+
+    /// Set the default of an existing generic, e.g. a const generic.
+    pub fn set_generic_default<T>(&mut self, name: impl ToString, default: T)
+    where
+        T: Into<Type>,
+    {
+        self.generic_defaults
+            .push((name.to_string(), default.into()));
+    }
+
+    /// Add a const generic, which is formatted as `const N: usize` in the head
+    /// of the definition and as `N` in the type.
+    pub fn const_generic<T>(&mut self, name: impl ToString, ty: T)
+    where
+        T: Into<Type>,
+    {
+        let name = name.to_string();
+        self.ty.generic(Type::new(&name));
+        self.const_generics.push((name, ty.into()));
+    }
+
+    /// Return an empty impl block for the type, with its generics and bounds.
+    pub fn new_impl(&self) -> Impl {
+        self.new_impl_for(&self.ty)
+    }
+
+    /// Return an empty impl block for `target`, with the generics and bounds
+    /// of this type definition.
+    pub fn new_impl_for(&self, target: &Type) -> Impl {
+        let mut imp = Impl::new(target);
+
+        for lifetime in self.ty.lifetimes() {
+            imp.generic(format!("'{}", lifetime.name()));
+        }
+        for param in self.generic_params() {
+            imp.generic(param);
+        }
+        for bound in &self.bounds {
+            for b in &bound.bound {
+                imp.bound(&bound.name, b);
+            }
+        }
+
+        imp
+    }
+
+    /// Returns the generic parameters of the definition, without their
+    /// defaults, e.g. `'a`, `T` and `const N: usize`.
+    pub fn generic_params(&self) -> Vec<String> {
+        self.ty
+            .with_generic_params(&[], &self.const_generics)
+            .generics()
+            .iter()
+            .map(|generic| generic.name().clone())
+            .collect()
+    }
+
+    /// Returns the names of the type parameters of the definition, e.g. `T`,
+    /// without its lifetimes and const parameters.
+    pub(crate) fn type_params(&self) -> Vec<String> {
+        self.ty
+            .generics()
+            .iter()
+            .map(|generic| generic.name().clone())
+            .filter(|name| self.const_generics.iter().all(|(c, _)| c != name))
+            .collect()
+    }
+
+    /// Returns the `where` bounds of the definition.
+    pub fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
+
+    /// Call `f` on every type used by the type definition, including nested ones.
+    ///
+    /// The declared name and generic parameters, e.g. `Foo<T>`, are not
+    /// visited since they aren't uses of types.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        for bound in &mut self.bounds {
+            bound.visit_types_mut(f);
+        }
+        for (_, ty) in self
+            .generic_defaults
+            .iter_mut()
+            .chain(self.const_generics.iter_mut())
+        {
+            ty.visit_mut(f);
+        }
+    }
+
     pub fn fmt_head(
         &self,
         keyword: &str,
         parents: &[Type],
         fmt: &mut Formatter<'_>,
+    ) -> fmt::Result {
+        self.fmt_signature(keyword, parents, fmt)?;
+        self.fmt_where(fmt)
+    }
+
+    /// Formats the head without the `where` clause, which goes after the
+    /// fields of tuple structs.
+    pub fn fmt_signature(
+        &self,
+        keyword: &str,
+        parents: &[Type],
+        fmt: &mut Formatter<'_>,
     ) -> fmt::Result {
         if let Some(ref docs) = self.docs {
             docs.fmt(fmt)?;
@@ -92,7 +403,7 @@ impl TypeDef {
 
         self.fmt_allow(fmt)?;
         self.fmt_derive(fmt)?;
-        self.fmt_repr(fmt)?;
+        self.fmt_repr(keyword, fmt)?;
         self.fmt_attributes(fmt)?;
         self.fmt_macros(fmt)?;
         self.fmt_cfg_attrs(fmt)?;
@@ -102,7 +413,9 @@ impl TypeDef {
         }
 
         write!(fmt, "{} ", keyword)?;
-        self.ty.fmt(fmt)?;
+        self.ty
+            .with_generic_params(&self.generic_defaults, &self.const_generics)
+            .fmt(fmt)?;
 
         if !parents.is_empty() {
             for (i, ty) in parents.iter().enumerate() {
@@ -116,14 +429,39 @@ impl TypeDef {
             }
         }
 
-        fmt_bounds(&self.bounds, fmt)?;
-
         Ok(())
     }
 
+    pub fn fmt_where(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        fmt_bounds(&self.bounds, fmt)
+    }
+
     fn fmt_attributes(&self, fmt: &mut Formatter) -> fmt::Result {
+        if let Some(ref deprecated) = self.deprecated {
+            writeln!(fmt, "#[{}]", deprecated)?;
+        }
+
+        match self.must_use {
+            Some(Some(ref message)) => writeln!(fmt, "#[must_use = {:?}]", message)?,
+            Some(None) => writeln!(fmt, "#[must_use]")?,
+            None => {}
+        }
+
+        if self.non_exhaustive {
+            writeln!(fmt, "#[non_exhaustive]")?;
+        }
+
+        if !self.serde.is_empty() {
+            let args: Vec<_> = self
+                .serde
+                .iter()
+                .map(|(key, value)| format!("{} = {:?}", key, value))
+                .collect();
+            writeln!(fmt, "#[serde({})]", args.join(", "))?;
+        }
+
         for attr in &self.attributes {
-            write!(fmt, "#[{}]\n", attr)?;
+            writeln!(fmt, "#[{}]", attr)?;
         }
 
         Ok(())
@@ -131,32 +469,100 @@ impl TypeDef {
 
     fn fmt_allow(&self, fmt: &mut Formatter) -> fmt::Result {
         for allow in &self.allow {
-            write!(fmt, "#[allow({})]\n", allow)?;
+            writeln!(fmt, "#[allow({})]", allow)?;
         }
 
         Ok(())
     }
 
-    fn fmt_repr(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
-        if let Some(ref repr) = self.repr {
-            write!(fmt, "#[repr({})]\n", repr)?;
+    fn fmt_repr(&self, keyword: &str, fmt: &mut Formatter<'_>) -> fmt::Result {
+        if self.repr.is_empty() {
+            return Ok(());
         }
 
-        Ok(())
+        self.validate_repr(keyword);
+        writeln!(fmt, "#[repr({})]", self.repr.join(", "))
+    }
+
+    /// Returns the primitive representation hint, e.g. `u8`.
+    pub fn primitive_repr(&self) -> Option<&str> {
+        self.repr
+            .iter()
+            .find(|repr| is_primitive_repr(repr))
+            .map(String::as_str)
+    }
+
+    /// Returns whether the representation hints include `hint`.
+    pub fn has_repr(&self, hint: &str) -> bool {
+        self.repr.iter().any(|repr| repr == hint)
+    }
+
+    /// Panics if the representation hints can't be combined, e.g.
+    /// `repr(packed, align(8))`.
+    fn validate_repr(&self, keyword: &str) {
+        let has = |hint: &str| {
+            self.repr
+                .iter()
+                .any(|repr| repr == hint || repr.starts_with(&format!("{}(", hint)))
+        };
+        let ints = self
+            .repr
+            .iter()
+            .filter(|repr| is_primitive_repr(repr))
+            .count();
+
+        assert!(
+            !has("transparent") || self.repr.len() == 1,
+            "`repr(transparent)` can't be combined with other representations"
+        );
+        assert!(
+            !(has("packed") && has("align")),
+            "`repr(packed)` and `repr(align)` can't be combined"
+        );
+        assert!(ints <= 1, "conflicting primitive representations");
+
+        if keyword == "enum" {
+            assert!(!has("packed"), "enums can't be `repr(packed)`");
+        } else {
+            assert!(
+                ints == 0,
+                "primitive representations are only valid for enums"
+            );
+        }
+    }
+
+    /// Returns the derives sorted by name, without duplicates.
+    ///
+    /// Derives are deduplicated by their last path segment: a path-qualified
+    /// derive such as `serde::Serialize` is dropped when `Serialize` is also
+    /// derived, whether or not the scope imports it.
+    fn derives(&self) -> Vec<&String> {
+        let name = |path: &str| path.rsplit("::").next().unwrap_or(path).to_string();
+
+        let mut derives = self
+            .derive
+            .iter()
+            .filter(|path| {
+                !path.contains("::") || !self.derive.iter().any(|other| *other == name(path))
+            })
+            .collect::<Vec<_>>();
+        derives.sort_by(|a, b| name(a).cmp(&name(b)).then_with(|| a.cmp(b)));
+        derives.dedup();
+        derives
     }
 
     fn fmt_derive(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         if !self.derive.is_empty() {
             write!(fmt, "#[derive(")?;
 
-            for (i, name) in self.derive.iter().enumerate() {
+            for (i, name) in self.derives().iter().enumerate() {
                 if i != 0 {
                     write!(fmt, ", ")?
                 }
                 write!(fmt, "{}", name)?;
             }
 
-            write!(fmt, ")]\n")?;
+            writeln!(fmt, ")]")?;
         }
 
         Ok(())
@@ -164,16 +570,81 @@ impl TypeDef {
 
     fn fmt_macros(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         for m in self.macros.iter() {
-            write!(fmt, "{}\n", m)?;
+            writeln!(fmt, "{}", m)?;
         }
         Ok(())
     }
 
     fn fmt_cfg_attrs(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         for attr in &self.cfg_attrs {
-            write!(fmt, "#[cfg_attr({})]\n", attr)?;
+            writeln!(fmt, "#[cfg_attr({})]", attr)?;
         }
 
         Ok(())
     }
 }
+
+fn is_primitive_repr(repr: &str) -> bool {
+    matches!(
+        repr,
+        "u8" | "u16"
+            | "u32"
+            | "u64"
+            | "u128"
+            | "usize"
+            | "i8"
+            | "i16"
+            | "i32"
+            | "i64"
+            | "i128"
+            | "isize"
+    )
+}
+
+/// The attributes of an item parsed by `syn`, with the doc comments joined.
+pub(crate) struct SynAttrs {
+    pub docs: Option<String>,
+
+    /// Other attributes
+    pub rest: Vec<syn::Meta>,
+
+    /// Attributes that aren't meta items, e.g. `#[rustfmt::skip::macros(m)]`
+    pub raw: Vec<String>,
+}
+
+impl SynAttrs {
+    pub fn new(attrs: &[syn::Attribute]) -> Self {
+        let mut docs = Vec::new();
+        let mut rest = Vec::new();
+        let mut raw = Vec::new();
+
+        for attr in attrs {
+            match attr.parse_meta() {
+                Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                    ref path,
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                })) if path.is_ident("doc") => {
+                    let line = lit.value();
+                    docs.push(line.strip_prefix(' ').unwrap_or(&line).to_string());
+                }
+                Ok(meta) => rest.push(meta),
+                Err(_) => raw.push(tokens_to_string(
+                    [attr.path.to_token_stream(), attr.tokens.clone()]
+                        .into_iter()
+                        .collect(),
+                )),
+            }
+        }
+
+        SynAttrs {
+            docs: if docs.is_empty() {
+                None
+            } else {
+                Some(docs.join("\n"))
+            },
+            rest,
+            raw,
+        }
+    }
+}
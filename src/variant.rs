@@ -1,7 +1,15 @@
 use std::fmt::{self, Write};
 
+use quote::ToTokens;
+
+use crate::body;
+use crate::cfg::Cfg;
+use crate::docs::{self, Docs};
+use crate::field::Field;
 use crate::fields::Fields;
-use crate::formatter::Formatter;
+use crate::formatter::{deprecated_attr, Formatter};
+use crate::tokens::tokens_to_string;
+use crate::type_def::SynAttrs;
 
 use crate::r#type::Type;
 
@@ -12,6 +20,16 @@ pub struct Variant {
     fields: Fields,
     /// Annotations for field e.g., `#[serde(rename = "variant")]`.
     annotations: Vec<String>,
+    /// Explicit discriminant, e.g. `0x10`
+    discriminant: Option<String>,
+    /// Variant documentation
+    docs: Option<Docs>,
+    /// The `#[deprecated]` attribute
+    deprecated: Option<String>,
+    /// The name set with `#[serde(rename = "...")]`
+    serde_rename: Option<String>,
+    /// Configuration predicates of `#[cfg(...)]` attributes
+    cfgs: Vec<Cfg>,
 }
 
 impl Variant {
@@ -21,7 +39,53 @@ impl Variant {
             name: name.to_string(),
             fields: Fields::Empty,
             annotations: Vec::new(),
+            discriminant: None,
+            docs: None,
+            deprecated: None,
+            serde_rename: None,
+            cfgs: Vec::new(),
+        }
+    }
+
+    /// Return a variant from a variant parsed by `syn`, keeping its fields,
+    /// discriminant, docs, `#[cfg]` predicates and attributes.
+    pub(crate) fn from_syn(variant: &syn::Variant) -> Self {
+        let mut ret = Variant::new(&variant.ident);
+
+        let attrs = SynAttrs::new(&variant.attrs);
+        if let Some(docs) = attrs.docs {
+            ret.doc(docs);
+        }
+        for meta in attrs.rest {
+            match meta {
+                syn::Meta::List(ref list)
+                    if list.path.is_ident("cfg") && list.nested.len() == 1 =>
+                {
+                    ret.cfg(tokens_to_string(list.nested.to_token_stream()));
+                }
+                meta => {
+                    ret.attr(tokens_to_string(meta.to_token_stream()));
+                }
+            }
+        }
+        for raw in attrs.raw {
+            ret.attr(raw);
+        }
+
+        for field in &variant.fields {
+            let field = Field::from_syn(field);
+            if field.name.is_empty() {
+                ret.push_tuple(field);
+            } else {
+                ret.push_named(field);
+            }
+        }
+
+        if let Some((_, ref discriminant)) = variant.discriminant {
+            ret.discriminant(tokens_to_string(discriminant.to_token_stream()));
         }
+
+        ret
     }
 
     /// Add a named field to the variant.
@@ -33,28 +97,311 @@ impl Variant {
         self
     }
 
+    /// Push a named field to the variant.
+    ///
+    /// A variant can either have named fields or tuple fields, but not both.
+    pub fn push_named(&mut self, field: Field) -> &mut Self {
+        self.fields.push_named(field);
+        self
+    }
+
+    /// Push a new named field to the variant, returning a mutable reference
+    /// to it to set its docs and attributes.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let mut event = Enum::new("Event");
+    /// let moved = event.new_variant("Moved");
+    /// moved.new_named("x", "i32").doc("Horizontal position");
+    /// moved.new_named("y", "i32").attr("serde(default)");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Formatting the enum panics if the field has a visibility.
+    pub fn new_named<T>(&mut self, name: impl ToString, ty: T) -> &mut Field
+    where
+        T: Into<Type>,
+    {
+        self.fields.new_named(name, ty)
+    }
+
+    /// Push a tuple field to the variant.
+    pub fn push_tuple(&mut self, field: Field) -> &mut Self {
+        self.fields.push_tuple(field);
+        self
+    }
+
+    /// Push a new tuple field to the variant, returning a mutable reference
+    /// to it to set its docs and attributes.
+    pub fn new_tuple<T>(&mut self, ty: T) -> &mut Field
+    where
+        T: Into<Type>,
+    {
+        self.fields.new_tuple(ty)
+    }
+
     /// Add a tuple field to the variant.
     pub fn tuple(&mut self, ty: impl ToString) -> &mut Self {
-        self.fields.tuple(None, ty);
+        self.fields.tuple(None, Type::new(ty));
+        self
+    }
+
+    /// Set the explicit discriminant of the variant, e.g. `Read = 0x10`.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let mut kind = Enum::new("Kind");
+    /// kind.repr("u8");
+    /// kind.new_variant("Read").discriminant("0x10");
+    /// kind.new_variant("Write").discriminant("0x20");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Formatting the enum panics if integer literal discriminants don't fit
+    /// in its primitive representation or are repeated, or if the enum has
+    /// variants with fields and no primitive representation.
+    pub fn discriminant(&mut self, value: impl ToString) -> &mut Self {
+        self.discriminant = Some(value.to_string());
+        self
+    }
+
+    /// Returns the explicit discriminant of the variant.
+    pub fn get_discriminant(&self) -> Option<&String> {
+        self.discriminant.as_ref()
+    }
+
+    /// Returns a pattern matching the variant of the enum at `path`, ignoring
+    /// its fields, e.g. `Event::Moved { .. }`.
+    pub(crate) fn pattern(&self, path: &str) -> String {
+        match self.fields {
+            Fields::Empty => format!("{}::{}", path, self.name),
+            Fields::Tuple(..) => format!("{}::{}(..)", path, self.name),
+            Fields::Named(..) => format!("{}::{} {{ .. }}", path, self.name),
+        }
+    }
+
+    /// Returns the pattern matching the variant and binding its fields, e.g.
+    /// `Shape::Circle(f0)` or `Shape::Rect { width, height }`.
+    ///
+    /// Tuple fields are bound as `f0`, `f1`, ... and named fields by name.
+    pub(crate) fn binding_pattern(&self, path: &str) -> String {
+        let bindings = self.bindings().join(", ");
+        match self.fields {
+            Fields::Empty => format!("{}::{}", path, self.name),
+            Fields::Tuple(..) => format!("{}::{}({})", path, self.name, bindings),
+            Fields::Named(..) => format!("{}::{} {{ {} }}", path, self.name, bindings),
+        }
+    }
+
+    /// Returns the names the fields of the variant are bound to by
+    /// `binding_pattern`.
+    pub(crate) fn bindings(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                if field.name.is_empty() {
+                    format!("f{}", i)
+                } else {
+                    field.name.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the fields of the variant.
+    pub(crate) fn fields(&self) -> &Fields {
+        &self.fields
+    }
+
+    /// Returns whether the variant has fields.
+    pub(crate) fn has_fields(&self) -> bool {
+        !matches!(self.fields, Fields::Empty)
+    }
+
+    /// Returns the name of the variant.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Set the variant documentation.
+    ///
+    /// The documentation can span several lines, each formatted as a `///`
+    /// comment. Paragraphs, lists and code blocks are kept as they are, and
+    /// long lines are wrapped like field docs. Leading and trailing blank
+    /// lines, as well as the indentation shared by all lines, are removed, so
+    /// an indented raw string can be used.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let mut level = Enum::new("Level");
+    /// level.new_variant("Low").doc(
+    ///     r#"
+    ///     Low priority.
+    ///
+    ///     - retried once
+    ///     - never paged
+    ///     "#,
+    /// );
+    /// ```
+    pub fn doc(&mut self, docs: impl ToString) -> &mut Self {
+        self.docs = Some(Docs::new(body::dedent_lines(&docs.to_string()).join("\n")));
+        self
+    }
+
+    /// Add an attribute to the variant.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let mut message = Enum::new("Message");
+    ///
+    /// // add a `#[serde(rename = "ping")]` attribute
+    /// message.new_variant("Ping").attr("serde(rename = \"ping\")");
+    /// ```
+    ///
+    /// This is the same as adding `#[...]` as an annotation.
+    pub fn attr(&mut self, attribute: impl ToString) -> &mut Self {
+        self.annotations
+            .push(format!("#[{}]", attribute.to_string()));
+        self
+    }
+
+    /// Mark the variant as `#[deprecated]`, with an optional version and
+    /// note.
+    pub fn deprecated(&mut self, since: Option<&str>, note: Option<&str>) -> &mut Self {
+        self.deprecated = Some(deprecated_attr(since, note));
+        self
+    }
+
+    /// Returns `true` if the variant is marked as `#[deprecated]`.
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated.is_some()
+    }
+
+    /// Set the name the variant is serialized as, with a
+    /// `#[serde(rename = "...")]` attribute.
+    ///
+    /// Calling this again replaces the previous name.
+    pub fn serde_rename(&mut self, name: impl ToString) -> &mut Self {
+        self.serde_rename = Some(name.to_string());
+        self
+    }
+
+    /// Returns the name set with [`serde_rename`], if any.
+    ///
+    /// [`serde_rename`]: #method.serde_rename
+    pub fn get_serde_rename(&self) -> Option<&String> {
+        self.serde_rename.as_ref()
+    }
+
+    /// Only compile the variant when the configuration predicate holds.
+    ///
+    /// ```
+    /// use codegen::Enum;
+    ///
+    /// let mut codec = Enum::new("Codec");
+    ///
+    /// // add a `#[cfg(feature = "experimental")]` attribute
+    /// codec.new_variant("Zstd").cfg("feature = \"experimental\"");
+    /// ```
+    ///
+    /// The arms of the matches generated by the enum, e.g. by
+    /// [`Enum::match_skeleton`], get the same attributes. Use [`cfgs`] to do
+    /// the same in other matches.
+    ///
+    /// [`Enum::match_skeleton`]: struct.Enum.html#method.match_skeleton
+    /// [`cfgs`]: #method.cfgs
+    pub fn cfg(&mut self, predicate: impl Into<Cfg>) -> &mut Self {
+        self.cfgs.push(predicate.into());
         self
     }
 
+    /// Returns the configuration predicates of the variant.
+    pub fn cfgs(&self) -> &[Cfg] {
+        &self.cfgs
+    }
+
+    /// Returns the `#[cfg(...)]` lines to put before the match arms of the
+    /// variant.
+    pub(crate) fn cfg_lines(&self) -> Vec<String> {
+        self.cfgs
+            .iter()
+            .map(|cfg| format!("#[cfg({})]", cfg))
+            .collect()
+    }
+
     /// Add an anotation to the variant.
     pub fn annotation(&mut self, annotation: impl Into<String>) -> &mut Self {
         self.annotations.push(annotation.into());
         self
     }
 
+    /// Call `f` on every type used by the variant, including nested ones.
+    pub(crate) fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.fields.visit_types_mut(f);
+    }
+
     /// Formats the variant using the given formatter.
     pub fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        for field in self.fields.iter() {
+            assert!(
+                field.visibility.is_none(),
+                "enum variant fields can't have a visibility"
+            );
+        }
+
+        if let Some(ref variant_docs) = self.docs {
+            docs::fmt_wrapped(variant_docs.to_str(), fmt)?;
+        }
+        for cfg in self.cfg_lines() {
+            writeln!(fmt, "{}", cfg)?;
+        }
+        if let Some(ref deprecated) = self.deprecated {
+            writeln!(fmt, "#[{}]", deprecated)?;
+        }
+        if let Some(ref rename) = self.serde_rename {
+            writeln!(fmt, "#[serde(rename = {:?})]", rename)?;
+        }
         for a in &self.annotations {
-            write!(fmt, "{}", a)?;
-            write!(fmt, "\n")?;
+            writeln!(fmt, "{}", a)?;
         }
         write!(fmt, "{}", self.name)?;
         self.fields.fmt(fmt)?;
-        write!(fmt, ",\n")?;
+        if let Some(ref discriminant) = self.discriminant {
+            write!(fmt, " = {}", discriminant)?;
+        }
+        writeln!(fmt, ",")?;
 
         Ok(())
     }
 }
+
+impl<'a> From<&'a str> for Variant {
+    fn from(src: &'a str) -> Self {
+        Variant::new(src)
+    }
+}
+
+impl From<String> for Variant {
+    fn from(src: String) -> Self {
+        Variant::new(src)
+    }
+}
+
+impl<N, D> From<(N, D)> for Variant
+where
+    N: ToString,
+    D: ToString,
+{
+    fn from((name, docs): (N, D)) -> Self {
+        let mut variant = Variant::new(name);
+        variant.doc(docs);
+        variant
+    }
+}
@@ -62,7 +62,7 @@ fn single_struct_documented_field() {
     let mut struct_ = Struct::new("Foo");
 
     let mut field1 = Field::new("one", "usize");
-    field1.doc(doc.clone());
+    field1.doc(doc);
     struct_.push_field(field1);
 
     let mut field2 = Field::new("two", "usize");
@@ -162,7 +162,7 @@ fn struct_with_derive() {
         .field("two", "String");
 
     let expect = r#"
-#[derive(Debug, Clone)]
+#[derive(Clone, Debug)]
 struct Foo {
     one: usize,
     two: String,
@@ -368,6 +368,25 @@ mod foo {
     assert_eq!(scope.to_string(), &expect[1..]);
 }
 
+#[test]
+fn documented_mod() {
+    let mut scope = Scope::new();
+    scope
+        .new_module("foo")
+        .doc("Hello some docs")
+        .attr("cfg(test)")
+        .new_struct("Foo");
+
+    let expect = r#"
+/// Hello some docs
+#[cfg(test)]
+mod foo {
+    struct Foo;
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
 #[test]
 fn struct_mod_import() {
     let mut scope = Scope::new();
@@ -523,6 +542,79 @@ trait Foo {
     assert_eq!(scope.to_string(), &expect[1..]);
 }
 
+#[test]
+fn function_with_async_and_extern_abi() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("fetch")
+        .vis("pub")
+        .set_async(true)
+        .extern_abi("C")
+        .ret("u32")
+        .line("0");
+
+    let expect = r#"
+pub async extern "C" fn fetch() -> u32 {
+    0
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_with_const_fn() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_impl("Foo")
+        .new_fn("new")
+        .vis("pub")
+        .set_const(true)
+        .ret("Self")
+        .line("Foo { bar: 0 }");
+
+    let expect = r#"
+impl Foo {
+    pub const fn new() -> Self {
+        Foo { bar: 0 }
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn function_with_unsafe() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("read")
+        .vis("pub")
+        .set_const(true)
+        .set_unsafe(true)
+        .arg("ptr", "*const u8")
+        .ret("u8")
+        .line("*ptr");
+
+    scope
+        .new_fn("callback")
+        .set_unsafe(true)
+        .extern_abi("C")
+        .line("todo!()");
+
+    let expect = r#"
+unsafe extern "C" fn callback() {
+    todo!()
+}
+
+pub const unsafe fn read(ptr: *const u8) -> u8 {
+    *ptr
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
 #[test]
 fn trait_with_macros() {
     let mut scope = Scope::new();
@@ -665,13 +757,35 @@ struct Foo {
     assert_eq!(scope.to_string(), &expect[1..]);
 }
 
+#[test]
+fn pub_struct_with_private_fields() {
+    let mut scope = Scope::new();
+
+    let user = scope.new_struct("User");
+    user.vis("pub");
+    user.new_field("name", "String").vis("pub");
+    user.new_field("cache", "Cache").vis("pub(crate)");
+    user.field("password_hash", "String");
+
+    let expect = r#"
+pub struct User {
+    pub name: String,
+    pub(crate) cache: Cache,
+    password_hash: String,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
 #[test]
 fn import_with_alias() {
     let mut scope = Scope::new();
     assert!(scope.get_module("foo").is_none());
 
     scope.get_or_new_module("foo").import("bar", "Bar", None);
-    scope.get_or_new_module("foo").import("bar", "Foo", Some("Baz"));
+    scope
+        .get_or_new_module("foo")
+        .import("bar", "Foo", Some("Baz"));
 
     scope
         .get_or_new_module("foo")
@@ -690,3 +804,3416 @@ mod foo {
 
     assert_eq!(scope.to_string(), &expect[1..]);
 }
+
+#[test]
+fn struct_with_lifetimes() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Foo")
+        .generic("'a")
+        .field("one", "&'a str")
+        .field("two", "Cow<'a, [u8]>")
+        .field("three", "&'a mut Vec<Bar<'a>>");
+
+    let expect = r#"
+struct Foo<'a> {
+    one: &'a str,
+    two: Cow<'a, [u8]>,
+    three: &'a mut Vec<Bar<'a>>,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_reference_args() {
+    let mut scope = Scope::new();
+
+    let mut buf = Type::mut_reference("Vec<u8>");
+    buf.set_reference_lifetime("'a");
+
+    scope
+        .new_fn("fill")
+        .generic("'a")
+        .arg("src", Type::reference("[u8]"))
+        .arg("buf", buf)
+        .line("buf.extend_from_slice(src);");
+
+    let expect = r#"
+fn fill<'a>(src: &[u8], buf: &'a mut Vec<u8>) {
+    buf.extend_from_slice(src);
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_tuple_types() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("split")
+        .arg("pair", "(u8, Vec<u8>)")
+        .ret(Type::tuple(vec!["Vec<u8>", "u8"]))
+        .line("(pair.1, pair.0)");
+
+    let expect = r#"
+fn split(pair: (u8, Vec<u8>)) -> (Vec<u8>, u8) {
+    (pair.1, pair.0)
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_array_fields() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Buffer")
+        .generic("const N: usize")
+        .field("data", Type::array("u8", "N"))
+        .field("header", "[u8; 4]")
+        .field("tail", "Box<[u8]>");
+
+    let expect = r#"
+struct Buffer<const N: usize> {
+    data: [u8; N],
+    header: [u8; 4],
+    tail: Box<[u8]>,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_raw_pointers() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("buf_new")
+        .vis("pub")
+        .extern_abi("C")
+        .arg("data", Type::const_ptr("u8"))
+        .arg("len", "usize")
+        .arg("out", "*mut *mut Buf")
+        .ret("i32")
+        .line("0");
+
+    let expect = r#"
+pub extern "C" fn buf_new(data: *const u8, len: usize, out: *mut *mut Buf) -> i32 {
+    0
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_trait_objects() {
+    let mut scope = Scope::new();
+
+    let mut handler = Type::dyn_trait("Handler<Request = Req>");
+    handler.bound("Send").lifetime("'static");
+
+    scope
+        .new_struct("Server")
+        .field("handler", Type::new("Box").generic(handler).clone())
+        .field("log", "Option<&'static dyn Log>");
+
+    let expect = r#"
+struct Server {
+    handler: Box<dyn Handler<Request = Req> + Send + 'static>,
+    log: Option<&'static dyn Log>,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_impl_trait() {
+    let mut scope = Scope::new();
+
+    let mut ret = Type::impl_trait("Iterator<Item = &'a str>");
+    ret.lifetime("'a");
+
+    scope
+        .new_fn("words")
+        .generic("'a")
+        .arg("src", Type::impl_trait("AsRef<str>"))
+        .arg("text", "&'a str")
+        .ret(ret)
+        .line("text.split(' ')");
+
+    let expect = r#"
+fn words<'a>(src: impl AsRef<str>, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    text.split(' ')
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_fn_ptr_fields() {
+    let mut scope = Scope::new();
+
+    let mut on_close = Type::fn_ptr(vec![Type::mut_ptr("c_void")]);
+    on_close.set_fn_unsafe(true).set_fn_abi("C");
+
+    scope
+        .new_struct("Callbacks")
+        .repr("C")
+        .field(
+            "on_data",
+            "Option<unsafe extern \"C\" fn(*const u8, usize) -> i32>",
+        )
+        .field("on_close", on_close);
+
+    let expect = r#"
+#[repr(C)]
+struct Callbacks {
+    on_data: Option<unsafe extern "C" fn(*const u8, usize) -> i32>,
+    on_close: unsafe extern "C" fn(*mut c_void),
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_with_const_generic_target() {
+    let mut scope = Scope::new();
+
+    let mut target = Type::new("ArrayVec");
+    target.generic("u8").const_generic("N");
+
+    let mut imp = Impl::new(target);
+    imp.generic("const N: usize")
+        .new_fn("capacity")
+        .arg_ref_self()
+        .ret("usize")
+        .line("N");
+    scope.push_impl(imp);
+
+    let expect = r#"
+impl<const N: usize> ArrayVec<u8, N> {
+    fn capacity(&self) -> usize {
+        N
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_associated_types() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("sum_all")
+        .generic("I: IntoIterator")
+        .arg("iter", "I")
+        .ret(Type::new("I").associated("Item"))
+        .bound("I::Item", "Sum<<I as IntoIterator>::Item>")
+        .line("iter.into_iter().sum()");
+
+    let expect = r#"
+fn sum_all<I: IntoIterator>(iter: I) -> I::Item
+where I::Item: Sum<<I as IntoIterator>::Item>,
+{
+    iter.into_iter().sum()
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_unit_and_never_ret() {
+    let mut scope = Scope::new();
+
+    scope.new_fn("noop").ret(Type::unit());
+    scope.new_fn("unit_str").ret("()");
+    scope
+        .new_fn("abort")
+        .ret(Type::never())
+        .line("std::process::abort()");
+
+    let expect = r#"
+fn abort() -> ! {
+    std::process::abort()
+}
+
+fn noop() {
+}
+
+fn unit_str() {
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_wrapped_types() {
+    let mut scope = Scope::new();
+
+    let user = Type::new("User");
+
+    scope
+        .new_struct("Cache")
+        .field("users", user.arc().vec())
+        .field("current", user.option());
+
+    scope
+        .new_fn("load")
+        .ret(user.vec().result("io::Error"))
+        .line("Ok(vec![])");
+
+    let expect = r#"
+struct Cache {
+    users: Vec<Arc<User>>,
+    current: Option<User>,
+}
+
+fn load() -> Result<Vec<User>, io::Error> {
+    Ok(vec![])
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_bounded_generic() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Foo")
+        .generic("T: Into<String>")
+        .field("one", "T");
+
+    let expect = r#"
+struct Foo<T: Into<String>> {
+    one: T,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_with_qualified_path() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_impl("Wrapper<T>")
+        .generic("T")
+        .impl_trait("Iterator")
+        .associate_type("Item", Type::qualified("T", "IntoIterator", "Item"))
+        .new_fn("next")
+        .arg_mut_self()
+        .ret("Option<Self::Item>")
+        .line("None");
+
+    let expect = r#"
+impl<T> Iterator for Wrapper<T> {
+    type Item = <T as IntoIterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        None
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_fn_trait_fields() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Callbacks")
+        .field(
+            "on_event",
+            Type::dyn_trait(Type::fn_trait("FnMut", vec!["Event"], None::<Type>)).boxed(),
+        )
+        .field("filter", "Box<dyn Fn(&Event) -> bool + Send>");
+
+    let expect = r#"
+struct Callbacks {
+    on_event: Box<dyn FnMut(Event)>,
+    filter: Box<dyn Fn(&Event) -> bool + Send>,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn scope_map_types() {
+    let mut scope = Scope::new();
+
+    scope.new_struct("Foo").field("old", "Option<Old>");
+
+    scope
+        .new_fn("convert")
+        .arg("old", "&Old")
+        .ret("Result<Old, Error>")
+        .line("todo!()");
+
+    scope
+        .new_impl("Foo")
+        .impl_trait("From<Old>")
+        .new_fn("from")
+        .arg("old", "Old")
+        .ret("Self")
+        .line("Foo { old: Some(old) }");
+
+    scope
+        .new_module("inner")
+        .scope()
+        .new_trait("Convert")
+        .new_fn("convert")
+        .arg_ref_self()
+        .ret("Vec<Old>");
+
+    scope.map_types(|ty| {
+        if ty.name() == "Old" {
+            ty.set_name("new::New");
+        }
+    });
+
+    let expect = r#"
+struct Foo {
+    old: Option<new::New>,
+}
+
+impl From<new::New> for Foo {
+    fn from(old: new::New) -> Self {
+        Foo { old: Some(old) }
+    }
+}
+
+fn convert(old: &new::New) -> Result<new::New, Error> {
+    todo!()
+}
+
+mod inner {
+    trait Convert {
+        fn convert(&self) -> Vec<new::New>;
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn scope_map_types_skips_declarations() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Old")
+        .generic("T")
+        .field("value", "T")
+        .field("next", "Option<Box<Old<T>>>");
+    scope.new_type_alias("Old2", "Old<u8>");
+
+    scope.map_types(|ty| {
+        if ty.name() == "Old" {
+            ty.set_name("New");
+        } else if ty.name() == "T" {
+            ty.set_name("U");
+        }
+    });
+
+    let expect = r#"
+struct Old<T> {
+    value: U,
+    next: Option<Box<New<U>>>,
+}
+
+type Old2 = New<u8>;"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_turbofish_body() {
+    let mut scope = Scope::new();
+
+    let ty = Type::new("Vec<u8>");
+    scope
+        .new_fn("empty")
+        .ret(&ty)
+        .line(format!("{}::new()", ty.turbofish()));
+
+    let expect = r#"
+fn empty() -> Vec<u8> {
+    Vec::<u8>::new()
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn scope_with_extern_block() {
+    let mut scope = Scope::new();
+
+    let block = scope.new_extern_block("C");
+    block.attr("link(name = \"m\")");
+    block.new_fn("cos").vis("pub").arg("x", "f64").ret("f64");
+    block
+        .new_fn("printf")
+        .arg("format", "*const c_char")
+        .ret("c_int");
+
+    scope
+        .new_fn("callback")
+        .vis("pub")
+        .set_unsafe(true)
+        .extern_abi("system")
+        .arg("data", "*mut c_void")
+        .line("todo!()");
+
+    let expect = r#"
+#[link(name = "m")]
+extern "C" {
+    pub fn cos(x: f64) -> f64;
+
+    fn printf(format: *const c_char) -> c_int;
+}
+
+pub unsafe extern "system" fn callback(data: *mut c_void) {
+    todo!()
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_where_bound() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("store")
+        .generic("T")
+        .generic("F")
+        .arg("value", "T")
+        .arg("f", "F")
+        .where_bound("T: Serialize + 'static")
+        .where_bound("F: FnOnce(&T) -> bool")
+        .line("f(&value)");
+
+    let expect = r#"
+fn store<T, F>(value: T, f: F)
+where T: Serialize + 'static,
+      F: FnOnce(&T) -> bool,
+{
+    f(&value)
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn trait_with_receivers() {
+    let mut scope = Scope::new();
+
+    let trt = scope.new_trait("Service");
+    trt.generic("'a");
+    trt.new_fn("call")
+        .receiver(Receiver::Ref(Some("a".into())))
+        .ret("&'a str");
+    trt.new_fn("reset").receiver(Receiver::RefMut(None));
+    trt.new_fn("into_inner")
+        .receiver(Receiver::boxed())
+        .ret("Inner");
+    trt.new_fn("poll_ready")
+        .receiver(Receiver::pinned())
+        .arg("cx", "&mut Context<'_>");
+    trt.new_fn("consume").receiver(Receiver::MutValue);
+
+    let expect = r#"
+trait Service<'a> {
+    fn call(&'a self) -> &'a str;
+
+    fn reset(&mut self);
+
+    fn into_inner(self: Box<Self>) -> Inner;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>);
+
+    fn consume(mut self);
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_arg_attributes() {
+    let mut scope = Scope::new();
+
+    let func = scope.new_fn("handle");
+    func.new_arg("buf", "Vec<u8>").set_mut(true);
+    func.new_arg("ctx", "&Context").attr("allow(unused)");
+    func.line("buf.clear();");
+
+    let expect = r#"
+fn handle(mut buf: Vec<u8>, #[allow(unused)] ctx: &Context) {
+    buf.clear();
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_pattern_args() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("handle")
+        .arg_pattern("(x, y)", "Point")
+        .arg_pattern("Wrapper(inner)", "Wrapper")
+        .arg_pattern("Config { verbose, .. }", "&Config")
+        .line("todo!()");
+
+    let expect = r#"
+fn handle((x, y): Point, Wrapper(inner): Wrapper, Config { verbose, .. }: &Config) {
+    todo!()
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_ret_impl() {
+    let mut scope = Scope::new();
+
+    let imp = scope.new_impl("Foo");
+    imp.new_fn("iter")
+        .arg_ref_self()
+        .ret_impl("Iterator<Item = u8> + Send + '_")
+        .line("self.items.iter().copied()");
+    imp.new_fn("load")
+        .set_async(true)
+        .ret_impl(Type::new("Stream<Item = u8>"))
+        .line("futures::stream::empty()");
+
+    let expect = r#"
+impl Foo {
+    fn iter(&self) -> impl Iterator<Item = u8> + Send + '_ {
+        self.items.iter().copied()
+    }
+
+    async fn load() -> impl Stream<Item = u8> {
+        futures::stream::empty()
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_doc_sections() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("get_user")
+        .vis("pub")
+        .doc("Fetches a user from the API.")
+        .arg("client", "&Client")
+        .arg("id", "u64")
+        .ret("Result<User, Error>")
+        .doc_arg("client", "The client used for the request")
+        .doc_arg("id", "The id of the user")
+        .doc_errors("Returns an error if the user doesn't exist.")
+        .doc_example("let user = get_user(&client, 42)?;\nassert_eq!(user.id, 42);")
+        .doc_example_with("no_run", "get_user(&Client::new(), 42).unwrap();")
+        .line("client.get(id)");
+
+    let expect = r#"
+/// Fetches a user from the API.
+///
+/// # Arguments
+///
+/// * `client` - The client used for the request
+/// * `id` - The id of the user
+///
+/// # Errors
+///
+/// Returns an error if the user doesn't exist.
+///
+/// # Examples
+///
+/// ```ignore
+/// let user = get_user(&client, 42)?;
+/// assert_eq!(user.id, 42);
+/// ```
+///
+/// ```no_run
+/// get_user(&Client::new(), 42).unwrap();
+/// ```
+pub fn get_user(client: &Client, id: u64) -> Result<User, Error> {
+    client.get(id)
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_typed_attributes() {
+    let mut scope = Scope::new();
+
+    let func = scope.new_fn("len");
+    func.attr("doc(hidden)")
+        .must_use(Some("the length is computed"))
+        .inline(Inline::Always)
+        .set_cold(true)
+        .attr("doc(hidden)")
+        .ret("usize")
+        .line("0");
+
+    assert_eq!(
+        func.attributes(),
+        [
+            "inline(always)",
+            "cold",
+            "must_use = \"the length is computed\"",
+            "doc(hidden)"
+        ]
+    );
+
+    let expect = r#"
+#[inline(always)]
+#[cold]
+#[must_use = "the length is computed"]
+#[doc(hidden)]
+fn len() -> usize {
+    0
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn items_with_generic_defaults() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_trait("StrExt")
+        .vis("pub")
+        .generic_with_default("T", "String")
+        .new_fn("convert")
+        .arg_ref_self()
+        .ret("T");
+
+    scope
+        .new_struct("Wrapper")
+        .generic("A")
+        .generic_with_default("B", "Vec<A>")
+        .tuple_field(None, "A")
+        .tuple_field(None, "B");
+
+    let expect = r#"
+pub trait StrExt<T = String> {
+    fn convert(&self) -> T;
+}
+
+struct Wrapper<A, B = Vec<A>>(A, B);"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_variadic() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_extern_block("C")
+        .new_fn("printf")
+        .arg("fmt", "*const c_char")
+        .set_variadic(true)
+        .ret("c_int");
+
+    scope
+        .new_fn("log")
+        .vis("pub")
+        .set_unsafe(true)
+        .extern_abi("C")
+        .set_variadic(true)
+        .line("todo!()");
+
+    let expect = r#"
+extern "C" {
+    fn printf(fmt: *const c_char, ...) -> c_int;
+}
+
+pub unsafe extern "C" fn log(...) {
+    todo!()
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_body_tokens() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("classify")
+        .arg("values", "&[i32]")
+        .ret("Vec<&'static str>")
+        .body_tokens(quote::quote! {
+            let mut out = Vec::new();
+            for v in values {
+                let label = match *v {
+                    0 => "zero",
+                    x if x < 0 => { "negative" }
+                    _ => "positive",
+                };
+                if label.len() > 4 {
+                    out.push(label);
+                } else {
+                    out.push(Default::default());
+                }
+            }
+            out
+        });
+
+    let expect = r#"
+fn classify(values: &[i32]) -> Vec<&'static str> {
+    let mut out = Vec::new();
+    for v in values {
+        let label = match *v {
+            0 => "zero",
+            x if x < 0 => { "negative" }
+            _ => "positive",
+        };
+        if label.len() > 4 {
+            out.push(label);
+        } else {
+            out.push(Default::default());
+        }
+    }
+    out
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_body_tokens_blocks() {
+    let mut scope = Scope::new();
+
+    scope.new_fn("run").body_tokens(quote::quote! {
+        if done { return } log(x);
+        if c {} let r = 1;
+        async move { x } while let Some(v) = it.next() { log(v); }
+        let f = |x: u8| -> u8 { x + 1 };
+        unsafe { ptr.read() }.len()
+    });
+
+    let expect = r#"
+fn run() {
+    if done {
+        return
+    }
+    log(x);
+    if c {}
+    let r = 1;
+    async move {
+        x
+    }
+    while let Some(v) = it.next() {
+        log(v);
+    }
+    let f = |x: u8| -> u8 {
+        x + 1
+    };
+    unsafe {
+        ptr.read()
+    }.len()
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_track_caller_and_no_mangle() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("fail")
+        .set_track_caller(true)
+        .arg("msg", "&str")
+        .ret("!")
+        .line("panic!(\"{}\", msg)");
+
+    scope
+        .new_fn("plugin_init")
+        .vis("pub")
+        .extern_abi("C")
+        .set_no_mangle(true)
+        .ret("i32")
+        .line("0");
+
+    let expect = r#"
+#[track_caller]
+fn fail(msg: &str) -> ! {
+    panic!("{}", msg)
+}
+
+#[no_mangle]
+pub extern "C" fn plugin_init() -> i32 {
+    0
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+#[should_panic(expected = "`#[track_caller]` requires the Rust ABI")]
+fn fn_with_track_caller_and_c_abi() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("callback")
+        .extern_abi("C")
+        .set_track_caller(true);
+
+    scope.to_string();
+}
+
+#[test]
+fn impl_with_cfg_gated_fns() {
+    let mut scope = Scope::new();
+
+    let imp = scope.new_impl("Client");
+    imp.new_fn("fetch")
+        .vis("pub")
+        .cfg("feature = \"blocking\"")
+        .arg_ref_self()
+        .ret("Response")
+        .line("self.inner.fetch()");
+    imp.new_fn("fetch")
+        .vis("pub")
+        .cfg(Cfg::not(Cfg::feature("blocking")))
+        .set_async(true)
+        .arg_ref_self()
+        .ret("Response")
+        .line("self.inner.fetch().await");
+
+    let expect = r#"
+impl Client {
+    #[cfg(feature = "blocking")]
+    pub fn fetch(&self) -> Response {
+        self.inner.fetch()
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    pub async fn fetch(&self) -> Response {
+        self.inner.fetch().await
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_lifetimes() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("longest")
+        .generic("T")
+        .lifetime("'a")
+        .generic("'b")
+        .bound("'b", "'a")
+        .bound("T", "'a")
+        .arg("x", "&'a T")
+        .arg("y", "&'b T")
+        .ret("&'a T")
+        .line("x");
+
+    let expect = r#"
+fn longest<'a, 'b, T>(x: &'a T, y: &'b T) -> &'a T
+where 'b: 'a,
+      T: 'a,
+{
+    x
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn trait_from_impl_signatures() {
+    let mut scope = Scope::new();
+
+    let mut len = Function::new("len");
+    len.arg_ref_self().ret("usize").line("self.items.len()");
+
+    scope.new_impl("Stack").push_fn(len.clone());
+
+    scope
+        .new_trait("Len")
+        .push_fn(len.set_signature_only(true).clone());
+
+    let mut block = ExternBlock::new("C");
+    block.push_fn(
+        Function::new("stack_len")
+            .arg("stack", "*const Stack")
+            .ret("usize")
+            .line("unreachable!()")
+            .set_signature_only(true)
+            .clone(),
+    );
+    scope.push_extern_block(block);
+
+    let expect = r#"
+extern "C" {
+    fn stack_len(stack: *const Stack) -> usize;
+}
+
+trait Len {
+    fn len(&self) -> usize;
+}
+
+impl Stack {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_pushed_lines() {
+    let mut scope = Scope::new();
+
+    let mut block = Block::new("for item in items");
+    block.push_lines(
+        "
+            if item.is_empty() {
+                continue;
+            }
+
+            total += item.len();
+        ",
+    );
+
+    scope
+        .new_module("outer")
+        .new_module("inner")
+        .new_fn("total")
+        .arg("items", "&[String]")
+        .ret("usize")
+        .push_line("    let mut total = 0;")
+        .push_block(block)
+        .push_line("total");
+
+    let expect = r#"
+mod outer {
+    mod inner {
+        fn total(items: &[String]) -> usize {
+            let mut total = 0;
+            for item in items {
+                if item.is_empty() {
+                    continue;
+                }
+
+                total += item.len();
+            }
+            total
+        }
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_closures() {
+    let mut scope = Scope::new();
+
+    let mut widen = Closure::new();
+    widen
+        .set_move(true)
+        .arg("x", "u32")
+        .ret("u64")
+        .line("u64::from(x) + offset");
+
+    let mut handler = Closure::new();
+    handler
+        .set_async(true)
+        .set_move(true)
+        .arg_inferred("req")
+        .line("let res = client.send(req).await?;")
+        .line("Ok(res)");
+
+    let mut double = Closure::new();
+    double.arg_inferred("x").line("x * 2");
+
+    scope
+        .new_fn("build")
+        .line(format!("let widen = {};", widen))
+        .line(format!("let handler = {};", handler))
+        .line(format!("values.iter().map({}).collect()", double));
+
+    let expect = r#"
+fn build() {
+    let widen = move |x: u32| -> u64 {
+        u64::from(x) + offset
+    };
+    let handler = async move |req| {
+        let res = client.send(req).await?;
+        Ok(res)
+    };
+    values.iter().map(|x| x * 2).collect()
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_try_chain() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_impl("Client")
+        .new_fn("user_name")
+        .vis("pub")
+        .arg_ref_self()
+        .arg("id", "u64")
+        .ret("Result<String, Error>")
+        .try_chain(
+            [
+                ("res", "self.get(id)"),
+                ("user", "res.json::<User>()"),
+                ("_", "self.audit(id)"),
+            ],
+            "user.name",
+        );
+
+    let expect = r#"
+impl Client {
+    pub fn user_name(&self, id: u64) -> Result<String, Error> {
+        let res = self.get(id)?;
+        let user = res.json::<User>()?;
+        let _ = self.audit(id)?;
+        Ok(user.name)
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn module_with_test_fns() {
+    let mut scope = Scope::new();
+
+    let tests = scope.new_module("tests");
+    tests.attr("cfg(test)").import("super", "*", None);
+    tests.push_fn(
+        Function::test("parses_empty")
+            .line("assert!(parse(\"\").is_ok());")
+            .clone(),
+    );
+    tests.push_fn(
+        Function::async_test("fetches", "tokio")
+            .line("assert!(fetch().await.is_ok());")
+            .clone(),
+    );
+
+    let expect = r#"
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches() {
+        assert!(fetch().await.is_ok());
+    }
+
+    #[test]
+    fn parses_empty() {
+        assert!(parse("").is_ok());
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn scope_with_bench_group() {
+    let mut scope = Scope::new();
+
+    let group = scope.new_bench_group("serializers");
+    group.new_bench("point", "black_box(&point).to_bytes()");
+    group
+        .new_bench("line", "black_box(&line).to_bytes()")
+        .doc("Benchmarks serializing a line");
+
+    let expect = r#"
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn bench_point(c: &mut Criterion) {
+    c.bench_function("point", |b| b.iter(|| black_box(&point).to_bytes()));
+}
+
+/// Benchmarks serializing a line
+fn bench_line(c: &mut Criterion) {
+    c.bench_function("line", |b| b.iter(|| black_box(&line).to_bytes()));
+}
+
+criterion_group!(serializers, bench_point, bench_line);
+
+criterion_main!(serializers);"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn scope_with_main() {
+    let mut scope = Scope::new();
+    scope.new_main(None).line("println!(\"Hello, world!\");");
+
+    let expect = r#"
+fn main() {
+    println!("Hello, world!");
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+
+    let mut scope = Scope::new();
+    scope
+        .new_main(Some("tokio"))
+        .ret("Result<(), Error>")
+        .line("cli::run().await?;")
+        .line("Ok(())");
+
+    let expect = r#"
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    cli::run().await?;
+    Ok(())
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn deprecated_items() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("OldClient")
+        .vis("pub")
+        .deprecated(Some("0.3.0"), Some("use `Client` instead"));
+    scope
+        .new_enum("OldError")
+        .vis("pub")
+        .deprecated(None, None)
+        .new_variant("Io");
+    scope
+        .new_trait("OldApi")
+        .deprecated(None, Some("removed from the API"));
+    scope
+        .new_fn("fetch_v1")
+        .vis("pub")
+        .deprecated(Some("0.2.0"), None)
+        .line("fetch()");
+
+    let expect = r#"
+#[deprecated(note = "removed from the API")]
+trait OldApi {
+}
+
+#[deprecated(since = "0.3.0", note = "use `Client` instead")]
+pub struct OldClient;
+
+#[deprecated]
+pub enum OldError {
+    Io,
+}
+
+#[deprecated(since = "0.2.0")]
+pub fn fetch_v1() {
+    fetch()
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn fn_with_export_c() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_fn("mylib_version")
+        .export_c("mylib_version")
+        .ret("u32")
+        .line("1");
+    scope
+        .new_fn("init")
+        .set_unsafe(true)
+        .export_c("mylib_init")
+        .arg("config", "*const Config")
+        .line("todo!()");
+
+    let expect = r#"
+#[export_name = "mylib_init"]
+pub unsafe extern "C" fn init(config: *const Config) {
+    todo!()
+}
+
+#[no_mangle]
+pub extern "C" fn mylib_version() -> u32 {
+    1
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn tuple_structs() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("UserId")
+        .vis("pub")
+        .derive("Debug")
+        .new_tuple_field("u64")
+        .vis("pub");
+
+    let pair = scope.new_struct("Pair").vis("pub(crate)");
+    pair.new_tuple_field("String")
+        .vis("pub(crate)")
+        .annotation("#[serde(rename = \"k\")]");
+    pair.push_tuple_field(Field::tuple("Value"));
+
+    scope
+        .new_struct("Meters")
+        .new_tuple_field("f64")
+        .doc("The length in meters");
+
+    let expect = r#"
+struct Meters(
+    /// The length in meters
+    f64,
+);
+
+pub(crate) struct Pair(#[serde(rename = "k")] pub(crate) String, Value);
+
+#[derive(Debug)]
+pub struct UserId(pub u64);"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn module_with_markers() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_module("state")
+        .vis("pub")
+        .push_markers(["Closed", "Open"], |marker| {
+            marker
+                .vis("pub")
+                .derive("Debug")
+                .derive("Clone")
+                .derive("Copy");
+        });
+
+    let expect = r#"
+pub mod state {
+    #[derive(Clone, Copy, Debug)]
+    pub struct Closed;
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Open;
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_field_attributes() {
+    let mut scope = Scope::new();
+
+    let user = scope.new_struct("User");
+    user.derive("Deserialize");
+    user.new_field("user_id", "u64")
+        .attr("serde(rename = \"userId\")");
+    user.new_field("tags", "Vec<String>")
+        .doc("Optional tags")
+        .attr("serde(default)")
+        .attr("builder(default)");
+
+    let expect = r#"
+#[derive(Deserialize)]
+struct User {
+    #[serde(rename = "userId")]
+    user_id: u64,
+    /// Optional tags
+    #[serde(default)]
+    #[builder(default)]
+    tags: Vec<String>,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_wrapped_field_docs() {
+    let mut scope = Scope::new();
+
+    scope.new_module("api").new_struct("Request").new_field("retries", "u32").doc(
+        "How many times the request is retried before giving up, not counting the first attempt.
+
+Retries happen when:
+- the connection is reset, or the server does not answer within the configured timeout
+- the server answers with a 503
+
+```
+let retries = 3;
+```",
+    );
+
+    let expect = r#"
+mod api {
+    struct Request {
+        /// How many times the request is retried before giving up, not counting
+        /// the first attempt.
+        ///
+        /// Retries happen when:
+        /// - the connection is reset, or the server does not answer within the
+        ///   configured timeout
+        /// - the server answers with a 503
+        ///
+        /// ```
+        /// let retries = 3;
+        /// ```
+        retries: u32,
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn structs_with_repr_hints() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Header")
+        .repr("C")
+        .repr_packed()
+        .field("tag", "u8")
+        .field("len", "u32");
+    scope
+        .new_struct("Page")
+        .repr("C")
+        .repr_align(4096)
+        .field("data", "[u8; 4096]");
+    scope
+        .new_struct("Handle")
+        .repr_transparent()
+        .tuple_field(None, "*mut c_void");
+    scope.new_enum("Kind").repr("C").repr("u8").new_variant("A");
+
+    let expect = r#"
+#[repr(transparent)]
+struct Handle(*mut c_void);
+
+#[repr(C, packed)]
+struct Header {
+    tag: u8,
+    len: u32,
+}
+
+#[repr(C, u8)]
+enum Kind {
+    A,
+}
+
+#[repr(C, align(4096))]
+struct Page {
+    data: [u8; 4096],
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+#[should_panic(expected = "`repr(packed)` and `repr(align)` can't be combined")]
+fn struct_with_packed_and_align() {
+    let mut scope = Scope::new();
+
+    scope.new_struct("Header").repr_packed().repr_align(8);
+    scope.to_string();
+}
+
+#[test]
+fn non_exhaustive_items() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Config")
+        .vis("pub")
+        .derive("Debug")
+        .set_non_exhaustive(true)
+        .new_field("timeout", "u64")
+        .vis("pub");
+    scope
+        .new_enum("Error")
+        .vis("pub")
+        .set_non_exhaustive(true)
+        .new_variant("Io");
+
+    let expect = r#"
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Config {
+    pub timeout: u64,
+}
+
+#[non_exhaustive]
+pub enum Error {
+    Io,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_qualified_derives() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("User")
+        .derive("serde::Serialize")
+        .derive("Debug")
+        .derive("serde::Deserialize")
+        .derive("Serialize")
+        .derive("Debug")
+        .field("id", "u64");
+
+    let expect = r#"
+#[derive(Debug, serde::Deserialize, Serialize)]
+struct User {
+    id: u64,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_cfg_derives() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Model")
+        .derive("Debug")
+        .derive_when("feature = \"serde\"", ["Serialize", "Deserialize"])
+        .derive_when(
+            Cfg::all([Cfg::feature("schema"), Cfg::new("unix")]),
+            ["JsonSchema"],
+        )
+        .field("id", "u64");
+
+    let expect = r#"
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(all(feature = "schema", unix), derive(JsonSchema))]
+struct Model {
+    id: u64,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_default_impl() {
+    let mut scope = Scope::new();
+
+    let mut config = Struct::new("Config");
+    config.generic("T");
+    config.new_field("retries", "u32").default_value("3");
+    config
+        .new_field("name", "String")
+        .default_value("String::from(\"local\")");
+    config.field("extra", "T");
+
+    let mut point = Struct::new("Point");
+    point.new_tuple_field("i32").default_value("1");
+    point.new_tuple_field("i32");
+
+    scope.push_impl(config.impl_default());
+    scope.push_struct(config);
+    scope.push_impl(point.impl_default());
+    scope.push_struct(point);
+
+    let expect = r#"
+struct Config<T> {
+    retries: u32,
+    name: String,
+    extra: T,
+}
+
+impl<T> Default for Config<T> {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            name: String::from("local"),
+            extra: Default::default(),
+        }
+    }
+}
+
+struct Point(i32, i32);
+
+impl Default for Point {
+    fn default() -> Self {
+        Self(1, Default::default())
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_explicit_default_values() {
+    let mut scope = Scope::new();
+
+    let mut client = Struct::new("Client");
+    client.new_field("retries", "u32").default_value("3");
+    client.field("timeout", "Duration");
+    client.field("agent", "String");
+
+    let mut pair = Struct::new("Pair");
+    pair.new_tuple_field("i32");
+    pair.new_tuple_field("i32");
+
+    scope.push_impl(
+        client.impl_default_with([("timeout", "Duration::from_secs(30)"), ("retries", "5")]),
+    );
+    scope.push_struct(client);
+    scope.push_impl(pair.impl_default_with([("1", "-1")]));
+    scope.push_struct(pair);
+
+    let expect = r#"
+struct Client {
+    retries: u32,
+    timeout: Duration,
+    agent: String,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            timeout: Duration::from_secs(30),
+            agent: Default::default(),
+        }
+    }
+}
+
+struct Pair(i32, i32);
+
+impl Default for Pair {
+    fn default() -> Self {
+        Self(Default::default(), -1)
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+#[should_panic(expected = "struct has no field `timeout`")]
+fn struct_with_unknown_default_value() {
+    let mut client = Struct::new("Client");
+    client.field("retries", "u32");
+
+    client.impl_default_with([("timeout", "30")]);
+}
+
+#[test]
+fn struct_with_redacted_debug_impl() {
+    let mut scope = Scope::new();
+
+    let mut credentials = Struct::new("Credentials");
+    credentials.generic("T");
+    credentials.field("user", "String");
+    credentials
+        .new_field("password", "String")
+        .set_debug(DebugStyle::Redact);
+    credentials
+        .new_field("session", "T")
+        .set_debug(DebugStyle::Skip);
+
+    let mut token = Struct::new("Token");
+    token.new_tuple_field("u64");
+    token
+        .new_tuple_field("String")
+        .set_debug(DebugStyle::Redact);
+
+    let marker = Struct::new("Marker");
+
+    scope.push_impl(credentials.impl_debug());
+    scope.push_impl(token.impl_debug());
+    scope.push_impl(marker.impl_debug());
+
+    let expect = r#"
+impl<T> std::fmt::Debug for Credentials<T>
+where T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("user", &self.user)
+            .field("password", &"<redacted>")
+            .finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for Marker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Marker")
+    }
+}
+
+impl std::fmt::Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Token")
+            .field(&self.0)
+            .field(&"<redacted>")
+            .finish()
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn generic_struct_with_debug_impl() {
+    let mut scope = Scope::new();
+
+    let mut map = Struct::new("Map");
+    map.generic("K")
+        .generic("V")
+        .generic("M")
+        .const_generic("N", "usize")
+        .field("keys", "[K; N]")
+        .field("values", "Vec<Option<V>>")
+        .field("_marker", "std::marker::PhantomData<M>");
+
+    scope.push_impl(map.impl_debug());
+
+    let expect = r#"
+impl<K, V, M, const N: usize> std::fmt::Debug for Map<K, V, M, N>
+where K: std::fmt::Debug,
+      V: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Map")
+            .field("keys", &self.keys)
+            .field("values", &self.values)
+            .field("_marker", &self._marker)
+            .finish()
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_sorted_fields() {
+    let mut scope = Scope::new();
+
+    let user = scope
+        .new_struct("User")
+        .field("name", "String")
+        .field("email", "String")
+        .field("age", "u16")
+        .sort_fields();
+    user.insert_field(0, Field::new("id", "u64"));
+
+    scope
+        .new_struct("Visit")
+        .field("path", "String")
+        .push_field(Field::new("user", "UserId").vis("pub").clone())
+        .field("at", "Instant")
+        .sort_fields_by_key(|field| field.visibility.is_none());
+
+    let expect = r#"
+struct User {
+    id: u64,
+    age: u16,
+    email: String,
+    name: String,
+}
+
+struct Visit {
+    pub user: UserId,
+    path: String,
+    at: Instant,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn structs_with_phantom_markers() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Id")
+        .generic("T")
+        .field("raw", "u64")
+        .phantom("fn() -> T");
+
+    scope
+        .new_struct("Handle")
+        .generic("'a")
+        .generic("T")
+        .generic("U")
+        .field("value", "Box<U>")
+        .phantom_unused();
+
+    scope
+        .new_struct("Token")
+        .generic("T")
+        .tuple_field(None, "u32")
+        .phantom_unused();
+
+    let expect = r#"
+struct Handle<'a, T, U> {
+    value: Box<U>,
+    _marker: std::marker::PhantomData<(&'a (), T)>,
+}
+
+struct Id<T> {
+    raw: u64,
+    _marker: std::marker::PhantomData<fn() -> T>,
+}
+
+struct Token<T>(u32, std::marker::PhantomData<T>);"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn structs_with_where_bounds() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Pool")
+        .generic("T")
+        .generic("F")
+        .where_bound("T: Clone + Send")
+        .bound("F", "Fn() -> T")
+        .bound("T", "'static")
+        .field("items", "Vec<T>")
+        .field("make", "F");
+
+    scope
+        .new_struct("Wrapper")
+        .generic("T")
+        .where_bound("T: Display")
+        .tuple_field(None, "T");
+
+    let expect = r#"
+struct Pool<T, F>
+where T: Clone + Send + 'static,
+      F: Fn() -> T,
+{
+    items: Vec<T>,
+    make: F,
+}
+
+struct Wrapper<T>(T)
+where T: Display,
+;"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn items_with_const_generics() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Buffer")
+        .const_generic("N", "usize")
+        .generic("T")
+        .generic("'a")
+        .field("data", "[T; N]")
+        .field("name", "&'a str");
+
+    scope
+        .new_impl("Buffer")
+        .const_generic("N", "usize")
+        .generic("T")
+        .generic("'a")
+        .target_generic("'a")
+        .target_generic("T")
+        .target_generic("N")
+        .new_fn("len")
+        .arg_ref_self()
+        .ret("usize")
+        .line("N");
+
+    scope
+        .new_trait("Chunked")
+        .const_generic("SIZE", "usize")
+        .new_fn("chunk")
+        .arg_ref_self()
+        .ret("[u8; SIZE]");
+
+    let expect = r#"
+struct Buffer<'a, T, const N: usize> {
+    data: [T; N],
+    name: &'a str,
+}
+
+impl<'a, T, const N: usize> Buffer<'a, T, N> {
+    fn len(&self) -> usize {
+        N
+    }
+}
+
+trait Chunked<const SIZE: usize> {
+    fn chunk(&self) -> [u8; SIZE];
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn must_use_items() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_struct("Guard")
+        .doc("Releases the lock when dropped.")
+        .must_use(Some("the lock is released when the guard is dropped"))
+        .derive("Debug")
+        .attr("doc(hidden)")
+        .deprecated(None, Some("use `Lease`"))
+        .field("lock", "Arc<Lock>");
+
+    scope
+        .new_enum("Response")
+        .must_use(None)
+        .set_non_exhaustive(true)
+        .new_variant("Ok");
+
+    let expect = r#"
+/// Releases the lock when dropped.
+#[derive(Debug)]
+#[deprecated(note = "use `Lease`")]
+#[must_use = "the lock is released when the guard is dropped"]
+#[doc(hidden)]
+struct Guard {
+    lock: Arc<Lock>,
+}
+
+#[must_use]
+#[non_exhaustive]
+enum Response {
+    Ok,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_from_struct_fields() {
+    let mut scope = Scope::new();
+
+    let mut user = Struct::new("User");
+    user.new_field("id", "u64").doc("The primary key");
+    user.new_field("name", "String").attr("serde(default)");
+
+    let fields: Vec<_> = user
+        .fields()
+        .map(|field| {
+            (
+                field.name.clone(),
+                field.ty.to_string(),
+                field.documentation.clone(),
+                field.annotation.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            (
+                "id".to_string(),
+                "u64".to_string(),
+                "The primary key".to_string(),
+                vec![],
+            ),
+            (
+                "name".to_string(),
+                "String".to_string(),
+                String::new(),
+                vec!["#[serde(default)]".to_string()],
+            ),
+        ]
+    );
+
+    let imp = scope.new_impl("User");
+    for field in user.fields() {
+        imp.new_fn(&field.name)
+            .vis("pub")
+            .arg_ref_self()
+            .ret(Type::reference(&field.ty))
+            .line(format!("&self.{}", field.name));
+    }
+
+    let expect = r#"
+impl User {
+    pub fn id(&self) -> &u64 {
+        &self.id
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_edited_fields() {
+    let mut scope = Scope::new();
+
+    let mut user = Struct::new("User");
+    user.generic("T")
+        .field("id", "u64")
+        .field("password_hash", "String")
+        .field("meta", "T");
+
+    let mut public = user.clone();
+    public.rename("PublicUser").vis("pub");
+    assert_eq!(
+        public.remove_field("password_hash").unwrap().ty.to_string(),
+        "String"
+    );
+    assert!(public.remove_field("password_hash").is_none());
+    assert!(public
+        .replace_field("id", Field::new("id", "UserId").vis("pub").clone())
+        .is_some());
+    assert!(public
+        .replace_field("email", Field::new("email", "String"))
+        .is_none());
+
+    let mut empty = Struct::new("Empty");
+    empty.field("inner", "u8");
+    empty.remove_field("inner");
+
+    scope.push_struct(public);
+    scope.push_struct(user);
+    scope.push_struct(empty);
+
+    let expect = r#"
+struct Empty;
+
+pub struct PublicUser<T> {
+    pub id: UserId,
+    meta: T,
+}
+
+struct User<T> {
+    id: u64,
+    password_hash: String,
+    meta: T,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_generated_new() {
+    let mut scope = Scope::new();
+
+    let mut user = Struct::new("User");
+    user.vis("pub");
+    user.field("id", "u64");
+    user.new_field("name", "String");
+    user.new_field("tags", "Vec<String>")
+        .default_value("Vec::new()");
+
+    let mut point = Struct::new("Point");
+    point
+        .generic("T")
+        .tuple_field(None, "i32")
+        .tuple_field(None, "i32")
+        .tuple_field(None, "std::marker::PhantomData<T>");
+
+    scope.push_impl(user.generate_new(true));
+    scope.push_impl(point.generate_new(false));
+
+    let expect = r#"
+impl<T> Point<T> {
+    fn new(f0: i32, f1: i32) -> Self {
+        Self(f0, f1, std::marker::PhantomData)
+    }
+}
+
+impl User {
+    pub fn new(id: impl Into<u64>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            tags: Vec::new(),
+        }
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+
+    let imp = user.generate_new(false);
+
+    let mut ret = String::new();
+    imp.fmt(&mut Formatter::new(&mut ret)).unwrap();
+    assert!(ret.contains("fn new(id: u64, name: String) -> Self {"));
+    assert!(ret.contains("            id,\n            name,\n"));
+}
+
+#[test]
+fn struct_with_builder() {
+    let mut scope = Scope::new();
+
+    let mut user = Struct::new("User");
+    user.vis("pub").generic("T");
+    user.new_field("name", "String").doc("Display name");
+    user.field("meta", "T");
+    user.new_field("admin", "bool").default_value("false");
+
+    scope.push_enum(BuilderGen::error_enum("BuildError"));
+    BuilderGen::new(&user)
+        .validate("validate_user")
+        .generate(&mut scope);
+
+    let expect = r#"
+#[derive(Debug)]
+pub enum BuildError {
+    MissingField(&'static str),
+}
+
+impl<T> Default for UserBuilder<T> {
+    fn default() -> Self {
+        Self {
+            name: None,
+            meta: None,
+            admin: None,
+        }
+    }
+}
+
+/// Builder for [`User`].
+pub struct UserBuilder<T> {
+    name: Option<String>,
+    meta: Option<T>,
+    admin: Option<bool>,
+}
+
+impl<T> UserBuilder<T> {
+    /// Display name
+    pub fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn meta(mut self, meta: T) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    pub fn admin(mut self, admin: bool) -> Self {
+        self.admin = Some(admin);
+        self
+    }
+
+    /// Builds the [`User`], failing if a required field wasn't set.
+    pub fn build(self) -> Result<User<T>, BuildError> {
+        let value = User {
+            name: self.name.ok_or(BuildError::MissingField("name"))?,
+            meta: self.meta.ok_or(BuildError::MissingField("meta"))?,
+            admin: self.admin.unwrap_or_else(|| false),
+        };
+        validate_user(&value)?;
+        Ok(value)
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_accessors() {
+    let mut scope = Scope::new();
+
+    let mut user = Struct::new("User");
+    user.vis("pub");
+    user.new_field("id", "u64").set_setter(false);
+    user.new_field("name", "String").doc("Display name");
+    user.new_field("cache", "Cache")
+        .set_getter(false)
+        .set_setter(false);
+
+    scope.push_impl(user.generate_accessors(GetterStyle::RefMut, SetterStyle::Chained));
+
+    let mut point = Struct::new("Point");
+    point.field("x", "i32");
+
+    scope.push_impl(point.generate_accessors(GetterStyle::Copy, SetterStyle::Plain));
+
+    let expect = r#"
+impl Point {
+    fn x(&self) -> i32 {
+        self.x
+    }
+
+    fn set_x(&mut self, x: i32) {
+        self.x = x;
+    }
+}
+
+impl User {
+    pub fn id(&self) -> &u64 {
+        &self.id
+    }
+
+    pub fn id_mut(&mut self) -> &mut u64 {
+        &mut self.id
+    }
+
+    /// Display name
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn set_name(&mut self, name: String) -> &mut Self {
+        self.name = name;
+        self
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_from_syn() {
+    let mut scope = Scope::new();
+
+    let item: syn::ItemStruct = syn::parse_str(
+        r#"
+        /// A user of the service.
+        ///
+        /// Users are created on sign up.
+        #[derive(Debug, serde::Serialize)]
+        #[serde(rename_all = "camelCase")]
+        #[repr(C)]
+        pub struct User<'a, T: Clone, const N: usize = 4>
+        where
+            T: Send,
+        {
+            /// The primary key
+            pub id: u64,
+            #[serde(skip)]
+            name: &'a str,
+            tags: [T; N],
+        }
+    "#,
+    )
+    .unwrap();
+
+    let mut user = Struct::from_syn(&item);
+    user.derive("Clone").field("email", "String");
+    scope.push_struct(user);
+
+    let item: syn::ItemStruct = syn::parse_str("pub(crate) struct Meters(pub f64);").unwrap();
+    scope.push_struct(Struct::from_syn(&item));
+
+    let expect = r#"
+pub(crate) struct Meters(pub f64);
+
+/// A user of the service.
+///
+/// Users are created on sign up.
+#[derive(Clone, Debug, serde::Serialize)]
+#[repr(C)]
+#[serde(rename_all = "camelCase")]
+pub struct User<'a, T, const N: usize = 4>
+where T: Clone + Send,
+{
+    /// The primary key
+    pub id: u64,
+    #[serde(skip)]
+    name: &'a str,
+    tags: [T; N],
+    email: String,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn struct_with_display_impl() {
+    let mut scope = Scope::new();
+
+    let mut version = Struct::new("Version");
+    version
+        .field("major", "u32")
+        .field("minor", "u32")
+        .field("patch", "u32");
+    scope.push_impl(version.impl_display("v{major}.{minor}.{patch}"));
+
+    let mut ratio = Struct::new("Ratio");
+    ratio.tuple_field(None, "f64");
+    scope.push_impl(ratio.impl_display("{{{0:.2}}}"));
+
+    let expect = r#"
+impl std::fmt::Display for Ratio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{{:.2}}}", self.0)
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+#[should_panic(expected = "display template references unknown field `build`")]
+fn struct_with_display_impl_and_unknown_field() {
+    let mut version = Struct::new("Version");
+    version.field("major", "u32");
+    version.impl_display("{major}+{build}");
+}
+
+#[test]
+fn enum_with_discriminants() {
+    let mut scope = Scope::new();
+
+    let kind = scope.new_enum("Kind").repr("u8");
+    kind.new_variant("Read").discriminant("0x10");
+    kind.new_variant("Write");
+    kind.new_variant("Exec").discriminant("0x40");
+
+    let message = scope.new_enum("Message").repr("u16");
+    message.new_variant("Ping").discriminant("1");
+    message
+        .new_variant("Data")
+        .tuple("Vec<u8>")
+        .discriminant("2");
+
+    let expect = r#"
+#[repr(u8)]
+enum Kind {
+    Read = 0x10,
+    Write,
+    Exec = 0x40,
+}
+
+#[repr(u16)]
+enum Message {
+    Ping = 1,
+    Data(Vec<u8>) = 2,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+#[should_panic(expected = "discriminant of `Big` doesn't fit in `u8`")]
+fn enum_with_out_of_range_discriminant() {
+    let mut scope = Scope::new();
+
+    let kind = scope.new_enum("Kind").repr("u8");
+    kind.new_variant("Small").discriminant("255");
+    kind.new_variant("Big");
+
+    scope.to_string();
+}
+
+#[test]
+fn enum_with_documented_variant_fields() {
+    let mut scope = Scope::new();
+
+    let event = scope.new_enum("Event");
+    let moved = event.new_variant("Moved");
+    moved.new_named("x", "i32").doc("Horizontal position");
+    moved
+        .new_named("y", "i32")
+        .doc("Vertical position")
+        .attr("serde(default)");
+    event
+        .new_variant("Key")
+        .new_tuple("char")
+        .attr("serde(with = \"key\")");
+    event.new_variant("Closed");
+
+    let expect = r#"
+enum Event {
+    Moved {
+        /// Horizontal position
+        x: i32,
+        /// Vertical position
+        #[serde(default)]
+        y: i32,
+    },
+    Key(#[serde(with = "key")] char),
+    Closed,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_documented_variants() {
+    let mut scope = Scope::new();
+
+    let message = scope.new_enum("Message");
+    message
+        .new_variant("Ping")
+        .doc("Checks that the peer is alive.\n\nThe peer answers with a `Pong`.")
+        .attr("serde(rename = \"ping\")");
+    message
+        .new_variant("Hello")
+        .deprecated(Some("0.3.0"), Some("use `Ping`"))
+        .annotation("#[serde(rename = \"hello\")]");
+
+    let expect = r#"
+enum Message {
+    /// Checks that the peer is alive.
+    ///
+    /// The peer answers with a `Pong`.
+    #[serde(rename = "ping")]
+    Ping,
+    #[deprecated(since = "0.3.0", note = "use `Ping`")]
+    #[serde(rename = "hello")]
+    Hello,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_cfg_variants() {
+    let mut scope = Scope::new();
+
+    let codec = scope.new_enum("Codec");
+    codec.new_variant("Gzip");
+    codec
+        .new_variant("Zstd")
+        .doc("Experimental")
+        .cfg(Cfg::feature("experimental"))
+        .cfg("unix");
+
+    let expect = r#"
+enum Codec {
+    Gzip,
+    /// Experimental
+    #[cfg(feature = "experimental")]
+    #[cfg(unix)]
+    Zstd,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_match_skeleton() {
+    let mut scope = Scope::new();
+
+    let mut event = Enum::new("Event");
+    event.set_non_exhaustive(true);
+    event.new_variant("Closed");
+    event.new_variant("Key").tuple("char");
+    event.new_variant("Moved").named("x", "i32");
+    event.new_variant("Touch").cfg("target_os = \"ios\"");
+
+    scope
+        .new_fn("handle")
+        .arg("event", "Event")
+        .push_block(event.match_skeleton("event"));
+
+    let expect = r#"
+fn handle(event: Event) {
+    match event {
+        Event::Closed => todo!(),
+        Event::Key(..) => todo!(),
+        Event::Moved { .. } => todo!(),
+        #[cfg(target_os = "ios")]
+        Event::Touch => todo!(),
+        _ => todo!(),
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_from_impls() {
+    let mut scope = Scope::new();
+
+    let mut error = Enum::new("Error");
+    error.new_variant("Io").tuple("std::io::Error");
+    error
+        .new_variant("Json")
+        .tuple("serde_json::Error")
+        .cfg(Cfg::feature("json"));
+    error.new_variant("Timeout");
+    error.new_variant("Status").tuple("u16").tuple("String");
+    error.new_variant("Read").tuple("Utf8Error");
+    error.new_variant("Write").tuple("Utf8Error");
+
+    scope.push_impls(error.generate_from_impls());
+
+    let expect = r#"
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::Io(value)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Error::Json(value)
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_string_impls() {
+    let mut scope = Scope::new();
+
+    let mut level = Enum::new("Level");
+    level.vis("pub").set_non_exhaustive(true);
+    level.new_variant("Debug");
+    level.new_variant("Trace").cfg("debug_assertions");
+
+    let (error, impls) = level.generate_string_impls(|variant| variant.name().to_lowercase());
+    scope.push_struct(error);
+    scope.push_impls(impls);
+
+    let expect = r#"
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Debug => f.write_str("debug"),
+            #[cfg(debug_assertions)]
+            Level::Trace => f.write_str("trace"),
+        }
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = ParseLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "debug" => Ok(Level::Debug),
+            #[cfg(debug_assertions)]
+            "trace" => Ok(Level::Trace),
+            _ => Err(ParseLevelError(s.to_string())),
+        }
+    }
+}
+
+/// The error returned when parsing a [`Level`] fails.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseLevelError(String);
+
+impl std::fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown Level: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseLevelError {}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_int_conversions() {
+    let mut scope = Scope::new();
+
+    let mut opcode = Enum::new("Opcode");
+    opcode.new_variant("Read").discriminant("0x01");
+    opcode.new_variant("Write");
+    scope.push_impls(opcode.generate_int_conversions("u8"));
+    scope.push_enum(opcode);
+
+    let expect = r#"
+#[repr(u8)]
+enum Opcode {
+    Read = 0x01,
+    Write,
+}
+
+impl TryFrom<u8> for Opcode {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            x if x == Opcode::Read as u8 => Ok(Opcode::Read),
+            x if x == Opcode::Write as u8 => Ok(Opcode::Write),
+            _ => Err(value),
+        }
+    }
+}
+
+impl From<Opcode> for u8 {
+    fn from(value: Opcode) -> Self {
+        value as u8
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_from_names() {
+    let mut scope = Scope::new();
+
+    let mut level = Enum::from_names(
+        "Level",
+        [("Low", "Low priority"), ("High", "High priority")],
+    );
+    level.vis("pub");
+    let names: Vec<_> = level.iter_variants().map(|v| v.name().as_str()).collect();
+    assert_eq!(names, ["Low", "High"]);
+    scope.push_enum(level);
+    scope.push_enum(Enum::from_names("Color", ["Red", "Green"]));
+
+    let expect = r#"
+enum Color {
+    Red,
+    Green,
+}
+
+pub enum Level {
+    /// Low priority
+    Low,
+    /// High priority
+    High,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_markdown_variant_docs() {
+    let mut scope = Scope::new();
+
+    let level = scope.new_module("level").new_enum("Level");
+    level.new_variant("Low").doc(
+        r#"
+        Low priority.
+
+        Handled by the background queue:
+        - retried once, after a delay that can be configured on the queue itself
+        - never paged
+
+        ```
+        let level = Level::Low;
+        ```
+        "#,
+    );
+    level.new_variant("High");
+
+    let expect = r#"
+mod level {
+    enum Level {
+        /// Low priority.
+        ///
+        /// Handled by the background queue:
+        /// - retried once, after a delay that can be configured on the queue
+        ///   itself
+        /// - never paged
+        ///
+        /// ```
+        /// let level = Level::Low;
+        /// ```
+        Low,
+        High,
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_edited_variants() {
+    let mut scope = Scope::new();
+
+    let mut color = Enum::from_names("Color", ["Red", "Green", "Blue"]);
+    color.variant_mut("Green").unwrap().discriminant(2);
+    assert_eq!(color.remove_variant("Red").unwrap().name(), "Red");
+    assert!(color.remove_variant("Red").is_none());
+    assert!(color.variant_mut("Red").is_none());
+    assert_eq!(color.variants().len(), 2);
+    scope.push_enum(color);
+
+    let expect = r#"
+enum Color {
+    Green = 2,
+    Blue,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_accessors() {
+    let mut scope = Scope::new();
+
+    let mut shape = Enum::new("Shape");
+    shape.vis("pub");
+    shape.new_variant("Circle").tuple("f64");
+    shape
+        .new_variant("Rect")
+        .named("width", "f64")
+        .named("height", "f64");
+    shape.new_variant("EmptySet").cfg("feature = \"empty\"");
+    scope.push_impl(shape.generate_accessors());
+
+    let expect = r#"
+impl Shape {
+    /// Returns `true` if the value is a [`Shape::Circle`].
+    pub fn is_circle(&self) -> bool {
+        matches!(self, Shape::Circle(..))
+    }
+
+    /// Returns a reference to the fields of the value if it is a [`Shape::Circle`].
+    pub fn as_circle(&self) -> Option<&f64> {
+        match self {
+            Shape::Circle(f0) => Some(f0),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of the value if it is a [`Shape::Circle`].
+    pub fn into_circle(self) -> Option<f64> {
+        match self {
+            Shape::Circle(f0) => Some(f0),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the value is a [`Shape::Rect`].
+    pub fn is_rect(&self) -> bool {
+        matches!(self, Shape::Rect { .. })
+    }
+
+    /// Returns a reference to the fields of the value if it is a [`Shape::Rect`].
+    pub fn as_rect(&self) -> Option<(&f64, &f64)> {
+        match self {
+            Shape::Rect { width, height } => Some((width, height)),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of the value if it is a [`Shape::Rect`].
+    pub fn into_rect(self) -> Option<(f64, f64)> {
+        match self {
+            Shape::Rect { width, height } => Some((width, height)),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the value is a [`Shape::EmptySet`].
+    #[cfg(feature = "empty")]
+    pub fn is_empty_set(&self) -> bool {
+        matches!(self, Shape::EmptySet)
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_with_serde_attrs() {
+    let mut scope = Scope::new();
+
+    let event = scope.new_enum("Event");
+    event.derive("serde::Serialize");
+    event
+        .serde_tag("kind")
+        .serde_content("data")
+        .serde_rename_all(RenameRule::Snake)
+        .serde_tag("type");
+    event.new_variant("ItemCreated").tuple("Item");
+    event
+        .new_variant("ItemDeleted")
+        .tuple("u64")
+        .serde_rename("deleted")
+        .serde_rename("removed");
+    event.new_variant("Reset");
+
+    let expect = r#"
+#[derive(serde::Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum Event {
+    ItemCreated(Item),
+    #[serde(rename = "removed")]
+    ItemDeleted(u64),
+    Reset,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+#[should_panic(expected = "several variants are serialized as `item_created`")]
+fn enum_with_duplicate_serde_names() {
+    let mut scope = Scope::new();
+
+    let event = scope.new_enum("Event");
+    event.serde_rename_all(RenameRule::Snake);
+    event.new_variant("ItemCreated");
+    event.new_variant("Created").serde_rename("item_created");
+
+    scope.to_string();
+}
+
+#[test]
+#[should_panic(expected = "internally tagged enums can't have tuple variant `Moved`")]
+fn enum_with_internal_tag_and_tuple_variant() {
+    let mut scope = Scope::new();
+
+    let event = scope.new_enum("Event");
+    event.serde_tag("type");
+    event.new_variant("Moved").tuple("i32").tuple("i32");
+
+    scope.to_string();
+}
+
+#[test]
+fn enum_from_syn() {
+    let mut scope = Scope::new();
+
+    let item: syn::ItemEnum = syn::parse_str(
+        r#"
+        /// The status of an account.
+        #[derive(Debug)]
+        #[repr(u8)]
+        pub enum Status<T: Clone> {
+            /// The account can be used
+            Active = 1,
+            #[cfg(feature = "admin")]
+            #[serde(rename = "banned")]
+            Suspended { reason: String } = 2,
+            Pending(T) = 3,
+        }
+    "#,
+    )
+    .unwrap();
+
+    let mut status = Enum::from_syn(&item);
+    status.new_variant("Deleted").discriminant(4);
+    scope.push_enum(status);
+
+    let expect = r#"
+/// The status of an account.
+#[derive(Debug)]
+#[repr(u8)]
+pub enum Status<T>
+where T: Clone,
+{
+    /// The account can be used
+    Active = 1,
+    #[cfg(feature = "admin")]
+    #[serde(rename = "banned")]
+    Suspended {
+        reason: String,
+    } = 2,
+    Pending(T) = 3,
+    Deleted = 4,
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn enum_match_with_bodies() {
+    let mut scope = Scope::new();
+
+    let mut expr = Enum::new("Expr");
+    expr.set_non_exhaustive(true);
+    expr.new_variant("Lit").tuple("i64");
+    expr.new_variant("Add")
+        .tuple("Box<Expr>")
+        .tuple("Box<Expr>");
+    expr.new_variant("Neg").named("inner", "Box<Expr>");
+    expr.new_variant("Unit");
+
+    scope
+        .new_impl("Printer")
+        .new_fn("visit_expr")
+        .arg_mut_self()
+        .arg("expr", "&Expr")
+        .push_block(expr.match_with(
+            "expr",
+            |variant| match variant.name().as_str() {
+                "Lit" => "self.lit(*f0)".to_string(),
+                "Add" => "self.add(f0, f1)".to_string(),
+                "Neg" => "self.neg(inner)".to_string(),
+                _ => "{}".to_string(),
+            },
+            "unimplemented!()",
+        ));
+
+    let expect = r#"
+impl Printer {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Lit(f0) => self.lit(*f0),
+            Expr::Add(f0, f1) => self.add(f0, f1),
+            Expr::Neg { inner } => self.neg(inner),
+            Expr::Unit => {},
+            _ => unimplemented!(),
+        }
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn unsafe_impls() {
+    let mut scope = Scope::new();
+
+    scope.new_impl("Handle").impl_trait("Send").set_unsafe(true);
+    scope
+        .new_impl("Buffer")
+        .generic("T")
+        .target_generic("T")
+        .impl_trait("Storage")
+        .set_unsafe(true)
+        .new_fn("capacity")
+        .arg_ref_self()
+        .ret("usize")
+        .line("self.cap");
+
+    let expect = r#"
+unsafe impl Send for Handle {}
+
+unsafe impl<T> Storage for Buffer<T> {
+    fn capacity(&self) -> usize {
+        self.cap
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn negative_impls() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_impl("Guard")
+        .generic("'a")
+        .target_generic("'a")
+        .impl_trait("Send")
+        .set_negative(true);
+
+    let expect = r#"
+impl<'a> !Send for Guard<'a> {}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+#[should_panic(expected = "negative impls can't have items")]
+fn negative_impl_with_items() {
+    let mut scope = Scope::new();
+
+    let imp = scope.new_impl("Guard");
+    imp.impl_trait("Clone").set_negative(true);
+    imp.new_fn("clone").arg_ref_self().ret("Self");
+
+    scope.to_string();
+}
+
+#[test]
+fn impl_with_documented_consts() {
+    let mut scope = Scope::new();
+
+    let imp = scope.new_impl("Ping");
+    imp.new_const("WIRE_ID", "u16", "0x01")
+        .vis("pub")
+        .doc("The identifier of the message on the wire.");
+    imp.new_const("MAX_LEN", "usize", "64")
+        .attr("allow(dead_code)");
+    imp.new_fn("len").arg_ref_self().ret("usize").line("0");
+
+    let expect = r#"
+impl Ping {
+    /// The identifier of the message on the wire.
+    pub const WIRE_ID: u16 = 0x01;
+    #[allow(dead_code)]
+    const MAX_LEN: usize = 64;
+
+    fn len(&self) -> usize {
+        0
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_with_generic_associated_types() {
+    let mut scope = Scope::new();
+
+    let imp = scope.new_impl("Buffer");
+    imp.generic("T")
+        .target_generic("T")
+        .impl_trait("Container")
+        .associate_type("Item", "T");
+    imp.new_associated_type("Iter", "std::slice::Iter<'a, T>")
+        .generic("T2")
+        .generic("'a")
+        .where_bound("Self: 'a")
+        .bound("T2", "Clone");
+    imp.new_fn("len")
+        .arg_ref_self()
+        .ret("usize")
+        .line("self.len");
+
+    let expect = r#"
+impl<T> Container for Buffer<T> {
+    type Item = T;
+    type Iter<'a, T2> = std::slice::Iter<'a, T> where Self: 'a, T2: Clone;
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn blanket_impls() {
+    let mut scope = Scope::new();
+
+    let mut read_ext = Impl::blanket("R", "ReadExt", ["std::io::Read", "?Sized"]);
+    read_ext
+        .new_fn("read_u8")
+        .arg_mut_self()
+        .ret("std::io::Result<u8>")
+        .line("todo!()");
+    scope.push_impl(read_ext);
+    scope.push_impl(Impl::blanket("T", "Marker", Vec::<Type>::new()));
+
+    let expect = r#"
+impl<R: std::io::Read + ?Sized> ReadExt for R {
+    fn read_u8(&mut self) -> std::io::Result<u8> {
+        todo!()
+    }
+}
+
+impl<T> Marker for T {}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_with_lifetimes() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_impl("Scanner")
+        .generic("T")
+        .lifetime("b")
+        .generic("'a")
+        .target_generic("'b")
+        .target_generic("T")
+        .impl_trait("Parser<'a>")
+        .bound("'b", "'a")
+        .new_fn("input")
+        .arg_ref_self()
+        .ret("&'a str")
+        .line("self.rest");
+
+    let expect = r#"
+impl<'b, 'a, T> Parser<'a> for Scanner<'b, T>
+where 'b: 'a,
+{
+    fn input(&self) -> &'a str {
+        self.rest
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_with_sorted_items() {
+    let mut scope = Scope::new();
+
+    let imp = scope
+        .new_impl("Stack")
+        .impl_trait("Container")
+        .set_sorted(true)
+        .associate_type("Item", "u8")
+        .associate_type("Error", "Full");
+    imp.new_const("MIN", "usize", "0");
+    imp.new_const("MAX", "usize", "64");
+    imp.macro_item("forward_len!();");
+    imp.new_fn("push").arg_mut_self().arg("item", "u8");
+    imp.new_fn("len").arg_ref_self().ret("usize");
+    imp.new_fn("clear").arg_mut_self();
+
+    let expect = r#"
+impl Container for Stack {
+    const MAX: usize = 64;
+    const MIN: usize = 0;
+    type Error = Full;
+    type Item = u8;
+
+    fn clear(&mut self) {
+    }
+
+    fn len(&self) -> usize {
+    }
+
+    fn push(&mut self, item: u8) {
+    }
+
+    forward_len!();
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_with_macro_items() {
+    let mut scope = Scope::new();
+
+    let imp = scope.new_impl("Stack");
+    imp.macro_item(
+        r#"
+        delegate! {
+            to self.inner {
+                pub fn len(&self) -> usize;
+            }
+        }
+        "#,
+    );
+    imp.new_fn("top")
+        .arg_ref_self()
+        .ret("Option<&u8>")
+        .line("self.inner.last()");
+    imp.macro_item("impl_ops!(Stack);");
+
+    let expect = r#"
+impl Stack {
+    delegate! {
+        to self.inner {
+            pub fn len(&self) -> usize;
+        }
+    }
+
+    fn top(&self) -> Option<&u8> {
+        self.inner.last()
+    }
+
+    impl_ops!(Stack);
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn impl_with_docs_and_attributes() {
+    let mut scope = Scope::new();
+
+    scope
+        .new_impl("Config")
+        .impl_trait("serde::Serialize")
+        .doc("Serializes the config as a map.")
+        .cfg(Cfg::feature("serde"))
+        .attr("automatically_derived")
+        .attr("automatically_derived")
+        .r#macro("#[rustfmt::skip]")
+        .new_fn("serialize")
+        .arg_ref_self()
+        .line("todo!()");
+
+    let expect = r#"
+/// Serializes the config as a map.
+#[cfg(feature = "serde")]
+#[automatically_derived]
+#[rustfmt::skip]
+impl serde::Serialize for Config {
+    fn serialize(&self) {
+        todo!()
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn get_or_new_impl() {
+    let mut scope = Scope::new();
+    assert!(scope.get_impl("User", None).is_none());
+
+    scope
+        .get_or_new_impl("User", None)
+        .new_fn("id")
+        .arg_ref_self()
+        .ret("u64")
+        .line("self.id");
+    scope
+        .get_or_new_impl("User", Some("Clone"))
+        .new_fn("clone")
+        .arg_ref_self()
+        .ret("Self")
+        .line("todo!()");
+    scope
+        .get_or_new_impl("User", None)
+        .new_fn("name")
+        .arg_ref_self()
+        .ret("&str")
+        .line("&self.name");
+    assert!(scope.get_impl("User", Some("Clone")).is_some());
+    assert!(scope.get_impl("User", Some("Debug")).is_none());
+
+    let expect = r#"
+impl User {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Clone for User {
+    fn clone(&self) -> Self {
+        todo!()
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn trait_dyn_compatibility() {
+    let mut store = Trait::new("Store");
+    store.associated_const("VERSION", "u32");
+    store.new_fn("open").arg("path", "&str").ret("Self");
+    store
+        .new_fn("get")
+        .arg_ref_self()
+        .generic("K")
+        .arg("key", "K");
+    store
+        .new_fn("set")
+        .arg_mut_self()
+        .arg("value", "impl Into<String>");
+    store.new_fn("merge").arg_mut_self().arg("other", "&Self");
+    store.new_fn("flush").arg_mut_self().set_async(true);
+    store
+        .new_fn("keys")
+        .arg_ref_self()
+        .ret("impl Iterator<Item = &str>");
+    store
+        .new_fn("copy")
+        .arg_ref_self()
+        .ret("Self")
+        .bound("Self", "Sized");
+    store.new_fn("len").arg_ref_self().ret("usize");
+    store.new_fn("entry").arg_ref_self().ret("Self::Entry");
+
+    assert_eq!(
+        store.dyn_compatibility_issues(),
+        [
+            "`const VERSION` is an associated constant",
+            "`fn open` has no `self` receiver",
+            "`fn get` has generic parameters",
+            "`fn set` has generic parameters",
+            "`fn merge` takes or returns `Self`",
+            "`fn flush` is `async`",
+            "`fn keys` returns `impl Trait`",
+        ]
+    );
+
+    let mut cloned = Trait::new("Cloned");
+    cloned.parent("Sized");
+    assert_eq!(
+        cloned.dyn_compatibility_issues(),
+        ["the trait requires `Self: Sized`"]
+    );
+
+    let mut render = Trait::new("Render");
+    render.new_fn("render").arg_ref_self().ret("String");
+    assert!(render.dyn_compatibility_issues().is_empty());
+}
+
+#[test]
+fn trait_with_forwarding_impls() {
+    let mut scope = Scope::new();
+
+    let mut store = Trait::new("Store");
+    store.associated_type("Error");
+    store.associated_const("NAME", "&'static str");
+    store
+        .new_fn("get")
+        .arg_ref_self()
+        .arg("key", "&str")
+        .ret("Option<String>");
+    store.new_fn("default_key").ret("String");
+    store
+        .new_fn("try_get")
+        .arg_ref_self()
+        .ret("Result<String, Self::Error>");
+    scope.push_impls(store.generate_forwarding_impls());
+
+    let mut writer = Trait::new("Writer");
+    writer.generic("W");
+    writer.new_fn("write").arg_mut_self().arg("value", "W");
+    scope.push_impls(writer.generate_forwarding_impls());
+
+    // constructors can't be forwarded
+    let mut make = Trait::new("Make");
+    make.new_fn("make").ret("Self");
+    scope.push_impls(make.generate_forwarding_impls());
+
+    let expect = r#"
+impl<T: Store + ?Sized> Store for Box<T> {
+    const NAME: &'static str = T::NAME;
+    type Error = T::Error;
+
+    fn get(&self, key: &str) -> Option<String> {
+        (**self).get(key)
+    }
+
+    fn default_key() -> String {
+        T::default_key()
+    }
+
+    fn try_get(&self) -> Result<String, Self::Error> {
+        (**self).try_get()
+    }
+}
+
+impl<T: Store + ?Sized> Store for std::sync::Arc<T> {
+    const NAME: &'static str = T::NAME;
+    type Error = T::Error;
+
+    fn get(&self, key: &str) -> Option<String> {
+        (**self).get(key)
+    }
+
+    fn default_key() -> String {
+        T::default_key()
+    }
+
+    fn try_get(&self) -> Result<String, Self::Error> {
+        (**self).try_get()
+    }
+}
+
+impl<T: Store + ?Sized> Store for &T {
+    const NAME: &'static str = T::NAME;
+    type Error = T::Error;
+
+    fn get(&self, key: &str) -> Option<String> {
+        (**self).get(key)
+    }
+
+    fn default_key() -> String {
+        T::default_key()
+    }
+
+    fn try_get(&self) -> Result<String, Self::Error> {
+        (**self).try_get()
+    }
+}
+
+impl<W, T: Writer<W> + ?Sized> Writer<W> for Box<T> {
+    fn write(&mut self, value: W) {
+        (**self).write(value)
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+}
+
+#[test]
+fn newtype_impls() {
+    let mut scope = Scope::new();
+
+    let mut meters = Struct::new("Meters");
+    meters.tuple_field(None, "f64");
+    scope.push_impls(meters.generate_newtype_impls("0"));
+
+    let expect = r#"
+impl std::ops::Deref for Meters {
+    type Target = f64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Meters {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<f64> for Meters {
+    fn from(value: f64) -> Self {
+        Self(value)
+    }
+}
+
+impl AsRef<f64> for Meters {
+    fn as_ref(&self) -> &f64 {
+        &self.0
+    }
+}"#;
+
+    assert_eq!(scope.to_string(), &expect[1..]);
+
+    let mut scope = Scope::new();
+
+    let mut id = Struct::new("Id");
+    id.generic("T").field("raw", "u64").phantom("T");
+    scope.push_impls(id.generate_newtype_impls("raw"));
+
+    assert!(scope.to_string().contains(
+        r#"
+impl<T> From<u64> for Id<T> {
+    fn from(value: u64) -> Self {
+        Self { raw: value, _marker: std::marker::PhantomData }
+    }
+}"#
+    ));
+}